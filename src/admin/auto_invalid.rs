@@ -0,0 +1,84 @@
+use crate::db::schema::*;
+use diesel::prelude::*;
+use log::error;
+use std::collections::HashSet;
+
+pub const AUTO_INVALID_REASON: &str = "任务时间过短（自动）";
+
+// 返回任务时间低于阈值、且尚未被标记为无效的任务，按id排序
+fn select_short_mission(
+    mission_list: Vec<(i32, i16)>,
+    marked_mission_id_set: &HashSet<i32>,
+    threshold: i16,
+) -> Vec<i32> {
+    let mut result = mission_list
+        .into_iter()
+        .filter(|&(mission_id, mission_time)| {
+            mission_time < threshold && !marked_mission_id_set.contains(&mission_id)
+        })
+        .map(|(mission_id, _)| mission_id)
+        .collect::<Vec<_>>();
+
+    result.sort_unstable();
+    result
+}
+
+pub(crate) fn mark_short_mission(
+    db_conn: &mut PgConnection,
+    threshold: i16,
+) -> Result<Vec<i32>, ()> {
+    let mission_list = mission::table
+        .select((mission::id, mission::mission_time))
+        .load::<(i32, i16)>(db_conn)
+        .map_err(|e| error!("cannot get mission list: {}", e))?;
+
+    let marked_mission_id_set = mission_invalid::table
+        .select(mission_invalid::mission_id)
+        .load::<i32>(db_conn)
+        .map_err(|e| error!("cannot get invalid mission list: {}", e))?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let to_mark_mission_id_list =
+        select_short_mission(mission_list, &marked_mission_id_set, threshold);
+
+    // 全部写入或全部不写入，避免部分任务被标记
+    db_conn
+        .transaction(|conn| {
+            for &mission_id in &to_mark_mission_id_list {
+                diesel::insert_into(mission_invalid::table)
+                    .values((
+                        mission_invalid::mission_id.eq(mission_id),
+                        mission_invalid::reason.eq(AUTO_INVALID_REASON),
+                    ))
+                    .on_conflict(mission_invalid::mission_id)
+                    .do_nothing()
+                    .execute(conn)?;
+            }
+
+            QueryResult::Ok(())
+        })
+        .map_err(|e| error!("cannot insert into invalid mission: {}", e))?;
+
+    Ok(to_mark_mission_id_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unmarked_short_mission_is_selected() {
+        let mission_list = vec![(3, 100), (1, 299), (2, 300), (4, 50)];
+        let marked_mission_id_set = HashSet::from([4]);
+
+        assert_eq!(
+            select_short_mission(mission_list.clone(), &marked_mission_id_set, 300),
+            vec![1, 3]
+        );
+        assert_eq!(
+            select_short_mission(mission_list, &marked_mission_id_set, 120),
+            vec![3]
+        );
+    }
+}
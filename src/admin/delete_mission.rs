@@ -2,12 +2,19 @@ use crate::db::schema::*;
 use diesel::prelude::*;
 use log::{error, info};
 
-pub fn delete_mission(db_conn: &mut PgConnection, mission_id: i32) -> Result<(), ()> {
+// 返回任务是否存在并被删除；各表的删除在同一事务中，任一步失败时任务保持完整
+pub fn delete_mission(db_conn: &mut PgConnection, mission_id: i32) -> Result<bool, ()> {
     info!("deleting mission {}", mission_id);
 
+    db_conn
+        .transaction(|db_conn| delete_mission_rows(db_conn, mission_id))
+        .map_err(|_| ())
+}
+
+fn delete_mission_rows(db_conn: &mut PgConnection, mission_id: i32) -> QueryResult<bool> {
     diesel::delete(damage_info::table.filter(damage_info::mission_id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!(
                 "cannot delete damage_info for mission {}: {}",
                 mission_id, e
@@ -16,13 +23,13 @@ pub fn delete_mission(db_conn: &mut PgConnection, mission_id: i32) -> Result<(),
 
     diesel::delete(kill_info::table.filter(kill_info::mission_id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!("cannot delete kill_info for mission {}: {}", mission_id, e);
         })?;
 
     diesel::delete(resource_info::table.filter(resource_info::mission_id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!(
                 "cannot delete resource_info for mission {}: {}",
                 mission_id, e
@@ -31,7 +38,7 @@ pub fn delete_mission(db_conn: &mut PgConnection, mission_id: i32) -> Result<(),
 
     diesel::delete(supply_info::table.filter(supply_info::mission_id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!(
                 "cannot delete supply_info for mission {}: {}",
                 mission_id, e
@@ -39,17 +46,77 @@ pub fn delete_mission(db_conn: &mut PgConnection, mission_id: i32) -> Result<(),
         })?;
     diesel::delete(player_info::table.filter(player_info::mission_id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!(
                 "cannot delete player_info for mission {}: {}",
                 mission_id, e
             );
         })?;
-    diesel::delete(mission::table.filter(mission::id.eq(mission_id)))
+    diesel::delete(mission_invalid::table.filter(mission_invalid::mission_id.eq(mission_id)))
+        .execute(db_conn)
+        .inspect_err(|e| {
+            error!(
+                "cannot delete mission_invalid for mission {}: {}",
+                mission_id, e
+            );
+        })?;
+    let deleted_count = diesel::delete(mission::table.filter(mission::id.eq(mission_id)))
         .execute(db_conn)
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!("cannot delete mission {}: {}", mission_id, e);
         })?;
 
-    Ok(())
+    Ok(deleted_count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::establish_connection;
+    use crate::db::mission::NewMission;
+
+    // 需要已完成迁移的数据库（DATABASE_URL），测试中的修改均会回滚
+    #[test]
+    #[ignore = "requires DATABASE_URL"]
+    fn invalid_mission_is_deleted_with_mark() {
+        let mut conn = establish_connection();
+
+        conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+            let mission_type_id = diesel::insert_into(mission_type::table)
+                .values(mission_type::mission_type_game_id.eq("MD_TestMission"))
+                .returning(mission_type::id)
+                .get_result::<i16>(conn)?;
+
+            let mission_id = diesel::insert_into(mission::table)
+                .values(&NewMission {
+                    begin_timestamp: 0,
+                    mission_time: 60,
+                    mission_type_id,
+                    hazard_id: 5,
+                    result: 0,
+                    reward_credit: 0.0,
+                    total_supply_count: 0,
+                })
+                .returning(mission::id)
+                .get_result::<i32>(conn)?;
+
+            diesel::insert_into(mission_invalid::table)
+                .values((
+                    mission_invalid::mission_id.eq(mission_id),
+                    mission_invalid::reason.eq("test"),
+                ))
+                .execute(conn)?;
+
+            assert_eq!(delete_mission(conn, mission_id), Ok(true));
+
+            let invalid_count = mission_invalid::table
+                .filter(mission_invalid::mission_id.eq(mission_id))
+                .count()
+                .get_result::<i64>(conn)?;
+            assert_eq!(invalid_count, 0);
+            assert_eq!(delete_mission(conn, mission_id), Ok(false));
+
+            Ok(())
+        });
+    }
 }
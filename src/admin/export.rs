@@ -0,0 +1,242 @@
+use crate::cache::mission::IDMapping;
+use crate::db::mission_log::*;
+use crate::db::models::*;
+use crate::db::schema::*;
+use diesel::prelude::*;
+use log::error;
+use std::collections::HashMap;
+
+// 将数据库中的任务还原为load_mission所接受的LogContent，用于备份
+// 数据库中未保存玩家加入、离开任务的时间，还原时以0与在场时间代替，不影响再次加载
+pub fn export_mission_log(db_conn: &mut PgConnection) -> Result<Vec<LogContent>, ()> {
+    let id_mapping = IDMapping::load_from_db(db_conn)?;
+
+    let id_to_character_game_id = match character::table.load::<Character>(db_conn) {
+        Ok(x) => x
+            .into_iter()
+            .map(|character| (character.id, character.character_game_id))
+            .collect::<HashMap<_, _>>(),
+        Err(e) => {
+            error!("cannot load character from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let id_to_mission_type_game_id = match mission_type::table
+        .select((mission_type::id, mission_type::mission_type_game_id))
+        .load::<(i16, String)>(db_conn)
+    {
+        Ok(x) => x.into_iter().collect::<HashMap<_, _>>(),
+        Err(e) => {
+            error!("cannot load mission type from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let all_mission_info = match mission::table
+        .select(Mission::as_select())
+        .order(mission::begin_timestamp)
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot load missions from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let all_player_info: Vec<PlayerInfo> =
+        match PlayerInfo::belonging_to(&all_mission_info).load(db_conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot load player info from db: {}", e);
+                return Err(());
+            }
+        };
+
+    let all_damage_info: Vec<DamageInfo> = match DamageInfo::belonging_to(&all_mission_info)
+        .order(damage_info::id)
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot load damage info from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let all_kill_info: Vec<KillInfo> = match KillInfo::belonging_to(&all_mission_info)
+        .order(kill_info::id)
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot load kill info from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let all_resource_info: Vec<ResourceInfo> = match ResourceInfo::belonging_to(&all_mission_info)
+        .order(resource_info::id)
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot load resource info from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let all_supply_info: Vec<SupplyInfo> = match SupplyInfo::belonging_to(&all_mission_info)
+        .order(supply_info::id)
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot load supply info from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let player_info_by_mission = all_player_info.grouped_by(&all_mission_info);
+    let damage_info_by_mission = all_damage_info.grouped_by(&all_mission_info);
+    let kill_info_by_mission = all_kill_info.grouped_by(&all_mission_info);
+    let resource_info_by_mission = all_resource_info.grouped_by(&all_mission_info);
+    let supply_info_by_mission = all_supply_info.grouped_by(&all_mission_info);
+
+    let player_name = |player_id: &i16| -> Result<String, ()> {
+        match id_mapping.id_to_player_name.get(player_id) {
+            Some(x) => Ok(x.clone()),
+            None => {
+                error!("unknown player id {} while exporting missions", player_id);
+                Err(())
+            }
+        }
+    };
+
+    let game_id = |map: &HashMap<i16, String>, id: &i16, what: &str| -> Result<String, ()> {
+        match map.get(id) {
+            Some(x) => Ok(x.clone()),
+            None => {
+                error!("unknown {} id {} while exporting missions", what, id);
+                Err(())
+            }
+        }
+    };
+
+    let mut result = Vec::with_capacity(all_mission_info.len());
+
+    for (
+        ((((mission, player_info_list), damage_info_list), kill_info_list), resource_info_list),
+        supply_info_list,
+    ) in all_mission_info
+        .into_iter()
+        .zip(player_info_by_mission)
+        .zip(damage_info_by_mission)
+        .zip(kill_info_by_mission)
+        .zip(resource_info_by_mission)
+        .zip(supply_info_by_mission)
+    {
+        let mission_info = LogMissionInfo {
+            begin_timestamp: mission.begin_timestamp,
+            mission_time: mission.mission_time,
+            mission_type_id: game_id(
+                &id_to_mission_type_game_id,
+                &mission.mission_type_id,
+                "mission type",
+            )?,
+            hazard_id: mission.hazard_id,
+            result: mission.result,
+            reward_credit: mission.reward_credit,
+            total_supply_count: mission.total_supply_count,
+        };
+
+        let mut player_info = Vec::with_capacity(player_info_list.len());
+        for item in player_info_list {
+            player_info.push(LogPlayerInfo {
+                player_name: player_name(&item.player_id)?,
+                character: game_id(&id_to_character_game_id, &item.character_id, "character")?,
+                player_rank: item.player_rank,
+                character_rank: item.character_rank,
+                character_promotion: item.character_promotion,
+                join_mission_time: 0,
+                left_mission_time: item.present_time,
+                total_present_time: item.present_time,
+                kill_num: item.kill_num,
+                revive_num: item.revive_num,
+                death_num: item.death_num,
+                gold_mined: item.gold_mined,
+                minerals_mined: item.minerals_mined,
+                player_escaped: item.player_escaped,
+            });
+        }
+
+        // causer_type/taker_type为1时id对应玩家，否则对应实体
+        let mut damage_info = Vec::with_capacity(damage_info_list.len());
+        for item in damage_info_list {
+            damage_info.push(LogDamageInfo {
+                mission_time: item.time,
+                damage: item.damage,
+                causer: match item.causer_type {
+                    1 => player_name(&item.causer_id)?,
+                    _ => game_id(&id_mapping.id_to_entity_game_id, &item.causer_id, "entity")?,
+                },
+                taker: match item.taker_type {
+                    1 => player_name(&item.taker_id)?,
+                    _ => game_id(&id_mapping.id_to_entity_game_id, &item.taker_id, "entity")?,
+                },
+                weapon: game_id(&id_mapping.id_to_weapon_game_id, &item.weapon_id, "weapon")?,
+                causer_type: item.causer_type,
+                taker_type: item.taker_type,
+            });
+        }
+
+        let mut kill_info = Vec::with_capacity(kill_info_list.len());
+        for item in kill_info_list {
+            kill_info.push(LogKillInfo {
+                mission_time: item.time,
+                player_name: player_name(&item.player_id)?,
+                killed_entity: game_id(
+                    &id_mapping.id_to_entity_game_id,
+                    &item.entity_id,
+                    "entity",
+                )?,
+            });
+        }
+
+        let mut resource_info = Vec::with_capacity(resource_info_list.len());
+        for item in resource_info_list {
+            resource_info.push(LogResourceInfo {
+                mission_time: item.time,
+                player_name: player_name(&item.player_id)?,
+                resource: game_id(
+                    &id_mapping.id_to_resource_game_id,
+                    &item.resource_id,
+                    "resource",
+                )?,
+                amount: item.amount,
+            });
+        }
+
+        let mut supply_info = Vec::with_capacity(supply_info_list.len());
+        for item in supply_info_list {
+            supply_info.push(LogSupplyInfo {
+                mission_time: item.time,
+                player_name: player_name(&item.player_id)?,
+                ammo: item.ammo,
+                health: item.health,
+            });
+        }
+
+        result.push(LogContent {
+            mission_info,
+            player_info,
+            damage_info,
+            kill_info,
+            resource_info,
+            supply_info,
+        });
+    }
+
+    Ok(result)
+}
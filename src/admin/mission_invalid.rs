@@ -0,0 +1,180 @@
+use crate::db::schema::*;
+use diesel::prelude::*;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct APISetMissionInvalid {
+    #[serde(rename = "missionId")]
+    pub mission_id: i32,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SetMissionInvalidResult {
+    #[serde(rename = "missionId")]
+    pub mission_id: i32,
+    pub success: bool,
+    // 失败原因，成功时为""
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MissionInvalidDetail {
+    #[serde(rename = "missionId")]
+    pub mission_id: i32,
+    #[serde(rename = "beginTimestamp")]
+    pub begin_timestamp: i64,
+    // mission_type_game_id
+    #[serde(rename = "missionType")]
+    pub mission_type: String,
+    #[serde(rename = "hazardId")]
+    pub hazard_id: i16,
+    pub reason: String,
+}
+
+// 无效任务及其基本信息，最新的任务在前
+pub(crate) fn load_mission_invalid_detailed(
+    db_conn: &mut PgConnection,
+) -> Result<Vec<MissionInvalidDetail>, ()> {
+    let result = mission_invalid::table
+        .inner_join(mission::table.inner_join(mission_type::table))
+        .select((
+            mission::id,
+            mission::begin_timestamp,
+            mission_type::mission_type_game_id,
+            mission::hazard_id,
+            mission_invalid::reason,
+        ))
+        .order((mission::begin_timestamp.desc(), mission::id.desc()))
+        .load::<(i32, i64, String, i16, String)>(db_conn)
+        .map_err(|e| error!("cannot get invalid mission list: {}", e))?;
+
+    Ok(result
+        .into_iter()
+        .map(
+            |(mission_id, begin_timestamp, mission_type, hazard_id, reason)| MissionInvalidDetail {
+                mission_id,
+                begin_timestamp,
+                mission_type,
+                hazard_id,
+                reason,
+            },
+        )
+        .collect())
+}
+
+// 返回每项的校验错误，None表示可以写入
+fn validate_request(
+    request_list: &[APISetMissionInvalid],
+    existing_mission_id_set: &HashSet<i32>,
+) -> Vec<Option<String>> {
+    request_list
+        .iter()
+        .map(|request| {
+            if !existing_mission_id_set.contains(&request.mission_id) {
+                Some("mission not found".to_string())
+            } else if request.reason.trim().is_empty() {
+                Some("reason is empty".to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// atomic为true时，只要有一项校验失败则全部不写入
+pub(crate) fn set_mission_invalid_bulk(
+    db_conn: &mut PgConnection,
+    request_list: Vec<APISetMissionInvalid>,
+    atomic: bool,
+) -> Result<Vec<SetMissionInvalidResult>, ()> {
+    let existing_mission_id_set = mission::table
+        .select(mission::id)
+        .filter(mission::id.eq_any(request_list.iter().map(|request| request.mission_id)))
+        .load::<i32>(db_conn)
+        .map_err(|e| error!("cannot get mission list: {}", e))?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let validate_result = validate_request(&request_list, &existing_mission_id_set);
+
+    let has_error = validate_result.iter().any(|x| x.is_some());
+
+    let to_write_list = request_list
+        .iter()
+        .zip(&validate_result)
+        .filter(|(_, error)| error.is_none() && !(atomic && has_error))
+        .map(|(request, _)| request)
+        .collect::<Vec<_>>();
+
+    db_conn
+        .transaction(|conn| {
+            for request in &to_write_list {
+                diesel::insert_into(mission_invalid::table)
+                    .values((
+                        mission_invalid::mission_id.eq(request.mission_id),
+                        mission_invalid::reason.eq(&request.reason),
+                    ))
+                    .on_conflict(mission_invalid::mission_id)
+                    .do_update()
+                    .set(mission_invalid::reason.eq(&request.reason))
+                    .execute(conn)?;
+            }
+
+            QueryResult::Ok(())
+        })
+        .map_err(|e| error!("cannot insert into invalid mission: {}", e))?;
+
+    Ok(request_list
+        .into_iter()
+        .zip(validate_result)
+        .map(|(request, error)| {
+            let (success, message) = match error {
+                Some(message) => (false, message),
+                None if atomic && has_error => (false, "not applied: atomic".to_string()),
+                None => (true, String::new()),
+            };
+
+            SetMissionInvalidResult {
+                mission_id: request.mission_id,
+                success,
+                message,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonexistent_mission_and_empty_reason_are_rejected() {
+        let request_list = vec![
+            APISetMissionInvalid {
+                mission_id: 1,
+                reason: "存档损坏".to_string(),
+            },
+            APISetMissionInvalid {
+                mission_id: 2,
+                reason: "存档损坏".to_string(),
+            },
+            APISetMissionInvalid {
+                mission_id: 3,
+                reason: " ".to_string(),
+            },
+        ];
+        let existing_mission_id_set = HashSet::from([1, 3]);
+
+        assert_eq!(
+            validate_request(&request_list, &existing_mission_id_set),
+            vec![
+                None,
+                Some("mission not found".to_string()),
+                Some("reason is empty".to_string())
+            ]
+        );
+    }
+}
@@ -1,15 +1,29 @@
+pub mod auto_invalid;
 pub mod delete_mission;
+pub mod export;
+pub mod mission_invalid;
+pub mod scout_special;
+pub mod unmapped;
 
-use crate::kpi::KPIConfig;
-use crate::{db::schema::player, APIResponse, AppState, DbPool, Mapping};
+use crate::cache::{schedule_cache_update, schedule_global_kpi_state_update, CacheUpdateStatus};
+use crate::kpi::{KPIConfig, KPIConfigDiff};
+use crate::{
+    api_parse_json_body, constant_time_eq, db::schema::player, APIResponse, AppState, DbPool,
+    LoginAttempt, Mapping, INVALID_MISSION_TIME_THRESHOLD, SESSION_COOKIE_NAME,
+};
 use actix_web::{
-    post,
-    web::{self, Buf, Bytes, Data, Json},
-    HttpRequest,
+    cookie::Cookie,
+    get, post,
+    web::{self, Bytes, Data, Json},
+    HttpRequest, HttpResponse,
 };
 use diesel::prelude::*;
 use diesel::{insert_into, update};
 use log::{error, warn};
+use mission_invalid::{APISetMissionInvalid, MissionInvalidDetail, SetMissionInvalidResult};
+use scout_special::ScoutSpecialPreview;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 
 #[derive(Insertable)]
@@ -19,29 +33,138 @@ struct NewPlayer {
     pub friend: bool,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct LoadMappingResult {
+    #[serde(rename = "cacheUpdate")]
+    pub cache_update: CacheUpdateStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WatchlistDiff {
+    // 新加入关注列表的玩家
+    pub added: Vec<String>,
+    // 从关注列表中移除的玩家
+    pub removed: Vec<String>,
+    // 仍在关注列表中的玩家
+    pub unchanged: Vec<String>,
+}
+
+// 数据库中存在、但当前mapping中没有对应名称的game_id，按类别分组
+#[derive(Serialize, Deserialize)]
+pub struct UnmappedGameId {
+    pub entity: Vec<String>,
+    pub weapon: Vec<String>,
+    pub resource: Vec<String>,
+    pub character: Vec<String>,
+    #[serde(rename = "missionType")]
+    pub mission_type: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoadWatchlistQuery {
+    // 为true时仅返回变更，不修改数据库
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SetMissionInvalidBulkQuery {
+    // 为true时，只要有一项校验失败则全部不写入
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+#[derive(Deserialize)]
+pub struct AutoInvalidQuery {
+    // 任务时间低于此值（秒）的任务将被标记为无效，未设置时使用INVALID_MISSION_TIME_THRESHOLD
+    #[serde(default)]
+    pub threshold: Option<i16>,
+}
+
+// 使用access_token换取session，其余管理接口均通过session验证
+#[post("/login")]
+async fn login(requests: HttpRequest, app_state: Data<AppState>) -> HttpResponse {
+    if let Some(access_token) = app_state.access_token.clone() {
+        let token_valid = match requests.cookie("access_token") {
+            Some(provieded_access_token) => constant_time_eq(
+                provieded_access_token.value().as_bytes(),
+                access_token.as_bytes(),
+            ),
+            None => false,
+        };
+
+        // 按连接的对端地址计数，位于反向代理之后时为代理的地址
+        match requests.peer_addr().map(|addr| addr.ip()) {
+            Some(ip) => match app_state.check_login_attempt(ip, token_valid) {
+                LoginAttempt::Accepted => (),
+                LoginAttempt::Rejected => {
+                    return HttpResponse::Ok().json(APIResponse::<()>::unauthorized());
+                }
+                LoginAttempt::Locked => {
+                    warn!("too many failed login attempts from {}", ip);
+                    return HttpResponse::Ok().json(APIResponse::<()>::unauthorized());
+                }
+            },
+            None => {
+                if !token_valid {
+                    return HttpResponse::Ok().json(APIResponse::<()>::unauthorized());
+                }
+            }
+        }
+    }
+
+    let session_id = app_state.new_session();
+
+    HttpResponse::Ok()
+        .cookie(
+            Cookie::build(SESSION_COOKIE_NAME, session_id)
+                .path("/")
+                .http_only(true)
+                .finish(),
+        )
+        .json(APIResponse::ok(()))
+}
+
+#[post("/logout")]
+async fn logout(requests: HttpRequest, app_state: Data<AppState>) -> HttpResponse {
+    app_state.remove_session(&requests);
+
+    let mut removal = Cookie::build(SESSION_COOKIE_NAME, "").path("/").finish();
+    removal.make_removal();
+
+    HttpResponse::Ok().cookie(removal).json(APIResponse::ok(()))
+}
+
+// 当前请求携带的session是否有效，供客户端检查登录状态
+#[get("/check_session")]
+async fn get_check_session(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+) -> Json<APIResponse<()>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    Json(APIResponse::ok(()))
+}
+
 #[post("/load_mapping")]
 async fn load_mapping(
     requests: HttpRequest,
     app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
     body: Bytes,
-) -> Json<APIResponse<()>> {
-    if let Some(access_token) = app_state.access_token.clone() {
-        if let Some(provieded_access_token) = requests.cookie("access_token") {
-            if provieded_access_token.value() != access_token {
-                return Json(APIResponse::unauthorized());
-            }
-        } else {
-            return Json(APIResponse::unauthorized());
-        }
+) -> Json<APIResponse<LoadMappingResult>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
     }
 
-    let mapping: Mapping = match serde_json::from_reader(body.reader()) {
+    let mapping: Mapping = match api_parse_json_body(&body) {
         Ok(x) => x,
         Err(e) => {
-            warn!("cannot parse payload body as json: {}", e);
-            return Json(APIResponse::bad_request(
-                "cannot parse payload body as json",
-            ));
+            warn!("cannot parse mapping: {}", e);
+            return Json(APIResponse::bad_request(&e));
         }
     };
 
@@ -57,9 +180,12 @@ async fn load_mapping(
             return Json(APIResponse::internal_error());
         }
         Ok(()) => {
-            let mut state_mapping = app_state.mapping.lock().unwrap();
-            *state_mapping = mapping;
-            return Json(APIResponse::ok(()));
+            *app_state.mapping.lock().unwrap() = mapping;
+
+            // mapping变化后缓存中的聚合数据需要重新生成
+            let cache_update = schedule_cache_update(app_state, db_pool, redis_client);
+
+            return Json(APIResponse::ok(LoadMappingResult { cache_update }));
         }
     }
 }
@@ -69,35 +195,23 @@ async fn load_watchlist(
     requests: HttpRequest,
     app_state: Data<AppState>,
     db_pool: Data<DbPool>,
+    query: web::Query<LoadWatchlistQuery>,
     body: Bytes,
-) -> Json<APIResponse<()>> {
-    if let Some(access_token) = app_state.access_token.clone() {
-        if let Some(provieded_access_token) = requests.cookie("access_token") {
-            if provieded_access_token.value() != access_token {
-                return Json(APIResponse::unauthorized());
-            }
-        } else {
-            return Json(APIResponse::unauthorized());
-        }
+) -> Json<APIResponse<WatchlistDiff>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
     }
 
-    let watchlist: Vec<String> = match serde_json::from_reader(body.reader()) {
+    let watchlist: Vec<String> = match api_parse_json_body(&body) {
         Ok(x) => x,
         Err(e) => {
-            return Json(APIResponse::bad_request(&format!(
-                "cannot parse payload as json: {}",
-                e
-            )));
+            warn!("cannot parse watchlist: {}", e);
+            return Json(APIResponse::bad_request(&e));
         }
     };
 
-    let watchlist = watchlist
-        .into_iter()
-        .map(|player_name| NewPlayer {
-            player_name,
-            friend: true,
-        })
-        .collect::<Vec<_>>();
+    let watchlist_set = watchlist.into_iter().collect::<HashSet<_>>();
+    let dry_run = query.dry_run;
 
     let result = web::block(move || {
         let mut conn = match db_pool.get() {
@@ -108,68 +222,112 @@ async fn load_watchlist(
             }
         };
 
-        match update(player::table)
-            .set(player::friend.eq(false))
-            .execute(&mut conn)
+        let prev_watchlist_set = match player::table
+            .select(player::player_name)
+            .filter(player::friend.eq(true))
+            .load::<String>(&mut conn)
         {
-            Ok(_) => {}
+            Ok(x) => x.into_iter().collect::<HashSet<_>>(),
             Err(e) => {
-                error!("cannot update db: {}", e);
+                error!("cannot get watchlist from db: {}", e);
                 return Err(());
             }
         };
 
-        match insert_into(player::table)
-            .values(&watchlist)
-            .on_conflict(player::player_name)
-            .do_update()
-            .set(player::friend.eq(true))
-            .execute(&mut conn)
-        {
-            Ok(_) => {}
-            Err(e) => {
-                error!("cannot update db: {}", e);
-                return Err(());
-            }
+        let mut added = watchlist_set
+            .difference(&prev_watchlist_set)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut removed = prev_watchlist_set
+            .difference(&watchlist_set)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut unchanged = watchlist_set
+            .intersection(&prev_watchlist_set)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        added.sort_unstable();
+        removed.sort_unstable();
+        unchanged.sort_unstable();
+
+        let diff = WatchlistDiff {
+            added,
+            removed,
+            unchanged,
         };
 
-        Ok(())
+        if dry_run {
+            return Ok(diff);
+        }
+
+        let watchlist = watchlist_set
+            .into_iter()
+            .map(|player_name| NewPlayer {
+                player_name,
+                friend: true,
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(e) = replace_watchlist(&mut conn, &watchlist) {
+            error!("cannot update db: {}", e);
+            return Err(());
+        }
+
+        Ok(diff)
     })
     .await
     .unwrap();
 
     match result {
-        Ok(()) => Json(APIResponse::ok(())),
+        Ok(x) => Json(APIResponse::ok(x)),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
 
+// 取消原有关注与写入新的关注列表在同一事务中，失败时关注列表保持不变
+fn replace_watchlist(conn: &mut PgConnection, watchlist: &[NewPlayer]) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        update(player::table)
+            .set(player::friend.eq(false))
+            .execute(conn)?;
+
+        insert_into(player::table)
+            .values(watchlist)
+            .on_conflict(player::player_name)
+            .do_update()
+            .set(player::friend.eq(true))
+            .execute(conn)?;
+
+        Ok(())
+    })
+}
+
 #[post("/load_kpi")]
 async fn load_kpi(
     requests: HttpRequest,
     app_state: Data<AppState>,
     body: Bytes,
 ) -> Json<APIResponse<()>> {
-    if let Some(access_token) = app_state.access_token.clone() {
-        if let Some(provieded_access_token) = requests.cookie("access_token") {
-            if provieded_access_token.value() != access_token {
-                return Json(APIResponse::unauthorized());
-            }
-        } else {
-            return Json(APIResponse::unauthorized());
-        }
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
     }
 
-    let kpi_config: KPIConfig = match serde_json::from_reader(body.reader()) {
+    let kpi_config: KPIConfig = match api_parse_json_body(&body) {
         Ok(x) => x,
         Err(e) => {
-            warn!("cannot parse payload body as json: {}", e);
-            return Json(APIResponse::bad_request(
-                "cannot parse payload body as json",
-            ));
+            warn!("cannot parse kpi config: {}", e);
+            return Json(APIResponse::bad_request(&e));
         }
     };
 
+    if let Err(problem_list) = kpi_config.validate() {
+        let message = problem_list.join("; ");
+        warn!("invalid kpi config: {}", message);
+        return Json(APIResponse::bad_request(&message));
+    }
+
     let write_path = app_state.instance_path.as_path().join("./kpi_config.json");
 
     match fs::write(&write_path, serde_json::to_vec(&kpi_config).unwrap()) {
@@ -195,27 +353,204 @@ async fn api_delete_mission(
     app_state: Data<AppState>,
     db_pool: Data<DbPool>,
     body: Bytes,
-) -> Json<APIResponse<()>> {
-    if let Some(access_token) = app_state.access_token.clone() {
-        if let Some(provieded_access_token) = requests.cookie("access_token") {
-            if provieded_access_token.value() != access_token {
-                return Json(APIResponse::unauthorized());
+) -> Json<APIResponse<Vec<i32>>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let to_delete_mission_list: Vec<i32> = match api_parse_json_body(&body) {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("cannot parse mission list to delete: {}", e);
+            return Json(APIResponse::bad_request(&e));
+        }
+    };
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        // 仅返回实际存在并被删除的任务
+        let mut deleted_mission_list = Vec::new();
+
+        for mission_id in to_delete_mission_list {
+            if delete_mission::delete_mission(&mut conn, mission_id)? {
+                deleted_mission_list.push(mission_id);
             }
-        } else {
-            return Json(APIResponse::unauthorized());
         }
+
+        Ok(deleted_mission_list)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[get("/export_mapping")]
+async fn export_mapping(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+) -> Json<APIResponse<Mapping>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let mapping = app_state.mapping.lock().unwrap().clone();
+
+    Json(APIResponse::ok(mapping))
+}
+
+#[get("/unmapped")]
+async fn get_unmapped(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+) -> Json<APIResponse<UnmappedGameId>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let mapping = app_state.mapping.lock().unwrap().clone();
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        unmapped::find_unmapped_game_id(&mut conn, &mapping)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 将任务时间过短、且尚未标记的任务标记为无效，返回新标记的任务id
+// 有新标记的任务时在后台更新GlobalKPIState
+#[post("/auto_invalid")]
+async fn api_auto_invalid(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<AutoInvalidQuery>,
+) -> Json<APIResponse<Vec<i32>>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let threshold = query.threshold.unwrap_or(INVALID_MISSION_TIME_THRESHOLD);
+
+    if threshold <= 0 {
+        return Json(APIResponse::bad_request("threshold must be positive"));
     }
 
-    let to_delete_mission_list: Vec<i32> = match serde_json::from_reader(body.reader()) {
+    let block_db_pool = db_pool.clone();
+
+    let result = web::block(move || {
+        let mut conn = match block_db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        auto_invalid::mark_short_mission(&mut conn, threshold)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => {
+            if !x.is_empty() {
+                schedule_global_kpi_state_update(app_state, db_pool, redis_client);
+            }
+            Json(APIResponse::ok(x))
+        }
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 批量将任务标记为无效（已标记的任务更新原因），返回每项的结果
+// 有写入成功的项时在后台更新GlobalKPIState
+#[post("/set_mission_invalid_bulk")]
+async fn set_mission_invalid_bulk(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<SetMissionInvalidBulkQuery>,
+    body: Bytes,
+) -> Json<APIResponse<Vec<SetMissionInvalidResult>>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let request_list: Vec<APISetMissionInvalid> = match api_parse_json_body(&body) {
         Ok(x) => x,
         Err(e) => {
-            warn!("cannot parse payload body as json: {}", e);
-            return Json(APIResponse::bad_request(
-                "cannot parse payload body as json",
-            ));
+            warn!("cannot parse mission invalid list: {}", e);
+            return Json(APIResponse::bad_request(&e));
         }
     };
 
+    let atomic = query.atomic;
+
+    let block_db_pool = db_pool.clone();
+
+    let result = web::block(move || {
+        let mut conn = match block_db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        mission_invalid::set_mission_invalid_bulk(&mut conn, request_list, atomic)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => {
+            if x.iter().any(|item| item.success) {
+                schedule_global_kpi_state_update(app_state, db_pool, redis_client);
+            }
+            Json(APIResponse::ok(x))
+        }
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 在服务端关联无效任务与任务信息，避免客户端分别获取后再合并
+#[get("/mission_invalid_detailed")]
+async fn get_mission_invalid_detailed(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+) -> Json<APIResponse<Vec<MissionInvalidDetail>>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
     let result = web::block(move || {
         let mut conn = match db_pool.get() {
             Ok(x) => x,
@@ -225,24 +560,277 @@ async fn api_delete_mission(
             }
         };
 
-        for mission_id in to_delete_mission_list {
-            delete_mission::delete_mission(&mut conn, mission_id)?;
+        mission_invalid::load_mission_invalid_detailed(&mut conn)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 按当前mapping中的scout_special_player_set预览各侦察玩家的KPI类型，便于在重新计算全局KPI前检查scout_special.txt
+#[get("/scout_special_preview")]
+async fn get_scout_special_preview(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+) -> Json<APIResponse<ScoutSpecialPreview>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let (scout_special_player_set, player_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.scout_special_player_set.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        scout_special::preview_scout_special(&mut conn, &scout_special_player_set, &player_mapping)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[get("/export_kpi")]
+async fn export_kpi(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+) -> Json<APIResponse<KPIConfig>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let kpi_config = app_state.kpi_config.lock().unwrap().clone();
+
+    match kpi_config {
+        Some(x) => Json(APIResponse::ok(x)),
+        None => Json(APIResponse::config_required("kpi_config")),
+    }
+}
+
+// 比较实例目录中的kpi_config.json与当前生效的KPI配置
+#[get("/kpi_config_diff")]
+async fn kpi_config_diff(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+) -> Json<APIResponse<KPIConfigDiff>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let active_kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
+        Some(x) => x,
+        None => return Json(APIResponse::config_required("kpi_config")),
+    };
+
+    let staged_path = app_state.instance_path.as_path().join("./kpi_config.json");
+
+    let staged_kpi_config: KPIConfig = match fs::read(&staged_path) {
+        Ok(content) => match serde_json::from_slice(&content) {
+            Ok(x) => x,
+            Err(e) => {
+                error!(
+                    "cannot parse kpi config {}: {}",
+                    staged_path.to_string_lossy(),
+                    e
+                );
+                return Json(APIResponse::internal_error());
+            }
+        },
+        Err(e) => {
+            error!(
+                "cannot read kpi config {}: {}",
+                staged_path.to_string_lossy(),
+                e
+            );
+            return Json(APIResponse::internal_error());
         }
+    };
 
-        Ok(())
+    Json(APIResponse::ok(active_kpi_config.diff(&staged_kpi_config)))
+}
+
+#[get("/export_watchlist")]
+async fn export_watchlist(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+) -> Json<APIResponse<Vec<String>>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        match player::table
+            .select(player::player_name)
+            .filter(player::friend.eq(true))
+            .order(player::player_name)
+            .load::<String>(&mut conn)
+        {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                error!("cannot get watchlist from db: {}", e);
+                Err(())
+            }
+        }
     })
     .await
     .unwrap();
 
     match result {
-        Ok(()) => Json(APIResponse::ok(())),
+        Ok(x) => Json(APIResponse::ok(x)),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
 
+// 返回与load_mission请求体相同格式（zstd压缩的msgpack）的全部任务数据，失败时返回JSON格式的APIResponse
+#[get("/export_mission")]
+async fn export_mission(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+) -> HttpResponse {
+    if !app_state.check_session(&requests) {
+        return HttpResponse::Ok().json(APIResponse::<()>::unauthorized());
+    }
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mission_list = export::export_mission_log(&mut conn)?;
+
+        let serialized = match rmp_serde::to_vec(&mission_list) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot serialize exported missions: {}", e);
+                return Err(());
+            }
+        };
+
+        match zstd::encode_all(&serialized[..], 3) {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                error!("cannot compress exported missions: {}", e);
+                Err(())
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(x),
+        Err(()) => HttpResponse::Ok().json(APIResponse::<()>::internal_error()),
+    }
+}
+
 pub fn scoped_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(login);
+    cfg.service(logout);
+    cfg.service(get_check_session);
     cfg.service(load_mapping);
     cfg.service(load_watchlist);
     cfg.service(load_kpi);
     cfg.service(api_delete_mission);
+    cfg.service(export_mapping);
+    cfg.service(get_unmapped);
+    cfg.service(api_auto_invalid);
+    cfg.service(set_mission_invalid_bulk);
+    cfg.service(get_mission_invalid_detailed);
+    cfg.service(get_scout_special_preview);
+    cfg.service(export_kpi);
+    cfg.service(kpi_config_diff);
+    cfg.service(export_watchlist);
+    cfg.service(export_mission);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::establish_connection;
+
+    fn watchlist_state(conn: &mut PgConnection) -> Vec<(String, bool)> {
+        player::table
+            .select((player::player_name, player::friend))
+            .order(player::player_name)
+            .load(conn)
+            .unwrap()
+    }
+
+    // 需要已完成迁移的数据库（DATABASE_URL），测试中的修改均会回滚
+    #[test]
+    #[ignore = "requires DATABASE_URL"]
+    fn failed_watchlist_replace_leaves_table_unchanged() {
+        let mut conn = establish_connection();
+
+        conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(player::table).execute(conn)?;
+            insert_into(player::table)
+                .values(&vec![
+                    NewPlayer {
+                        player_name: "old_friend".to_string(),
+                        friend: true,
+                    },
+                    NewPlayer {
+                        player_name: "stranger".to_string(),
+                        friend: false,
+                    },
+                ])
+                .execute(conn)?;
+
+            let before = watchlist_state(conn);
+
+            // 玩家名中的NUL字符会使插入失败，此时取消关注的修改已执行
+            let watchlist = vec![
+                NewPlayer {
+                    player_name: "stranger".to_string(),
+                    friend: true,
+                },
+                NewPlayer {
+                    player_name: "bad\0name".to_string(),
+                    friend: true,
+                },
+            ];
+
+            assert!(replace_watchlist(conn, &watchlist).is_err());
+            assert_eq!(watchlist_state(conn), before);
+
+            Ok(())
+        });
+    }
 }
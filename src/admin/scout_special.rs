@@ -0,0 +1,139 @@
+use crate::canonical_player_name;
+use crate::db::schema::*;
+use crate::kpi::CharacterKPIType;
+use diesel::prelude::*;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Serialize, Deserialize)]
+pub struct ScoutPlayerClassification {
+    #[serde(rename = "playerName")]
+    pub player_name: String,
+    #[serde(rename = "scoutMissionCount")]
+    pub scout_mission_count: i32,
+    // scout或scout_special
+    #[serde(rename = "kpiType")]
+    pub kpi_type: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScoutSpecialPreview {
+    pub player: Vec<ScoutPlayerClassification>,
+    // scout_special.txt中未在数据中以侦察身份出现的玩家，可能是名称有误
+    pub unmatched: Vec<String>,
+}
+
+// scout_player_name_list：每局中选择侦察的玩家名称（同一玩家可重复出现）
+// 与计算KPI时一致，按player_mapping合并别名后以主名称分类
+fn classify_scout_player(
+    scout_player_name_list: Vec<String>,
+    scout_special_player_set: &HashSet<String>,
+    player_mapping: &HashMap<String, String>,
+) -> ScoutSpecialPreview {
+    let mut scout_mission_count: BTreeMap<String, i32> = BTreeMap::new();
+
+    for player_name in scout_player_name_list {
+        let player_name = canonical_player_name(player_mapping, player_name);
+        *scout_mission_count.entry(player_name).or_default() += 1;
+    }
+
+    let mut unmatched = scout_special_player_set
+        .iter()
+        .filter(|player_name| !scout_mission_count.contains_key(*player_name))
+        .cloned()
+        .collect::<Vec<_>>();
+    unmatched.sort_unstable();
+
+    let player = scout_mission_count
+        .into_iter()
+        .map(|(player_name, count)| {
+            let kpi_type =
+                CharacterKPIType::from_player("SCOUT", &player_name, scout_special_player_set);
+
+            ScoutPlayerClassification {
+                player_name,
+                scout_mission_count: count,
+                kpi_type: kpi_type.to_string(),
+            }
+        })
+        .collect();
+
+    ScoutSpecialPreview { player, unmatched }
+}
+
+pub(crate) fn preview_scout_special(
+    db_conn: &mut PgConnection,
+    scout_special_player_set: &HashSet<String>,
+    player_mapping: &HashMap<String, String>,
+) -> Result<ScoutSpecialPreview, ()> {
+    let scout_player_name_list = player_info::table
+        .inner_join(player::table)
+        .inner_join(character::table)
+        .filter(character::character_game_id.eq("SCOUT"))
+        .select(player::player_name)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot get scout player list: {}", e))?;
+
+    Ok(classify_scout_player(
+        scout_player_name_list,
+        scout_special_player_set,
+        player_mapping,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scout_player_is_classified_by_special_set() {
+        let scout_player_name_list = ["A", "B", "A"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let scout_special_player_set = HashSet::from(["A".to_string(), "C".to_string()]);
+
+        let preview = classify_scout_player(
+            scout_player_name_list,
+            &scout_special_player_set,
+            &HashMap::new(),
+        );
+
+        assert_eq!(
+            preview
+                .player
+                .iter()
+                .map(|x| (
+                    x.player_name.as_str(),
+                    x.scout_mission_count,
+                    x.kpi_type.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("A", 2, "scout_special"), ("B", 1, "scout")]
+        );
+        assert_eq!(preview.unmatched, vec!["C".to_string()]);
+    }
+
+    #[test]
+    fn scout_player_alias_is_classified_by_canonical_name() {
+        let scout_player_name_list = ["A", "A_old"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let scout_special_player_set = HashSet::from(["A".to_string()]);
+        let player_mapping = HashMap::from([("A_old".to_string(), "A".to_string())]);
+
+        let preview = classify_scout_player(
+            scout_player_name_list,
+            &scout_special_player_set,
+            &player_mapping,
+        );
+
+        assert_eq!(preview.player.len(), 1);
+        assert_eq!(preview.player[0].player_name, "A");
+        assert_eq!(preview.player[0].scout_mission_count, 2);
+        assert_eq!(preview.player[0].kpi_type, "scout_special");
+        assert!(preview.unmatched.is_empty());
+    }
+}
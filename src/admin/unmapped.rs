@@ -0,0 +1,140 @@
+use super::UnmappedGameId;
+use crate::db::schema::*;
+use crate::Mapping;
+use diesel::prelude::*;
+use log::error;
+use std::collections::HashMap;
+
+// 合并后的目标已有映射时，原game_id无需单独映射
+fn is_mapped(
+    game_id: &str,
+    name_mapping: &HashMap<String, String>,
+    combine: &HashMap<String, String>,
+) -> bool {
+    name_mapping.contains_key(game_id)
+        || combine
+            .get(game_id)
+            .is_some_and(|target| name_mapping.contains_key(target))
+}
+
+fn filter_unmapped(game_id_list: Vec<String>, is_mapped: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut result = game_id_list
+        .into_iter()
+        .filter(|game_id| !is_mapped(game_id))
+        .collect::<Vec<_>>();
+
+    result.sort_unstable();
+    result
+}
+
+fn generate(
+    entity_list: Vec<String>,
+    weapon_list: Vec<String>,
+    resource_list: Vec<String>,
+    character_list: Vec<String>,
+    mission_type_list: Vec<String>,
+    mapping: &Mapping,
+) -> UnmappedGameId {
+    let no_combine = HashMap::new();
+
+    UnmappedGameId {
+        // 黑名单中的实体不会显示，无需映射
+        entity: filter_unmapped(entity_list, |game_id| {
+            mapping.entity_blacklist_set.contains(game_id)
+                || is_mapped(game_id, &mapping.entity_mapping, &mapping.entity_combine)
+        }),
+        weapon: filter_unmapped(weapon_list, |game_id| {
+            is_mapped(game_id, &mapping.weapon_mapping, &mapping.weapon_combine)
+        }),
+        resource: filter_unmapped(resource_list, |game_id| {
+            is_mapped(game_id, &mapping.resource_mapping, &no_combine)
+        }),
+        character: filter_unmapped(character_list, |game_id| {
+            is_mapped(game_id, &mapping.character_mapping, &no_combine)
+        }),
+        mission_type: filter_unmapped(mission_type_list, |game_id| {
+            is_mapped(game_id, &mapping.mission_type_mapping, &no_combine)
+        }),
+    }
+}
+
+pub(crate) fn find_unmapped_game_id(
+    db_conn: &mut PgConnection,
+    mapping: &Mapping,
+) -> Result<UnmappedGameId, ()> {
+    let entity_list = entity::table
+        .select(entity::entity_game_id)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot load entity from db: {}", e))?;
+
+    let weapon_list = weapon::table
+        .select(weapon::weapon_game_id)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot load weapon from db: {}", e))?;
+
+    let resource_list = resource::table
+        .select(resource::resource_game_id)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot load resource from db: {}", e))?;
+
+    let character_list = character::table
+        .select(character::character_game_id)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot load character from db: {}", e))?;
+
+    let mission_type_list = mission_type::table
+        .select(mission_type::mission_type_game_id)
+        .load::<String>(db_conn)
+        .map_err(|e| error!("cannot load mission type from db: {}", e))?;
+
+    Ok(generate(
+        entity_list,
+        weapon_list,
+        resource_list,
+        character_list,
+        mission_type_list,
+        mapping,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_and_blacklisted_entity_are_not_unmapped() {
+        let mut mapping = Mapping::default();
+        mapping
+            .entity_mapping
+            .insert("ED_Spider_Grunt".to_string(), "Grunt".to_string());
+        mapping.entity_combine.insert(
+            "ED_Spider_Grunt_Guard".to_string(),
+            "ED_Spider_Grunt".to_string(),
+        );
+        mapping
+            .entity_blacklist_set
+            .insert("ED_Spider_Lobber_Proj".to_string());
+        mapping
+            .mission_type_mapping
+            .insert("MissionType_Egg".to_string(), "Egg".to_string());
+
+        let result = generate(
+            vec![
+                "ED_Spider_Grunt".to_string(),
+                "ED_Spider_Grunt_Guard".to_string(),
+                "ED_Spider_Lobber_Proj".to_string(),
+                "ED_Spider_New".to_string(),
+            ],
+            vec!["WPN_NewWeapon".to_string()],
+            Vec::new(),
+            Vec::new(),
+            vec!["MissionType_Egg".to_string(), "MissionType_New".to_string()],
+            &mapping,
+        );
+
+        assert_eq!(result.entity, vec!["ED_Spider_New".to_string()]);
+        assert_eq!(result.weapon, vec!["WPN_NewWeapon".to_string()]);
+        assert!(result.resource.is_empty());
+        assert_eq!(result.mission_type, vec!["MissionType_New".to_string()]);
+    }
+}
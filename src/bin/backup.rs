@@ -0,0 +1,146 @@
+use mission_backend_rs::admin::mission_invalid::MissionInvalidDetail;
+use mission_backend_rs::client::backup::*;
+use mission_backend_rs::client::*;
+use mission_backend_rs::kpi::KPIConfig;
+use mission_backend_rs::{Mapping, KPI_VERSION};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let backup_dir = match client_args().into_iter().next() {
+        Some(x) => PathBuf::from(x),
+        None => return Err(ClientError::Other("usage: backup <dir>".to_string())),
+    };
+
+    // 不覆盖已有备份
+    if backup_dir.join(MANIFEST_FILE).exists() {
+        return Err(ClientError::Other(format!(
+            "{} already contains a backup",
+            backup_dir.to_string_lossy()
+        )));
+    }
+
+    fs::create_dir_all(&backup_dir).map_err(|e| {
+        format!(
+            "cannot create backup dir {}: {}",
+            backup_dir.to_string_lossy(),
+            e
+        )
+    })?;
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+    let endpoint_url = &config.endpoint_url;
+    let max_retries = config.max_retries();
+
+    let mapping: Mapping = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/admin/export_mapping",
+        max_retries,
+    )?;
+    write_json_file(&backup_dir, MAPPING_FILE, &mapping)?;
+    println!("exported mapping");
+
+    let kpi_config: Option<KPIConfig> =
+        fetch_api_optional(&http_client, endpoint_url, "/admin/export_kpi", max_retries)?;
+    match &kpi_config {
+        Some(x) => {
+            write_json_file(&backup_dir, KPI_CONFIG_FILE, x)?;
+            println!("exported kpi config");
+        }
+        None => println!("kpi config is not loaded on server, skipped"),
+    }
+
+    let watchlist: Vec<String> = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/admin/export_watchlist",
+        max_retries,
+    )?;
+    write_json_file(&backup_dir, WATCHLIST_FILE, &watchlist)?;
+    println!("exported watchlist: {} players", watchlist.len());
+
+    let mission_invalid_list: Vec<MissionInvalidDetail> = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/admin/mission_invalid_detailed",
+        max_retries,
+    )?;
+    let mission_invalid_list = mission_invalid_list
+        .into_iter()
+        .map(|item| BackupMissionInvalid {
+            begin_timestamp: item.begin_timestamp,
+            reason: item.reason,
+        })
+        .collect::<Vec<_>>();
+    write_json_file(&backup_dir, MISSION_INVALID_FILE, &mission_invalid_list)?;
+    println!(
+        "exported invalid mission marks: {} missions",
+        mission_invalid_list.len()
+    );
+
+    let mission_dump = fetch_raw(
+        &http_client,
+        endpoint_url,
+        "/admin/export_mission",
+        max_retries,
+    )?;
+    // 写入前先解码一次，确认导出的数据完整并统计任务数
+    let mission_count = decode_mission_dump(&mission_dump)?.len();
+    write_file(&backup_dir, MISSION_FILE, &mission_dump)?;
+    println!(
+        "exported missions: {} missions, {}",
+        mission_count,
+        format_size(mission_dump.len())
+    );
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        kpi_version: KPI_VERSION.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        endpoint_url: endpoint_url.clone(),
+        mission_count,
+        watchlist_count: watchlist.len(),
+        has_kpi_config: kpi_config.is_some(),
+        mission_invalid_count: Some(mission_invalid_list.len()),
+    };
+
+    // 最后写入manifest，缺少manifest的目录视为不完整的备份
+    write_json_file(&backup_dir, MANIFEST_FILE, &manifest)?;
+
+    println!(
+        "backup written to {}. Rock and stone!",
+        backup_dir.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+fn write_json_file<T: Serialize>(
+    backup_dir: &Path,
+    file_name: &str,
+    data: &T,
+) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(data)
+        .map_err(|e| format!("cannot serialize {}: {}", file_name, e))?;
+
+    write_file(backup_dir, file_name, &content)
+}
+
+fn write_file(backup_dir: &Path, file_name: &str, content: &[u8]) -> Result<(), String> {
+    let file_path = backup_dir.join(file_name);
+
+    fs::write(&file_path, content)
+        .map_err(|e| format!("cannot write {}: {}", file_path.to_string_lossy(), e))
+}
@@ -0,0 +1,379 @@
+use mission_backend_rs::client::*;
+use mission_backend_rs::mission::{
+    MissionDamageInfo, MissionGeneralData, MissionKPIInfo, MissionList,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use std::cmp::Reverse;
+use std::io;
+use std::process::ExitCode;
+
+const PAGE_SIZE: usize = 20;
+
+const TAB_LIST: [&str; 3] = ["general", "damage", "kpi"];
+
+enum View {
+    MissionList,
+    Mission {
+        mission_id: i32,
+        // TAB_LIST中的下标
+        tab: usize,
+        scroll: u16,
+        content: Vec<String>,
+    },
+}
+
+struct Browser {
+    http_client: Client,
+    endpoint_url: String,
+    max_retries: u32,
+    mission_list: MissionList,
+    table_state: TableState,
+    view: View,
+}
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+    let max_retries = config.max_retries();
+
+    let mut mission_list: MissionList = fetch_api(
+        &http_client,
+        &config.endpoint_url,
+        "/mission/mission_list",
+        max_retries,
+    )?;
+
+    // 最新的任务排在最前
+    mission_list
+        .mission_info
+        .sort_unstable_by_key(|x| Reverse(x.begin_timestamp));
+
+    let mut browser = Browser {
+        http_client,
+        endpoint_url: config.endpoint_url,
+        max_retries,
+        mission_list,
+        table_state: TableState::default().with_selected(Some(0)),
+        view: View::MissionList,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = browser.run(&mut terminal);
+    // 无论是否出错都需恢复终端
+    ratatui::restore();
+
+    result.map_err(|e| ClientError::Other(format!("terminal error: {}", e)))
+}
+
+impl Browser {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            let keep_running = match self.view {
+                View::MissionList => self.handle_list_key(key.code),
+                View::Mission { .. } => self.handle_mission_key(key.code),
+            };
+
+            if !keep_running {
+                return Ok(());
+            }
+        }
+    }
+
+    // 返回false表示退出
+    fn handle_list_key(&mut self, key_code: KeyCode) -> bool {
+        let mission_count = self.mission_list.mission_info.len();
+        let selected = self.table_state.selected().unwrap_or(0);
+        let last = mission_count.saturating_sub(1);
+
+        let next_selected = match key_code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => (selected + 1).min(last),
+            KeyCode::PageUp => selected.saturating_sub(PAGE_SIZE),
+            KeyCode::PageDown => (selected + PAGE_SIZE).min(last),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            KeyCode::Enter => {
+                if let Some(mission) = self.mission_list.mission_info.get(selected) {
+                    let mission_id = mission.mission_id;
+                    self.open_mission(mission_id, 0);
+                }
+                selected
+            }
+            _ => selected,
+        };
+
+        self.table_state.select(Some(next_selected));
+
+        true
+    }
+
+    fn handle_mission_key(&mut self, key_code: KeyCode) -> bool {
+        let (mission_id, tab, scroll) = match &mut self.view {
+            View::Mission {
+                mission_id,
+                tab,
+                scroll,
+                ..
+            } => (*mission_id, *tab, scroll),
+            View::MissionList => return true,
+        };
+
+        match key_code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc | KeyCode::Backspace => self.view = View::MissionList,
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(PAGE_SIZE as u16),
+            KeyCode::PageDown => *scroll = scroll.saturating_add(PAGE_SIZE as u16),
+            KeyCode::Left => {
+                self.open_mission(mission_id, (tab + TAB_LIST.len() - 1) % TAB_LIST.len())
+            }
+            KeyCode::Right | KeyCode::Tab => {
+                self.open_mission(mission_id, (tab + 1) % TAB_LIST.len())
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn open_mission(&mut self, mission_id: i32, tab: usize) {
+        let content = match tab {
+            0 => self.general_lines(mission_id),
+            1 => self.damage_lines(mission_id),
+            _ => self.kpi_lines(mission_id),
+        };
+
+        self.view = View::Mission {
+            mission_id,
+            tab,
+            scroll: 0,
+            content,
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, help_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        match &self.view {
+            View::MissionList => {
+                frame.render_stateful_widget(
+                    mission_table(&self.mission_list, self.table_state.selected()),
+                    main_area,
+                    &mut self.table_state,
+                );
+                frame.render_widget(
+                    Paragraph::new("↑/↓ select  PgUp/PgDn page  Home/End  Enter open  q quit"),
+                    help_area,
+                );
+            }
+            View::Mission {
+                mission_id,
+                tab,
+                scroll,
+                content,
+            } => {
+                let [tab_area, content_area] =
+                    Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(main_area);
+
+                frame.render_widget(
+                    Tabs::new(TAB_LIST)
+                        .select(*tab)
+                        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                        .block(Block::bordered().title(format!("mission {}", mission_id))),
+                    tab_area,
+                );
+                frame.render_widget(
+                    Paragraph::new(
+                        content
+                            .iter()
+                            .map(|line| Line::raw(line.as_str()))
+                            .collect::<Vec<_>>(),
+                    )
+                    .block(Block::bordered())
+                    .scroll((*scroll, 0)),
+                    content_area,
+                );
+                frame.render_widget(
+                    Paragraph::new("←/→ switch tab  ↑/↓ scroll  Esc back  q quit"),
+                    help_area,
+                );
+            }
+        }
+    }
+
+    fn fetch<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        fetch_api(
+            &self.http_client,
+            &self.endpoint_url,
+            path,
+            self.max_retries,
+        )
+    }
+
+    fn general_lines(&self, mission_id: i32) -> Vec<String> {
+        let general: MissionGeneralData =
+            match self.fetch(&format!("/mission/{}/general", mission_id)) {
+                Ok(x) => x,
+                Err(e) => return vec![e.to_string()],
+            };
+
+        let mut result = vec![
+            format!(
+                "damage: {:.0}, kill: {}, minerals: {:.0}, nitra: {:.0}, supply: {}, credit: {:.0}",
+                general.total_damage,
+                general.total_kill,
+                general.total_minerals,
+                general.total_nitra,
+                general.total_supply_count,
+                general.reward_credit
+            ),
+            String::new(),
+            format!(
+                "{:<24} {:<10} {:>8} {:>6} {:>6} {:<8}",
+                "player", "character", "present", "revive", "death", "escaped"
+            ),
+        ];
+
+        let mut player_list = general.player_info.iter().collect::<Vec<_>>();
+        player_list.sort_unstable_by_key(|(player_name, _)| *player_name);
+
+        for (player_name, info) in player_list {
+            result.push(format!(
+                "{:<24} {:<10} {:>8} {:>6} {:>6} {:<8}",
+                player_name,
+                info.character_game_id,
+                format_mission_time(info.present_time),
+                info.revive_num,
+                info.death_num,
+                info.player_escaped
+            ));
+        }
+
+        result
+    }
+
+    fn damage_lines(&self, mission_id: i32) -> Vec<String> {
+        let damage: MissionDamageInfo = match self.fetch(&format!("/mission/{}/damage", mission_id))
+        {
+            Ok(x) => x,
+            Err(e) => return vec![e.to_string()],
+        };
+
+        let mut result = vec![format!(
+            "{:<24} {:>12} {:>6} {:>10} {:>6}",
+            "player", "damage", "kill", "ff", "supply"
+        )];
+
+        let mut player_list = damage.info.iter().collect::<Vec<_>>();
+        player_list.sort_unstable_by_key(|(player_name, _)| *player_name);
+
+        for (player_name, info) in player_list {
+            result.push(format!(
+                "{:<24} {:>12.0} {:>6} {:>10.0} {:>6}",
+                player_name,
+                info.damage.values().sum::<f64>(),
+                info.kill.values().sum::<i32>(),
+                info.ff.cause.values().sum::<f64>(),
+                info.supply_count
+            ));
+        }
+
+        result
+    }
+
+    fn kpi_lines(&self, mission_id: i32) -> Vec<String> {
+        let kpi: Vec<MissionKPIInfo> = match self.fetch(&format!("/mission/{}/kpi", mission_id)) {
+            Ok(x) => x,
+            Err(e) => return vec![e.to_string()],
+        };
+
+        let mut result = Vec::new();
+
+        for player_kpi in &kpi {
+            result.push(format!(
+                "{} ({}): {:.3}",
+                player_kpi.player_name, player_kpi.kpi_character_type, player_kpi.mission_kpi
+            ));
+            for component in &player_kpi.component {
+                result.push(format!(
+                    "  {:<12} {:>8.3} x {:.3}",
+                    component.name, component.transformed_index, component.weight
+                ));
+            }
+            result.push(String::new());
+        }
+
+        result
+    }
+}
+
+fn mission_table(mission_list: &MissionList, selected: Option<usize>) -> Table<'_> {
+    let mission_info = &mission_list.mission_info;
+
+    let row_list = mission_info.iter().map(|mission| {
+        let mission_type = mission_list
+            .mission_type_mapping
+            .get(&mission.mission_type_id)
+            .unwrap_or(&mission.mission_type_id);
+
+        Row::new([
+            mission.mission_id.to_string(),
+            format_timestamp(mission.begin_timestamp),
+            mission_type.clone(),
+            mission.hazard_id.to_string(),
+            format_mission_time(mission.mission_time),
+            format_mission_result(mission.mission_result).to_string(),
+            if mission.mission_invalid {
+                "invalid".to_string()
+            } else {
+                String::new()
+            },
+        ])
+    });
+
+    Table::new(
+        row_list,
+        [
+            Constraint::Length(8),
+            Constraint::Length(17),
+            Constraint::Min(12),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(["id", "begin", "type", "hazard", "time", "result", ""])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    .block(Block::bordered().title(format!(
+        "missions ({}/{})",
+        selected.map_or(0, |x| x + 1),
+        mission_info.len()
+    )))
+}
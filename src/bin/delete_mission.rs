@@ -0,0 +1,67 @@
+use mission_backend_rs::client::*;
+use std::collections::HashSet;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let to_delete_mission_list = client_args()
+        .into_iter()
+        .map(|x| {
+            x.parse::<i32>()
+                .map_err(|e| format!("invalid mission id {}: {}", x, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if to_delete_mission_list.is_empty() {
+        return Err(ClientError::Other(
+            "usage: delete_mission <mission_id>...".to_string(),
+        ));
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let body = serde_json::to_vec(&to_delete_mission_list)
+        .map_err(|e| format!("cannot serialize mission list: {}", e))?;
+
+    let deleted_mission_list: Vec<i32> = post_api(
+        &http_client,
+        &config.endpoint_url,
+        "/admin/delete_mission",
+        body,
+        config.max_retries(),
+    )?;
+
+    let deleted_mission_set = deleted_mission_list.iter().collect::<HashSet<_>>();
+
+    for mission_id in &to_delete_mission_list {
+        if !deleted_mission_set.contains(mission_id) {
+            println!("mission {} not found", mission_id);
+        }
+    }
+
+    println!(
+        "deleted {} of {} missions",
+        deleted_mission_list.len(),
+        to_delete_mission_list.len()
+    );
+
+    // 其余任务的缓存不受影响，仅需重新计算全局KPI状态
+    if !deleted_mission_list.is_empty() {
+        if let Err(e) = update_cache(
+            &[CacheType::GlobalKPIState],
+            &config.endpoint_url,
+            &http_client,
+            config.max_retries(),
+        ) {
+            println!("failed updating cache: {}", e);
+        }
+    }
+
+    Ok(())
+}
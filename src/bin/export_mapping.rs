@@ -0,0 +1,70 @@
+use mission_backend_rs::client::mapping::*;
+use mission_backend_rs::client::*;
+use mission_backend_rs::Mapping;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let mapping_dir = match client_args().into_iter().next() {
+        Some(x) => PathBuf::from(x),
+        None => {
+            return Err(ClientError::Other(
+                "usage: export_mapping <dir>".to_string(),
+            ))
+        }
+    };
+
+    // 不覆盖已有的mapping文件
+    if let Some(file_name) = MAPPING_FILE_LIST
+        .iter()
+        .find(|file_name| mapping_dir.join(file_name).exists())
+    {
+        return Err(ClientError::Other(format!(
+            "{} already contains {}",
+            mapping_dir.to_string_lossy(),
+            file_name
+        )));
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let mapping: Mapping = fetch_api(
+        &http_client,
+        &config.endpoint_url,
+        "/admin/export_mapping",
+        config.max_retries(),
+    )?;
+
+    let mapping_files = mapping_to_files(&mapping);
+
+    fs::create_dir_all(&mapping_dir).map_err(|e| {
+        format!(
+            "cannot create mapping dir {}: {}",
+            mapping_dir.to_string_lossy(),
+            e
+        )
+    })?;
+
+    for (file_name, content) in &mapping_files.file_list {
+        let file_path = mapping_dir.join(file_name);
+        fs::write(&file_path, content)
+            .map_err(|e| format!("cannot write {}: {}", file_path.to_string_lossy(), e))?;
+        println!("exported {}: {} lines", file_name, content.lines().count());
+    }
+
+    for entry in &mapping_files.skipped {
+        println!("warning: cannot be written as text, skipped: {}", entry);
+    }
+
+    println!("Success. Rock and stone!");
+
+    Ok(())
+}
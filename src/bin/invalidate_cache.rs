@@ -0,0 +1,56 @@
+use mission_backend_rs::cache::{APICacheType, InvalidateCacheRequest, InvalidateCacheResult};
+use mission_backend_rs::client::*;
+use std::process::ExitCode;
+
+const USAGE: &str =
+    "usage: invalidate_cache <mission_raw|mission_kpi_raw|global_kpi_state> [mission_id]";
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let args = client_args();
+
+    let cache_type = match args.first().map(|x| x.as_str()) {
+        Some("mission_raw") => APICacheType::MissionRaw,
+        Some("mission_kpi_raw") => APICacheType::MissionKPIRaw,
+        Some("global_kpi_state") => APICacheType::GlobalKPIState,
+        _ => return Err(ClientError::Other(USAGE.to_string())),
+    };
+
+    let mission_id = match args.get(1) {
+        Some(x) => Some(
+            x.parse::<i32>()
+                .map_err(|e| format!("invalid mission id {}: {}", x, e))?,
+        ),
+        None => None,
+    };
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let body = serde_json::to_vec(&InvalidateCacheRequest {
+        cache_type,
+        mission_id,
+    })
+    .map_err(|e| format!("cannot serialize request: {}", e))?;
+
+    let result: InvalidateCacheResult = post_api(
+        &http_client,
+        &config.endpoint_url,
+        "/cache/invalidate",
+        body,
+        config.max_retries(),
+    )?;
+
+    if result.deleted {
+        println!("invalidated {}", result.key);
+    } else {
+        println!("{} was not cached", result.key);
+    }
+
+    Ok(())
+}
@@ -1,21 +1,12 @@
-use actix_web::web::Buf;
 use mission_backend_rs::client::*;
 use mission_backend_rs::kpi::*;
-use mission_backend_rs::APIResponse;
-use mission_backend_rs::ClientConfig;
-use reqwest::blocking::ClientBuilder;
-use reqwest::cookie::Jar;
-use reqwest::StatusCode;
-use reqwest::Url;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::env;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
-use std::str::FromStr;
-use std::sync::Arc;
+use std::process::ExitCode;
 
 #[derive(Deserialize)]
 struct EntityRecord {
@@ -34,43 +25,18 @@ struct ResourceRecord {
     pub weight: f64,
 }
 
-fn main() -> Result<(), String> {
-    author_info();
-    let config_file_path = match env::var("CONFIG_PATH") {
-        Ok(val) => PathBuf::from_str(&val).expect("invalid CONFIG_PATH"),
-        Err(_) => PathBuf::from_str("./config.json").unwrap(),
-    };
+fn main() -> ExitCode {
+    exit_with(run())
+}
 
-    let file_content = fs::read(&config_file_path).map_err(|e| {
-        format!(
-            "cannot read config file {}: {}",
-            config_file_path.to_string_lossy(),
-            e
-        )
-    })?;
-
-    let config: ClientConfig = serde_json::from_slice(&file_content[..]).map_err(|e| {
-        format!(
-            "cannot parse config file {}: {}",
-            config_file_path.to_string_lossy(),
-            e
-        )
-    })?;
-
-    if config.access_token.is_none() {
-        println!("warning: no access token specified!");
-    }
+fn run() -> Result<(), ClientError> {
+    author_info();
 
-    let access_token = config.access_token.unwrap_or("Rock and stone!".to_string());
+    let config = load_client_config()?;
 
-    let kpi_config_path = match config.kpi_config_path {
-        Some(mut path) => {
-            if !(path.ends_with('/') || path.ends_with('\\')) {
-                path.push('/');
-            }
-            PathBuf::from_str(&path).expect("invalid kpi path")
-        }
-        None => PathBuf::from_str("./kpi/").unwrap(),
+    let kpi_config_path = match &config.kpi_config_path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from("./kpi/"),
     };
 
     let character_component_weight =
@@ -87,82 +53,57 @@ fn main() -> Result<(), String> {
     let transform_range = load_transform_range(&kpi_config_path.join("transform_range.txt"))
         .map_err(|e| format!("cannot load transform range: {}", e))?;
 
+    let baseline_excluded_player =
+        load_baseline_excluded_player(&kpi_config_path.join("baseline_excluded_player.txt"))
+            .map_err(|e| format!("cannot load baseline excluded player: {}", e))?;
+
+    let friendly_fire = load_friendly_fire(&kpi_config_path.join("friendly_fire.txt"))
+        .map_err(|e| format!("cannot load friendly fire config: {}", e))?;
+
     let kpi_config = KPIConfig {
         character_weight_table,
         priority_table,
         resource_weight_table,
         character_component_weight,
         transform_range,
+        baseline_excluded_player,
+        friendly_fire,
     };
 
-    let serialized = serde_json::to_vec(&kpi_config).unwrap();
+    if let Err(problem_list) = kpi_config.validate() {
+        return Err(format!("invalid kpi config:\n{}", problem_list.join("\n")).into());
+    }
 
-    let cookie_jar = Arc::new(Jar::default());
+    let force = client_args().iter().any(|x| x == "--force");
 
-    let http_client = ClientBuilder::new()
-        .cookie_provider(cookie_jar.clone())
-        .build()
-        .unwrap();
+    let serialized = serde_json::to_vec(&kpi_config)
+        .map_err(|e| format!("cannot serialize kpi config: {}", e))?;
 
     let endpoint_url = &config.endpoint_url;
-
-    let upload_endpoint = format!("{}/admin/load_kpi", endpoint_url);
-
-    println!("upload endpoint: {}", upload_endpoint);
-
-    cookie_jar.add_cookie_str(
-        &format!("access_token = {};", access_token).as_str(),
-        &upload_endpoint
-            .parse::<Url>()
-            .expect("failed parsing load kpi url"),
-    );
-
-    match http_client
-        .post(
-            upload_endpoint
-                .parse::<Url>()
-                .expect("failed parsing load kpi url"),
-        )
-        .body(serialized)
-        .send()
-    {
-        Ok(response) => match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().expect("failed fetching response body");
-                let api_response: APIResponse<()> = match serde_json::from_reader(body.reader()) {
-                    Ok(x) => x,
-                    Err(e) => panic!("failed parsing response body {}", e),
-                };
-
-                if api_response.code == 200 {
-                    match update_cache(
-                        &[CacheType::MissionKPIRawCache, CacheType::GlobalKPIState],
-                        endpoint_url,
-                        &http_client,
-                    ) {
-                        Ok(_) => {
-                            println!("Success. Rock and stone!");
-                        }
-                        Err(e) => {
-                            println!("failed updating cache: {}", e);
-                        }
-                    }
-                } else {
-                    return Err(format!(
-                        "Server returned {}: {}",
-                        api_response.code, api_response.message
-                    ));
-                }
-            }
-            other => {
-                println!("unexpected status code from server: {}", other);
-                println!("body: {:?}", response.text());
-                return Err("cannot load kpi config".into());
-            }
-        },
-        Err(e) => {
-            return Err(format!("failed sending request: {}", e));
-        }
+    let max_retries = config.max_retries();
+
+    let http_client = build_http_client(&config)?;
+
+    println!("upload endpoint: {}/admin/load_kpi", endpoint_url);
+
+    check_kpi_version(&http_client, endpoint_url, max_retries, force)?;
+
+    post_api::<()>(
+        &http_client,
+        endpoint_url,
+        "/admin/load_kpi",
+        serialized,
+        max_retries,
+    )?;
+
+    match update_cache(
+        &[CacheType::MissionKPIRawCache, CacheType::GlobalKPIState],
+        endpoint_url,
+        &http_client,
+        max_retries,
+    ) {
+        Ok(_) => println!("Success. Rock and stone!"),
+        Err(e) => println!("failed updating cache: {}", e),
     }
 
     Ok(())
@@ -304,3 +245,51 @@ fn load_transform_range(
 
     Ok(result)
 }
+
+// 可选文件，不存在时不排除任何玩家
+fn load_baseline_excluded_player(file_path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file_content = fs::read_to_string(file_path)?;
+
+    Ok(file_content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect())
+}
+
+// 可选文件，每行为`<cutoff|coefficient|floor> <value>`，未给出的项使用默认值；
+// 文件不存在时使用服务端默认的友伤指数曲线
+fn load_friendly_fire(file_path: &Path) -> Result<Option<FriendlyFireConfig>, Box<dyn Error>> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let file_content = fs::read_to_string(file_path)?;
+
+    let mut result = FriendlyFireConfig::default();
+
+    for line in file_content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        let (key, value) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("invalid line: {}", line))?;
+        let value = value.trim().parse::<f64>()?;
+
+        match key {
+            "cutoff" => result.cutoff = value,
+            "coefficient" => result.coefficient = value,
+            "floor" => result.floor = value,
+            _ => return Err(format!("unknown key: {}", key).into()),
+        }
+    }
+
+    Ok(Some(result))
+}
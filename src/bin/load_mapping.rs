@@ -1,57 +1,24 @@
-use actix_web::web::Buf;
+use mission_backend_rs::admin::LoadMappingResult;
+use mission_backend_rs::cache::CacheUpdateStatus;
 use mission_backend_rs::client::*;
-use mission_backend_rs::{APIResponse, ClientConfig, Mapping};
-use reqwest::cookie::Jar;
-use reqwest::{blocking::ClientBuilder, StatusCode, Url};
+use mission_backend_rs::Mapping;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::str::FromStr;
-use std::sync::Arc;
-use std::{env, fs, path::PathBuf};
+use std::process::ExitCode;
+use std::{fs, path::PathBuf};
 
-fn main() {
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
     author_info();
-    let config_file_path = match env::var("CONFIG_PATH") {
-        Ok(val) => PathBuf::from_str(&val).expect("invalid CONFIG_PATH"),
-        Err(_) => PathBuf::from_str("./config.json").unwrap(),
-    };
 
-    let file_content = match fs::read(&config_file_path) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot read config file {}: {}",
-                config_file_path.to_string_lossy(),
-                e
-            );
-        }
-    };
+    let config = load_client_config()?;
 
-    let config: ClientConfig = match serde_json::from_slice(&file_content[..]) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot parse config file {}: {}",
-                config_file_path.to_string_lossy(),
-                e
-            );
-        }
-    };
-
-    if config.access_token.is_none() {
-        println!("warning: no access token specified!");
-    }
-
-    let access_token = config.access_token.unwrap_or("Rock and stone!".to_string());
-
-    let mapping_path = match config.mapping_path {
-        Some(mut path) => {
-            if !(path.ends_with('/') || path.ends_with('\\')) {
-                path.push('/');
-            }
-            PathBuf::from_str(&path).expect("invalid mapping path")
-        }
-        None => PathBuf::from_str("./mapping/").unwrap(),
+    let mapping_path = match &config.mapping_path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from("./mapping/"),
     };
 
     let entity_black_list_path = mapping_path.as_path().join("entity_blacklist.txt");
@@ -82,6 +49,21 @@ fn main() {
         }
     };
 
+    let non_mineral_resource_list_path = mapping_path.as_path().join("non_mineral_resource.txt");
+
+    let non_mineral_resource_list_file_content =
+        match fs::read_to_string(&non_mineral_resource_list_path) {
+            Ok(content) => content,
+            Err(e) => {
+                println!(
+                    "failed reading mapping file {}: {}, default value will be used",
+                    non_mineral_resource_list_path.as_os_str().to_str().unwrap(),
+                    e
+                );
+                String::new()
+            }
+        };
+
     let entity_blacklist = entity_black_list_file_content
         .lines()
         .filter(|&x| !x.trim().starts_with('#'))
@@ -93,6 +75,13 @@ fn main() {
         .filter(|&x| !x.trim().starts_with('#'))
         .map(|x| String::from(x))
         .collect::<Vec<String>>();
+
+    let non_mineral_resource_list = non_mineral_resource_list_file_content
+        .lines()
+        .filter(|&x| !x.trim().is_empty() && !x.trim().starts_with('#'))
+        .map(|x| String::from(x.trim()))
+        .collect::<Vec<String>>();
+
     let character_mapping = parse_mapping_file(&mapping_path.join("character.txt"));
     let entity_mapping = parse_mapping_file(&mapping_path.join("entity.txt"));
     let entity_combine = parse_mapping_file(&mapping_path.join("entity_combine.txt"));
@@ -101,6 +90,8 @@ fn main() {
     let weapon_mapping = parse_mapping_file(&mapping_path.join("weapon.txt"));
     let weapon_combine = parse_mapping_file(&mapping_path.join("weapon_combine.txt"));
     let weapon_character = parse_mapping_file(&mapping_path.join("weapon_hero.txt"));
+    // 每行为`<别名>|<主名称>`
+    let player_mapping = parse_mapping_file(&mapping_path.join("player.txt"));
 
     let mapping = Mapping {
         character_mapping,
@@ -113,82 +104,36 @@ fn main() {
         weapon_combine,
         weapon_character,
         scout_special_player_set: scout_special_list.into_iter().collect(),
+        non_mineral_resource_set: non_mineral_resource_list.into_iter().collect(),
+        player_mapping,
     };
 
-    let serialized = serde_json::to_vec(&mapping).unwrap();
-
-    let cookie_jar = Arc::new(Jar::default());
-
-    let http_client = ClientBuilder::new()
-        .cookie_provider(cookie_jar.clone())
-        .build()
-        .unwrap();
+    let serialized =
+        serde_json::to_vec(&mapping).map_err(|e| format!("cannot serialize mapping: {}", e))?;
 
     let endpoint_url = &config.endpoint_url;
 
-    let upload_endpoint = format!("{}/admin/load_mapping", endpoint_url);
+    println!("upload url: {}/admin/load_mapping", endpoint_url);
 
-    println!("upload url: {}", upload_endpoint);
+    let http_client = build_http_client(&config)?;
 
-    cookie_jar.add_cookie_str(
-        &format!("access_token = {};", access_token).as_str(),
-        &upload_endpoint
-            .parse::<Url>()
-            .expect("failed parsing load mapping url"),
-    );
+    let load_mapping_result: LoadMappingResult = post_api(
+        &http_client,
+        endpoint_url,
+        "/admin/load_mapping",
+        serialized,
+        config.max_retries(),
+    )?;
 
-    match http_client
-        .post(
-            upload_endpoint
-                .parse::<Url>()
-                .expect("failed parsing load mapping url"),
-        )
-        .body(serialized)
-        .send()
-    {
-        Ok(response) => match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().expect("failed fetching response body");
-                let api_response: APIResponse<()> = match serde_json::from_reader(body.reader()) {
-                    Ok(x) => x,
-                    Err(e) => panic!("failed parsing response body {}", e),
-                };
-
-                if api_response.code == 200 {
-                    match update_cache(
-                        &[
-                            CacheType::MissionRawCache,
-                            CacheType::MissionKPIRawCache,
-                            CacheType::GlobalKPIState,
-                        ],
-                        endpoint_url,
-                        &http_client,
-                    ) {
-                        Ok(_) => {
-                            println!("Success. Rock and stone!");
-                        }
-                        Err(e) => {
-                            println!("failed updating cache: {}", e);
-                        }
-                    }
-                } else {
-                    println!(
-                        "Server returned {}: {}",
-                        api_response.code, api_response.message
-                    );
-                }
-            }
-            other => {
-                println!("unexpected status code from server: {}", other);
-                println!("body: {:?}", response.text());
-                panic!("cannot load mapping");
-            }
-        },
-        Err(e) => {
-            println!("failed sending request: {}", e);
-            panic!("cannot load mapping");
-        }
+    // 服务端会在加载mapping后自动在后台重新生成缓存
+    match load_mapping_result.cache_update {
+        CacheUpdateStatus::Scheduled => println!("cache update scheduled on server"),
+        CacheUpdateStatus::Queued => println!("cache update already running on server, queued"),
     }
+
+    println!("Success. Rock and stone!");
+
+    Ok(())
 }
 
 fn parse_mapping_file(file_path: &Path) -> HashMap<String, String> {
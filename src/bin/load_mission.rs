@@ -1,533 +1,272 @@
-use actix_web::web::Buf;
-use encoding_rs::{DecoderResult, UTF_16LE, UTF_8};
+use mission_backend_rs::client::raw_log::{
+    parse_mission_log, parse_mission_log_since, TimestampCheck,
+};
 use mission_backend_rs::client::*;
 use mission_backend_rs::db::mission_log::*;
+use mission_backend_rs::mission::load::LoadResult;
 use mission_backend_rs::mission::APIMission;
-use mission_backend_rs::APIResponse;
-use mission_backend_rs::ClientConfig;
-use regex::Regex;
-use reqwest::blocking::ClientBuilder;
-use reqwest::cookie::Jar;
-use reqwest::{StatusCode, Url};
-use std::env;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Write;
 use std::path::Path;
-use std::path::PathBuf;
-use std::str::FromStr;
-use std::sync::Arc;
+use std::process::ExitCode;
 use std::time;
 
-const MAX_LOG_LENGTH: usize = 64 * 1024 * 1024;
-
-fn main() {
-    author_info();
-    let config_file_path = match env::var("CONFIG_PATH") {
-        Ok(val) => PathBuf::from_str(&val).expect("invalid CONFIG_PATH"),
-        Err(_) => PathBuf::from_str("./config.json").unwrap(),
-    };
-
-    let file_content = match fs::read(&config_file_path) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot read config file {}: {}",
-                config_file_path.as_os_str().to_str().unwrap(),
-                e
-            );
-        }
-    };
-
-    let config: ClientConfig = match serde_json::from_slice(&file_content[..]) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot parse config file {}: {}",
-                config_file_path.as_os_str().to_str().unwrap(),
-                e
-            );
-        }
-    };
-
-    if config.access_token.is_none() {
-        println!("warning: no access token specified!");
-    }
-
-    let access_token = config.access_token.unwrap_or("Rock and stone!".to_string());
-
-    let endpoint_url = config.endpoint_url;
-
-    let upload_url = format!("{}/mission/load_mission", endpoint_url);
-    let mission_list_url = format!("{}/mission/api_mission_list", endpoint_url);
-
-    println!("upload url: {}", upload_url);
-    println!("mission list url: {}", mission_list_url);
-
-    let cookie_jar = Arc::new(Jar::default());
-
-    let http_client = ClientBuilder::new()
-        .cookie_provider(cookie_jar.clone())
-        .build()
-        .unwrap();
-
-    let upload_url = upload_url
-        .parse::<Url>()
-        .expect("failed parsing endpoint url");
-
-    cookie_jar.add_cookie_str(
-        &format!("access_token = {};", access_token).as_str(),
-        &upload_url,
-    );
+// 上次成功上传后本地任务中最大的begin_timestamp，下次运行时仅上传晚于该时间的任务
+#[derive(Serialize, Deserialize)]
+struct LastUploadState {
+    max_begin_timestamp: i64,
+}
 
-    let response: APIResponse<Vec<APIMission>> = match http_client
-        .get(
-            mission_list_url
-                .parse::<Url>()
-                .expect("failed parsing mission list url"),
-        )
-        .send()
-    {
-        Ok(response) => match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().expect("failed fetching response body");
-                match serde_json::from_reader(body.reader()) {
-                    Ok(x) => x,
-                    Err(e) => panic!("failed parsing response body {}", e),
-                }
-            }
-            other => {
-                println!("unexpected status code from server: {}", other);
-                println!("body: {:?}", response.text());
-                panic!("cannot get mission list");
-            }
-        },
-        Err(e) => {
-            println!("failed sending request: {}", e);
-            panic!("cannot get mission list");
-        }
-    };
+fn main() -> ExitCode {
+    exit_with(run())
+}
 
-    let mission_list = response.data.unwrap();
+fn run() -> Result<(), ClientError> {
+    author_info();
 
-    println!("remote mission count: {}", mission_list.len());
+    let config = load_client_config()?;
 
-    let mut mission_timestamp_list = mission_list
-        .iter()
-        .map(|item| item.begin_timestamp)
-        .collect::<Vec<i64>>();
+    let max_retries = config.max_retries();
+    let upload_batch_size = config.upload_batch_size();
+    let timestamp_check = config.timestamp_check();
+    let last_upload_state_path = config.last_upload_state_path();
 
-    mission_timestamp_list.sort_unstable();
+    let endpoint_url = &config.endpoint_url;
 
-    let start = time::Instant::now();
-    let mission_list = parse_mission_log(Path::new("./raw_log")).ok().unwrap();
+    println!("upload url: {}/mission/load_mission", endpoint_url);
     println!(
-        "loaded {} missions in {:?}",
-        mission_list.len(),
-        start.elapsed()
+        "mission list url: {}/mission/api_mission_list",
+        endpoint_url
     );
 
-    let to_upload_mission_list = mission_list
-        .into_iter()
-        .filter(|item| {
-            mission_timestamp_list
-                .binary_search(&item.mission_info.begin_timestamp)
-                .is_err()
-        })
-        .collect::<Vec<LogContent>>();
+    let http_client = build_http_client(&config)?;
 
-    println!("to upload mission count: {}", to_upload_mission_list.len());
+    // --full：忽略本地上传进度，与服务端任务列表完整比对
+    let full_upload = client_args().iter().any(|x| x == "--full");
 
-    let serialized = rmp_serde::to_vec(&to_upload_mission_list).unwrap();
-
-    let compressed = compress(&serialized);
-
-    println!("sending request and waiting for mission loading..");
-    match http_client.post(upload_url).body(compressed).send() {
-        Ok(response) => match response.status() {
-            StatusCode::OK => {
-                match update_cache(
-                    &[
-                        CacheType::MissionRawCache,
-                        CacheType::MissionKPIRawCache,
-                        CacheType::GlobalKPIState,
-                    ],
-                    &endpoint_url,
-                    &http_client,
-                ) {
-                    Ok(_) => {
-                        println!("Success. Rock and stone!");
-                    }
-                    Err(e) => {
-                        println!("failed updating cache: {}", e);
-                    }
-                }
-            }
-            other => {
-                println!("unexpected status code from server: {}", other);
-                println!("body: {:?}", response.text());
+    let last_upload_timestamp = match full_upload {
+        true => None,
+        false => {
+            let x = load_last_upload_timestamp(&last_upload_state_path);
+            if x.is_none() {
+                println!("no local upload state found, falling back to full diff");
             }
-        },
-        Err(e) => {
-            println!("failed sending request: {}", e);
+            x
         }
-    }
-}
-
-fn compress(data: &[u8]) -> Vec<u8> {
-    println!("Serialized len = {}", format_size(data.len()));
-
-    let compressed = Vec::with_capacity(data.len());
+    };
 
     let start = time::Instant::now();
 
-    let mut encoder = zstd::Encoder::new(compressed, 15).unwrap();
-
-    encoder.write_all(&data).unwrap();
-    let mut compressed = encoder.finish().unwrap();
-
-    let finish = time::Instant::now();
-
-    println!(
-        "Compressed using zstd, compressed len = {} with level 15, time: {:?}",
-        format_size(compressed.len()),
-        finish.duration_since(start)
-    );
-
-    compressed.shrink_to_fit();
-    compressed
-}
-
-fn get_log_file_list(base_path: &Path) -> Vec<PathBuf> {
-    let re = Regex::new("MissionMonitor_([0-9]+).txt").unwrap();
-    std::fs::read_dir(base_path)
-        .unwrap()
-        .into_iter()
-        .filter(|r| {
-            re.is_match(
-                r.as_ref()
-                    .unwrap()
-                    .file_name()
-                    .as_os_str()
-                    .to_str()
-                    .unwrap(),
-            )
-        })
-        .map(|r| r.unwrap().path())
-        .collect()
-}
-
-fn parse_mission_log(base_path: &Path) -> Result<Vec<LogContent>, String> {
-    let file_path_list = get_log_file_list(base_path);
-
-    let mut parsed_mission_list = Vec::new();
-    for file_path in file_path_list {
-        parsed_mission_list.push(get_file_content_parted(&file_path).map_err(|e| {
-            format!(
-                "cannot parse log: {}: {}",
-                &file_path.as_os_str().to_str().unwrap(),
-                e
-            )
-        })?);
-    }
+    // 本次处理的本地任务中最大的begin_timestamp，忽略时钟异常的任务，避免超前的时间戳导致之后的任务被跳过
+    let mut local_max_timestamp = last_upload_timestamp;
 
-    parsed_mission_list.sort_unstable_by(|a, b| {
-        a.mission_info
-            .begin_timestamp
-            .cmp(&b.mission_info.begin_timestamp)
-    });
+    let to_upload_mission_list = match last_upload_timestamp {
+        Some(since) => {
+            println!("uploading missions after {}", since);
 
-    let mut deep_dive_mission_list = Vec::new();
+            let mission_list =
+                parse_mission_log_since(Path::new("./raw_log"), &timestamp_check, since)
+                    .map_err(|e| format!("cannot parse mission log: {}", e))?;
+            println!(
+                "loaded {} missions in {:?}",
+                mission_list.len(),
+                start.elapsed()
+            );
 
-    for mission in &parsed_mission_list {
-        let first_player_join_time = mission
-            .player_info
-            .iter()
-            .map(|p| p.join_mission_time)
-            .min()
-            .unwrap();
+            local_max_timestamp =
+                max_valid_timestamp(&mission_list, &timestamp_check).max(local_max_timestamp);
 
-        if first_player_join_time > 0 {
-            deep_dive_mission_list.push(mission.mission_info.begin_timestamp);
+            mission_list
         }
-    }
-
-    for i in 0..parsed_mission_list.len() {
-        let list_ptr = parsed_mission_list.as_mut_ptr();
-
-        // SAFETY: 0 <= i < parsed_mission_list.len()
-
-        let current_mission = unsafe { &mut *list_ptr.add(i) };
+        None => {
+            let mission_timestamp_list =
+                fetch_remote_timestamp_list(&http_client, endpoint_url, max_retries)?;
+
+            let mission_list = parse_mission_log(Path::new("./raw_log"), &timestamp_check)
+                .map_err(|e| format!("cannot parse mission log: {}", e))?;
+            println!(
+                "loaded {} missions in {:?}",
+                mission_list.len(),
+                start.elapsed()
+            );
 
-        let prev_mission = match i {
-            0 => None,
-            // SAFETY:
-            // 1. 0 <= x - 1 < parsed_mission_list.len()
-            // 2. x - 1 = i - 1 != i
-            x => unsafe { Some(&mut *list_ptr.add(x - 1)) },
-        };
+            local_max_timestamp = max_valid_timestamp(&mission_list, &timestamp_check);
 
-        // 对于深潜，第一层对应的first_player_join_time为0，而二、三层不为0
-        // 对于普通深潜，每一层的难度都显示为0.75（3）
-        if deep_dive_mission_list
-            .binary_search(&current_mission.mission_info.begin_timestamp)
-            .is_ok()
-        {
-            // 若当前任务first_player_join_time不为0，但前一任务为0，说明当前是第二层，前一任务是第一层
-            // 若当前任务first_player_join_time不为0，前一任务也不为0，说明当前是第三层，前一任务是第二层
-            // 注：除非在第一层手动放弃任务，否则不论第二层是否胜利，都会有第二层的数据
-            // 若在第一层手动放弃任务，则第一层无法识别为深潜
-            if let Some(prev_mission) = prev_mission {
-                match deep_dive_mission_list
-                    .binary_search(&prev_mission.mission_info.begin_timestamp)
-                {
-                    Ok(_) => {
-                        // 前一层是第二层，当前是第三层
-                        if prev_mission.mission_info.hazard_id == 3
-                            || prev_mission.mission_info.hazard_id == 101
-                        {
-                            // 普通深潜
-                            prev_mission.mission_info.hazard_id = 101;
-                            current_mission.mission_info.hazard_id = 102;
-                        } else {
-                            // 精英深潜
-                            prev_mission.mission_info.hazard_id = 104;
-                            current_mission.mission_info.hazard_id = 105;
-                        }
-                    }
-                    Err(_) => {
-                        // 前一层是第一层，当前是第二层
-                        if prev_mission.mission_info.hazard_id == 3
-                            || prev_mission.mission_info.hazard_id == 100
-                        {
-                            // 普通深潜
-                            prev_mission.mission_info.hazard_id = 100;
-                            current_mission.mission_info.hazard_id = 101;
-                        } else {
-                            // 精英深潜
-                            prev_mission.mission_info.hazard_id = 103;
-                            current_mission.mission_info.hazard_id = 104;
-                        }
-                    }
-                }
-            }
+            mission_list
+                .into_iter()
+                .filter(|item| {
+                    mission_timestamp_list
+                        .binary_search(&item.mission_info.begin_timestamp)
+                        .is_err()
+                })
+                .collect::<Vec<LogContent>>()
         }
-    }
-
-    Ok(parsed_mission_list)
-}
-
-fn get_file_content_parted(file_path: &Path) -> Result<LogContent, Box<dyn std::error::Error>> {
-    let raw_file_content = std::fs::read(file_path)?;
-
-    let mut file_content = String::with_capacity(MAX_LOG_LENGTH);
+    };
 
-    if raw_file_content[0] == 0xFF && raw_file_content[1] == 0xFE {
-        // UTF-16-LE
-        let mut decoder = UTF_16LE.new_decoder();
+    println!("to upload mission count: {}", to_upload_mission_list.len());
 
-        let (result, _) = decoder.decode_to_string_without_replacement(
-            &raw_file_content,
-            &mut file_content,
-            false,
-        );
-        if let DecoderResult::Malformed(_, _) = result {
-            panic!(
-                "Cannot decode input: {} with UTF-16-LE",
-                file_path.file_name().unwrap().to_str().unwrap()
-            );
-        }
-    } else {
-        let mut decoder = UTF_8.new_decoder();
-        let (result, _) = decoder.decode_to_string_without_replacement(
-            &raw_file_content,
-            &mut file_content,
-            true,
-        );
-        if let DecoderResult::Malformed(_, _) = result {
-            panic!(
-                "Cannot decode input: {} with UTF-8",
-                file_path.file_name().unwrap().to_str().unwrap()
-            );
-        }
+    if to_upload_mission_list.is_empty() {
+        save_last_upload_timestamp(&last_upload_state_path, local_max_timestamp);
+        println!("nothing to upload. Rock and stone!");
+        return Ok(());
     }
 
-    file_content.shrink_to_fit();
+    // 分批上传，避免单个请求过大导致超时或服务端内存不足；
+    // 服务端按begin_timestamp跳过已加载的任务，中断后重新运行即可继续
+    let batch_count = to_upload_mission_list.len().div_ceil(upload_batch_size);
 
-    let file_part_list = file_content.split("______").collect::<Vec<&str>>();
-
-    let mission_info = LogMissionInfo::try_from(file_content.as_str())
-        .map_err(|e| format!("load mission info: {}", e))?;
-
-    let player_info_part = file_part_list[1];
-
-    let mut player_info: Vec<LogPlayerInfo> = Vec::new();
+    println!(
+        "uploading in {} batches of at most {} missions",
+        batch_count, upload_batch_size
+    );
 
-    for player_info_line in player_info_part.lines() {
-        if player_info_line.trim().len() == 0 {
-            continue;
-        }
-        player_info.push(
-            player_info_line
-                .try_into()
-                .map_err(|e| format!("load player info: {}", e))?,
+    for (batch_idx, batch) in to_upload_mission_list.chunks(upload_batch_size).enumerate() {
+        println!(
+            "[{}/{}] uploading {} missions ({} - {})",
+            batch_idx + 1,
+            batch_count,
+            batch.len(),
+            batch.first().unwrap().mission_info.begin_timestamp,
+            batch.last().unwrap().mission_info.begin_timestamp
         );
-    }
 
-    let damage_info_part = file_part_list[2];
-
-    let mut damage_info: Vec<LogDamageInfo> = Vec::new();
+        let serialized =
+            rmp_serde::to_vec(batch).map_err(|e| format!("cannot serialize missions: {}", e))?;
+
+        let compressed = compress(&serialized)?;
+
+        let load_result: LoadResult = match post_zstd_api(
+            &http_client,
+            endpoint_url,
+            "/mission/load_mission",
+            compressed,
+            max_retries,
+        ) {
+            Ok(x) => x,
+            Err(e) => {
+                println!("[{}/{}] {}", batch_idx + 1, batch_count, e);
+                println!("upload aborted, run again to resume from the failed batch");
+                return Err(e);
+            }
+        };
 
-    for damage_info_line in damage_info_part.lines() {
-        if damage_info_line.trim().len() == 0 {
-            continue;
-        }
-        damage_info.push(
-            damage_info_line
-                .try_into()
-                .map_err(|e| format!("load player info: {}", e))?,
+        println!(
+            "[{}/{}] loaded: {}, skipped: {}, decode time: {}, load time: {}",
+            batch_idx + 1,
+            batch_count,
+            load_result.load_count,
+            load_result.skip_count,
+            load_result.decode_time,
+            load_result.load_time
         );
+        for warning in &load_result.warnings {
+            println!("[{}/{}] warning: {}", batch_idx + 1, batch_count, warning);
+        }
     }
 
-    let mut range_begin_idx: usize = 0;
-    let mut range_begin_item = &damage_info[range_begin_idx];
-
-    let mut combined_damage_info: Vec<LogDamageInfo> = Vec::with_capacity(damage_info.len());
-
-    for (i, current_damage_info) in damage_info.iter().enumerate() {
-        if !current_damage_info.combine_eq(range_begin_item) {
-            let range_end_idx = i;
+    save_last_upload_timestamp(&last_upload_state_path, local_max_timestamp);
+
+    // 服务端加载任务时已为新任务生成mission_raw缓存，这里只需更新KPI相关缓存
+    match update_cache(
+        &[CacheType::MissionKPIRawCache, CacheType::GlobalKPIState],
+        endpoint_url,
+        &http_client,
+        max_retries,
+    ) {
+        Ok(_) => println!("Success. Rock and stone!"),
+        Err(e) => println!("failed updating cache: {}", e),
+    }
 
-            let damage_sum = damage_info[range_begin_idx..range_end_idx]
-                .iter()
-                .map(|item| item.damage)
-                .sum::<f64>();
+    Ok(())
+}
 
-            combined_damage_info.push(LogDamageInfo {
-                mission_time: range_begin_item.mission_time,
-                damage: damage_sum,
-                taker: range_begin_item.taker.clone(),
-                causer: range_begin_item.causer.clone(),
-                weapon: range_begin_item.weapon.clone(),
-                causer_type: range_begin_item.causer_type,
-                taker_type: range_begin_item.taker_type,
-            });
+fn fetch_remote_timestamp_list(
+    http_client: &Client,
+    endpoint_url: &str,
+    max_retries: u32,
+) -> Result<Vec<i64>, ClientError> {
+    let mission_list: Vec<APIMission> = fetch_api(
+        http_client,
+        endpoint_url,
+        "/mission/api_mission_list",
+        max_retries,
+    )?;
 
-            range_begin_idx = i;
-            range_begin_item = &damage_info[range_begin_idx];
-        }
-    }
-
-    let range_end_idx = damage_info.len();
+    println!("remote mission count: {}", mission_list.len());
 
-    let damage_sum = damage_info[range_begin_idx..range_end_idx]
+    let mut mission_timestamp_list = mission_list
         .iter()
-        .map(|item| item.damage)
-        .sum::<f64>();
-
-    combined_damage_info.push(LogDamageInfo {
-        mission_time: range_begin_item.mission_time,
-        damage: damage_sum,
-        taker: range_begin_item.taker.clone(),
-        causer: range_begin_item.causer.clone(),
-        weapon: range_begin_item.weapon.clone(),
-        causer_type: range_begin_item.causer_type,
-        taker_type: range_begin_item.taker_type,
-    });
-
-    let kill_info_part = file_part_list[3];
-
-    let mut kill_info: Vec<LogKillInfo> = Vec::new();
-
-    for kill_info_line in kill_info_part.lines() {
-        if kill_info_line.trim().len() == 0 {
-            continue;
-        }
-        kill_info.push(
-            kill_info_line
-                .try_into()
-                .map_err(|e| format!("load kill info: {}", e))?,
-        );
-    }
+        .map(|item| item.begin_timestamp)
+        .collect::<Vec<i64>>();
 
-    let resource_info_part = file_part_list[4];
+    mission_timestamp_list.sort_unstable();
 
-    let mut resource_info: Vec<LogResourceInfo> = Vec::new();
+    Ok(mission_timestamp_list)
+}
 
-    for resource_info_line in resource_info_part.lines() {
-        if resource_info_line.trim().len() == 0 {
-            continue;
-        }
-        resource_info.push(
-            resource_info_line
-                .try_into()
-                .map_err(|e| format!("load resource info: {}", e))?,
-        );
-    }
+fn max_valid_timestamp(
+    mission_list: &[LogContent],
+    timestamp_check: &TimestampCheck,
+) -> Option<i64> {
+    mission_list
+        .iter()
+        .map(|mission| mission.mission_info.begin_timestamp)
+        .filter(|&begin_timestamp| !timestamp_check.is_skewed(begin_timestamp))
+        .max()
+}
 
-    let supply_info_part = file_part_list[5];
-    let mut supply_info: Vec<LogSupplyInfo> = Vec::new();
+fn load_last_upload_timestamp(state_path: &Path) -> Option<i64> {
+    let content = fs::read(state_path).ok()?;
 
-    for supply_info_line in supply_info_part.lines() {
-        if supply_info_line.trim().len() == 0 {
-            continue;
+    match serde_json::from_slice::<LastUploadState>(&content) {
+        Ok(x) => Some(x.max_begin_timestamp),
+        Err(e) => {
+            println!("cannot parse {}: {}", state_path.to_string_lossy(), e);
+            None
         }
-        supply_info.push(
-            supply_info_line
-                .try_into()
-                .map_err(|e| format!("load supply info: {}", e))?,
-        );
     }
+}
 
-    let mission_time = mission_info.mission_time;
+fn save_last_upload_timestamp(state_path: &Path, max_begin_timestamp: Option<i64>) {
+    let max_begin_timestamp = match max_begin_timestamp {
+        Some(x) => x,
+        None => return,
+    };
 
-    // Fix total present time
+    let content = serde_json::to_vec(&LastUploadState {
+        max_begin_timestamp,
+    })
+    .unwrap();
 
-    for current_player_info in &mut player_info {
-        if current_player_info.total_present_time == 0 {
-            current_player_info.total_present_time = mission_time;
-        }
+    if let Err(e) = write_state_file(state_path, &content) {
+        println!("{}", e);
     }
+}
 
-    let first_player_join_time = player_info
-        .iter()
-        .map(|player| player.join_mission_time)
-        .min()
-        .ok_or(String::from("player count is 0"))?;
+fn compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    println!("Serialized len = {}", format_size(data.len()));
 
-    // Fix time for damage info, killed info, resource info, supply info
+    let compressed = Vec::with_capacity(data.len());
 
-    for current_damage_info in &mut damage_info {
-        current_damage_info.mission_time -= first_player_join_time;
-    }
+    let start = time::Instant::now();
 
-    for current_kill_info in &mut kill_info {
-        current_kill_info.mission_time -= first_player_join_time;
-    }
+    let compress_error = |e: std::io::Error| format!("cannot compress missions: {}", e);
 
-    for current_resource_info in &mut resource_info {
-        current_resource_info.mission_time -= first_player_join_time;
-    }
+    let mut encoder = zstd::Encoder::new(compressed, 15).map_err(compress_error)?;
 
-    for current_supply_info in &mut supply_info {
-        current_supply_info.mission_time -= first_player_join_time;
-    }
+    encoder.write_all(data).map_err(compress_error)?;
+    let mut compressed = encoder.finish().map_err(compress_error)?;
 
-    Ok(LogContent {
-        mission_info,
-        player_info,
-        damage_info: combined_damage_info,
-        kill_info,
-        resource_info,
-        supply_info,
-    })
+    let finish = time::Instant::now();
 
-    // Identify Deep Dive in get_mission_list
-}
+    println!(
+        "Compressed using zstd, compressed len = {} with level 15, time: {:?}",
+        format_size(compressed.len()),
+        finish.duration_since(start)
+    );
 
-fn format_size(size: usize) -> String {
-    match size {
-        0..1024 => format!("{}B", size),
-        1024..1048576 => format!("{:.2}KiB", size as f64 / 1024.0),
-        1048576.. => format!("{:.2}MiB", size as f64 / (1024.0 * 1024.0)),
-    }
+    compressed.shrink_to_fit();
+    Ok(compressed)
 }
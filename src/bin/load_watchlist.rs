@@ -1,113 +1,76 @@
-use actix_web::web::Buf;
+use mission_backend_rs::admin::WatchlistDiff;
 use mission_backend_rs::client::*;
-use mission_backend_rs::APIResponse;
-use mission_backend_rs::ClientConfig;
-use reqwest::{blocking::ClientBuilder, cookie::Jar, StatusCode, Url};
-use std::env;
 use std::fs;
 use std::path::PathBuf;
-use std::str::FromStr;
-use std::sync::Arc;
-fn main() {
-    author_info();
-    let config_file_path = match env::var("CONFIG_PATH") {
-        Ok(val) => PathBuf::from_str(&val).expect("invalid CONFIG_PATH"),
-        Err(_) => PathBuf::from_str("./config.json").unwrap(),
-    };
-
-    let file_content = match fs::read(&config_file_path) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot read config file {}: {}",
-                config_file_path.to_string_lossy(),
-                e
-            );
-        }
-    };
+use std::process::ExitCode;
 
-    let config: ClientConfig = match serde_json::from_slice(&file_content[..]) {
-        Ok(val) => val,
-        Err(e) => {
-            panic!(
-                "cannot parse config file {}: {}",
-                config_file_path.to_string_lossy(),
-                e
-            );
-        }
-    };
+fn main() -> ExitCode {
+    exit_with(run())
+}
 
-    if config.access_token.is_none() {
-        println!("warning: no access token specified!");
-    }
+fn run() -> Result<(), ClientError> {
+    author_info();
 
-    let access_token = config.access_token.unwrap_or("Rock and stone!".to_string());
+    let config = load_client_config()?;
 
-    let watchlist_path =
-        PathBuf::from_str(&config.watchlist_path.unwrap_or("./watchlist.txt".into()))
-            .expect("invalid watchlist path");
+    let watchlist_path = PathBuf::from(
+        config
+            .watchlist_path
+            .as_deref()
+            .unwrap_or("./watchlist.txt"),
+    );
 
-    let file_content = match fs::read_to_string(&watchlist_path) {
-        Ok(x) => x,
-        Err(e) => {
-            panic!(
-                "cannot read watchlist file {}: {}",
-                watchlist_path.to_string_lossy(),
-                e
-            );
-        }
-    };
+    let file_content = fs::read_to_string(&watchlist_path).map_err(|e| {
+        format!(
+            "cannot read watchlist file {}: {}",
+            watchlist_path.to_string_lossy(),
+            e
+        )
+    })?;
 
     let watchlist = file_content.lines().collect::<Vec<_>>();
 
-    let serialized = serde_json::to_vec(&watchlist).unwrap();
+    let serialized =
+        serde_json::to_vec(&watchlist).map_err(|e| format!("cannot serialize watchlist: {}", e))?;
 
-    let cookie_jar = Arc::new(Jar::default());
+    // 仅预览关注列表的变更，不修改服务端数据
+    let dry_run = client_args().iter().any(|x| x == "--dry-run");
 
-    let upload_url = format!("{}/admin/load_watchlist", config.endpoint_url);
+    let upload_path = match dry_run {
+        true => "/admin/load_watchlist?dry_run=true",
+        false => "/admin/load_watchlist",
+    };
 
-    println!("upload url: {}", upload_url);
+    println!("upload url: {}{}", config.endpoint_url, upload_path);
 
-    let http_client = ClientBuilder::new()
-        .cookie_provider(cookie_jar.clone())
-        .build()
-        .unwrap();
+    let http_client = build_http_client(&config)?;
 
-    let upload_url = upload_url
-        .parse::<Url>()
-        .expect("failed parsing endpoint url");
+    let result: WatchlistDiff = post_api(
+        &http_client,
+        &config.endpoint_url,
+        upload_path,
+        serialized,
+        config.max_retries(),
+    )?;
 
-    cookie_jar.add_cookie_str(
-        &format!("access_token = {};", access_token).as_str(),
-        &upload_url,
+    for player_name in &result.added {
+        println!("+ {}", player_name);
+    }
+    for player_name in &result.removed {
+        println!("- {}", player_name);
+    }
+    println!(
+        "added: {}, removed: {}, unchanged: {}",
+        result.added.len(),
+        result.removed.len(),
+        result.unchanged.len()
     );
 
-    match http_client.post(upload_url).body(serialized).send() {
-        Ok(response) => match response.status() {
-            StatusCode::OK => {
-                let body = response.bytes().expect("failed fetching response body");
-                let api_response: APIResponse<()> = match serde_json::from_reader(body.reader()) {
-                    Ok(x) => x,
-                    Err(e) => panic!("failed parsing response body {}", e),
-                };
+    if dry_run {
+        println!("dry run, watchlist is not changed");
+    } else {
+        println!("Success. Rock and stone!");
+    }
 
-                if api_response.code == 200 {
-                    println!("Success. Rock and stone!");
-                } else {
-                    panic!(
-                        "Server returned {}: {}",
-                        api_response.code, api_response.message
-                    );
-                }
-            }
-            other => {
-                println!("unexpected status code from server: {}", other);
-                println!("body: {:?}", response.text());
-                panic!("cannot load watchlist");
-            }
-        },
-        Err(e) => {
-            panic!("failed sending request: {}", e);
-        }
-    };
+    Ok(())
 }
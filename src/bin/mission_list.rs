@@ -0,0 +1,242 @@
+use mission_backend_rs::admin::mission_invalid::MissionInvalidDetail;
+use mission_backend_rs::client::*;
+use mission_backend_rs::mission::{APIMission, MissionList};
+use mission_backend_rs::ClientConfig;
+use reqwest::blocking::Client;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Serialize)]
+struct MissionCsvRow<'a> {
+    mission_id: i32,
+    begin_timestamp: i64,
+    mission_type: &'a str,
+    hazard_id: i16,
+    result: i16,
+    mission_time: i16,
+}
+
+const USAGE: &str = "usage: mission_list [--since <time>] [--until <time>] [--limit <n>] [--csv <path>] [--invalid] [--output <human|json>]";
+
+#[derive(Default)]
+struct MissionListArgs {
+    // 写入CSV文件而不是输出到stdout
+    csv_path: Option<PathBuf>,
+    // 仅保留最近的N个任务
+    entry_limit: Option<usize>,
+    // 按begin_timestamp筛选，包含两端
+    since: Option<i64>,
+    until: Option<i64>,
+    // 仅列出无效任务及其原因
+    invalid_only: bool,
+}
+
+impl MissionListArgs {
+    // `--output`、`--json`由OutputFormat::from_args处理，这里跳过
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = MissionListArgs::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--csv" => {
+                    let value = args.next().ok_or("missing value for --csv")?;
+                    result.csv_path = Some(PathBuf::from(value));
+                }
+                "--limit" => {
+                    let value = args.next().ok_or("missing value for --limit")?;
+                    result.entry_limit = Some(
+                        value
+                            .parse()
+                            .map_err(|e| format!("invalid value for --limit: {}: {}", value, e))?,
+                    );
+                }
+                "--since" => {
+                    let value = args.next().ok_or("missing value for --since")?;
+                    result.since = Some(parse_timestamp(&value)?);
+                }
+                "--until" => {
+                    let value = args.next().ok_or("missing value for --until")?;
+                    result.until = Some(parse_timestamp(&value)?);
+                }
+                "--invalid" => result.invalid_only = true,
+                "--json" => {}
+                "--output" => {
+                    args.next();
+                }
+                x if x.starts_with("--output=") => {}
+                x => return Err(format!("unknown argument: {}\n{}", x, USAGE)),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    let output_format = OutputFormat::from_args(client_args().into_iter())?;
+    let args = MissionListArgs::from_args(client_args().into_iter())?;
+
+    if output_format == OutputFormat::Human && args.csv_path.is_none() {
+        author_info();
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    if args.invalid_only {
+        let invalid_list = fetch_mission_invalid_detailed(&http_client, &config)?;
+
+        if output_format.write_json(&invalid_list)? {
+            return Ok(());
+        }
+
+        print_mission_invalid_list(&invalid_list);
+
+        return Ok(());
+    }
+
+    let mut mission_list: Vec<APIMission> = fetch_api(
+        &http_client,
+        &config.endpoint_url,
+        "/mission/api_mission_list",
+        config.max_retries(),
+    )?;
+
+    mission_list.retain(|mission| {
+        args.since.is_none_or(|x| mission.begin_timestamp >= x)
+            && args.until.is_none_or(|x| mission.begin_timestamp <= x)
+    });
+
+    // 最新的任务排在最前
+    mission_list.sort_unstable_by_key(|x| Reverse(x.begin_timestamp));
+
+    if let Some(entry_limit) = args.entry_limit {
+        mission_list.truncate(entry_limit);
+    }
+
+    if let Some(csv_path) = &args.csv_path {
+        write_csv(csv_path, &mission_list)?;
+        println!(
+            "written {} missions to {}",
+            mission_list.len(),
+            csv_path.to_string_lossy()
+        );
+        return Ok(());
+    }
+
+    if output_format.write_json(&mission_list)? {
+        return Ok(());
+    }
+
+    print_mission_list(&mission_list);
+
+    Ok(())
+}
+
+fn write_csv(csv_path: &Path, mission_list: &[APIMission]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(csv_path)
+        .map_err(|e| format!("cannot create {}: {}", csv_path.to_string_lossy(), e))?;
+
+    for mission in mission_list {
+        writer
+            .serialize(MissionCsvRow {
+                mission_id: mission.id,
+                begin_timestamp: mission.begin_timestamp,
+                mission_type: &mission.mission_type,
+                hazard_id: mission.hazard_id,
+                result: mission.result,
+                mission_time: mission.mission_time,
+            })
+            .map_err(|e| format!("cannot write csv row: {}", e))?;
+    }
+
+    writer
+        .flush()
+        .map_err(|e| format!("cannot write {}: {}", csv_path.to_string_lossy(), e))
+}
+
+fn print_mission_list(mission_list: &[APIMission]) {
+    println!(
+        "{:>8} {:<20} {:<24} {:>6} {:<8} {:>8}",
+        "id", "begin", "type", "hazard", "result", "time"
+    );
+
+    for mission in mission_list {
+        println!(
+            "{:>8} {:<20} {:<24} {:>6} {:<8} {:>8}",
+            mission.id,
+            format_timestamp(mission.begin_timestamp),
+            mission.mission_type,
+            mission.hazard_id,
+            format_mission_result(mission.result),
+            format_mission_time(mission.mission_time)
+        );
+    }
+
+    println!("{} missions", mission_list.len());
+}
+
+// 优先使用服务端关联好的结果，旧版本服务端回退到从任务列表中筛选
+fn fetch_mission_invalid_detailed(
+    http_client: &Client,
+    config: &ClientConfig,
+) -> Result<Vec<MissionInvalidDetail>, ClientError> {
+    if let Some(x) = fetch_api_if_supported(
+        http_client,
+        &config.endpoint_url,
+        "/admin/mission_invalid_detailed",
+        config.max_retries(),
+    )? {
+        return Ok(x);
+    }
+
+    let mission_list: MissionList = fetch_api(
+        http_client,
+        &config.endpoint_url,
+        "/mission/mission_list",
+        config.max_retries(),
+    )?;
+
+    let mut result = mission_list
+        .mission_info
+        .into_iter()
+        .filter(|mission| mission.mission_invalid)
+        .map(|mission| MissionInvalidDetail {
+            mission_id: mission.mission_id,
+            begin_timestamp: mission.begin_timestamp,
+            mission_type: mission.mission_type_id,
+            hazard_id: mission.hazard_id,
+            reason: mission.mission_invalid_reason,
+        })
+        .collect::<Vec<_>>();
+
+    result.sort_unstable_by_key(|x| Reverse((x.begin_timestamp, x.mission_id)));
+
+    Ok(result)
+}
+
+fn print_mission_invalid_list(invalid_list: &[MissionInvalidDetail]) {
+    println!(
+        "{:>8} {:<20} {:<24} {:>6} reason",
+        "id", "begin", "type", "hazard"
+    );
+
+    for mission in invalid_list {
+        println!(
+            "{:>8} {:<20} {:<24} {:>6} {}",
+            mission.mission_id,
+            format_timestamp(mission.begin_timestamp),
+            mission.mission_type,
+            mission.hazard_id,
+            mission.reason
+        );
+    }
+
+    println!("{} invalid missions", invalid_list.len());
+}
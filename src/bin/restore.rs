@@ -0,0 +1,272 @@
+use mission_backend_rs::admin::mission_invalid::{APISetMissionInvalid, SetMissionInvalidResult};
+use mission_backend_rs::admin::{LoadMappingResult, WatchlistDiff};
+use mission_backend_rs::client::backup::*;
+use mission_backend_rs::client::*;
+use mission_backend_rs::mission::load::LoadResult;
+use mission_backend_rs::mission::APIMission;
+use mission_backend_rs::KPI_VERSION;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let force = client_args().iter().any(|x| x == "--force");
+
+    let backup_dir = match client_args().into_iter().find(|x| x != "--force") {
+        Some(x) => PathBuf::from(x),
+        None => {
+            return Err(ClientError::Other(
+                "usage: restore <dir> [--force]".to_string(),
+            ))
+        }
+    };
+
+    let manifest = BackupManifest::load(&backup_dir)?;
+
+    println!(
+        "backup of {} created at {} by version {}",
+        manifest.endpoint_url,
+        chrono::DateTime::from_timestamp(manifest.created_at, 0)
+            .map(|x| x.to_rfc3339())
+            .unwrap_or(manifest.created_at.to_string()),
+        manifest.tool_version
+    );
+    println!(
+        "missions: {}, watchlist: {}, kpi config: {}",
+        manifest.mission_count, manifest.watchlist_count, manifest.has_kpi_config
+    );
+
+    if manifest.kpi_version != KPI_VERSION {
+        println!(
+            "warning: backup was created with KPI version {}, current version is {}",
+            manifest.kpi_version, KPI_VERSION
+        );
+    }
+
+    // 上传前先校验所有文件，避免恢复到一半才发现备份损坏
+    let mapping = read_file(&backup_dir, MAPPING_FILE)?;
+
+    let watchlist = read_file(&backup_dir, WATCHLIST_FILE)?;
+    let watchlist_count = serde_json::from_slice::<Vec<String>>(&watchlist)
+        .map_err(|e| format!("cannot parse {}: {}", WATCHLIST_FILE, e))?
+        .len();
+    if watchlist_count != manifest.watchlist_count {
+        return Err(ClientError::Other(format!(
+            "watchlist count mismatch: manifest {}, file {}",
+            manifest.watchlist_count, watchlist_count
+        )));
+    }
+
+    let kpi_config = match manifest.has_kpi_config {
+        true => Some(read_file(&backup_dir, KPI_CONFIG_FILE)?),
+        false => None,
+    };
+
+    let mission_invalid_list = match manifest.mission_invalid_count {
+        Some(expected_count) => {
+            let mission_invalid_list = serde_json::from_slice::<Vec<BackupMissionInvalid>>(
+                &read_file(&backup_dir, MISSION_INVALID_FILE)?,
+            )
+            .map_err(|e| format!("cannot parse {}: {}", MISSION_INVALID_FILE, e))?;
+            if mission_invalid_list.len() != expected_count {
+                return Err(ClientError::Other(format!(
+                    "invalid mission count mismatch: manifest {}, file {}",
+                    expected_count,
+                    mission_invalid_list.len()
+                )));
+            }
+            mission_invalid_list
+        }
+        None => {
+            println!("warning: backup does not contain invalid mission marks");
+            Vec::new()
+        }
+    };
+
+    let mission_list = decode_mission_dump(&read_file(&backup_dir, MISSION_FILE)?)?;
+    if mission_list.len() != manifest.mission_count {
+        return Err(ClientError::Other(format!(
+            "mission count mismatch: manifest {}, file {}",
+            manifest.mission_count,
+            mission_list.len()
+        )));
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+    let endpoint_url = &config.endpoint_url;
+    let max_retries = config.max_retries();
+    let upload_batch_size = config.upload_batch_size();
+
+    if kpi_config.is_some() {
+        check_kpi_version(&http_client, endpoint_url, max_retries, force)?;
+    }
+
+    println!("restoring to {}", endpoint_url);
+
+    let _: LoadMappingResult = post_api(
+        &http_client,
+        endpoint_url,
+        "/admin/load_mapping",
+        mapping,
+        max_retries,
+    )?;
+    println!("restored mapping");
+
+    let watchlist_result: WatchlistDiff = post_api(
+        &http_client,
+        endpoint_url,
+        "/admin/load_watchlist",
+        watchlist,
+        max_retries,
+    )?;
+    println!(
+        "restored watchlist: {} added, {} removed",
+        watchlist_result.added.len(),
+        watchlist_result.removed.len()
+    );
+
+    if let Some(kpi_config) = kpi_config {
+        post_api::<()>(
+            &http_client,
+            endpoint_url,
+            "/admin/load_kpi",
+            kpi_config,
+            max_retries,
+        )?;
+        println!("restored kpi config");
+    }
+
+    // 服务端按begin_timestamp跳过已存在的任务，中断后重新运行即可继续
+    let batch_count = mission_list.len().div_ceil(upload_batch_size);
+    let mut load_count = 0;
+    let mut skip_count = 0;
+
+    for (batch_idx, batch) in mission_list.chunks(upload_batch_size).enumerate() {
+        let serialized =
+            rmp_serde::to_vec(batch).map_err(|e| format!("cannot serialize missions: {}", e))?;
+        let compressed = zstd::encode_all(&serialized[..], 15)
+            .map_err(|e| format!("cannot compress missions: {}", e))?;
+
+        let load_result: LoadResult = post_zstd_api(
+            &http_client,
+            endpoint_url,
+            "/mission/load_mission",
+            compressed,
+            max_retries,
+        )
+        .map_err(|e| {
+            format!(
+                "[{}/{}] {}, run again to resume",
+                batch_idx + 1,
+                batch_count,
+                e
+            )
+        })?;
+
+        load_count += load_result.load_count;
+        skip_count += load_result.skip_count;
+
+        println!(
+            "[{}/{}] loaded: {}, skipped: {}",
+            batch_idx + 1,
+            batch_count,
+            load_result.load_count,
+            load_result.skip_count
+        );
+        for warning in &load_result.warnings {
+            println!("[{}/{}] warning: {}", batch_idx + 1, batch_count, warning);
+        }
+    }
+
+    println!(
+        "restored missions: {} loaded, {} skipped",
+        load_count, skip_count
+    );
+
+    let remote_mission_list: Vec<APIMission> = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/mission/api_mission_list",
+        max_retries,
+    )?;
+
+    // 任务id在恢复后可能不同，按begin_timestamp找到对应任务后重新标记，同时恢复手动标记的原因
+    if !mission_invalid_list.is_empty() {
+        let begin_timestamp_to_id = remote_mission_list
+            .iter()
+            .map(|mission| (mission.begin_timestamp, mission.id))
+            .collect::<HashMap<_, _>>();
+
+        let mut request_list = Vec::with_capacity(mission_invalid_list.len());
+        for item in mission_invalid_list {
+            match begin_timestamp_to_id.get(&item.begin_timestamp) {
+                Some(&mission_id) => request_list.push(APISetMissionInvalid {
+                    mission_id,
+                    reason: item.reason,
+                }),
+                None => println!(
+                    "warning: invalid mission with begin_timestamp {} not found on server",
+                    item.begin_timestamp
+                ),
+            }
+        }
+
+        let result_list: Vec<SetMissionInvalidResult> = post_api(
+            &http_client,
+            endpoint_url,
+            "/admin/set_mission_invalid_bulk",
+            serde_json::to_vec(&request_list)
+                .map_err(|e| format!("cannot serialize invalid missions: {}", e))?,
+            max_retries,
+        )?;
+
+        for result in result_list.iter().filter(|result| !result.success) {
+            println!(
+                "warning: cannot mark mission {} invalid: {}",
+                result.mission_id, result.message
+            );
+        }
+        println!(
+            "restored invalid mission marks: {} missions",
+            result_list.iter().filter(|result| result.success).count()
+        );
+    }
+
+    // mission_raw缓存已由load_mission增量生成；无效任务标记需在更新GlobalKPIState前恢复
+    update_cache(
+        &[CacheType::MissionKPIRawCache, CacheType::GlobalKPIState],
+        endpoint_url,
+        &http_client,
+        max_retries,
+    )?;
+
+    // 服务端可能已有备份之外的任务，因此只要求任务数不少于备份
+    if remote_mission_list.len() < manifest.mission_count {
+        return Err(ClientError::Other(format!(
+            "restore incomplete: server has {} missions, backup has {}",
+            remote_mission_list.len(),
+            manifest.mission_count
+        )));
+    }
+
+    println!(
+        "restore finished, server has {} missions. Rock and stone!",
+        remote_mission_list.len()
+    );
+
+    Ok(())
+}
+
+fn read_file(backup_dir: &Path, file_name: &str) -> Result<Vec<u8>, String> {
+    let file_path = backup_dir.join(file_name);
+
+    fs::read(&file_path).map_err(|e| format!("cannot read {}: {}", file_path.to_string_lossy(), e))
+}
@@ -0,0 +1,97 @@
+use mission_backend_rs::admin::mission_invalid::{APISetMissionInvalid, SetMissionInvalidResult};
+use mission_backend_rs::client::*;
+use std::fs;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+// 文件每行为`<mission_id> <reason>`，空行与`#`开头的行忽略
+fn parse_invalid_list(file_content: &str) -> Result<Vec<APISetMissionInvalid>, String> {
+    file_content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (mission_id, reason) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("missing reason: {}", line))?;
+
+            let mission_id = mission_id
+                .parse::<i32>()
+                .map_err(|e| format!("invalid mission id {}: {}", mission_id, e))?;
+
+            Ok(APISetMissionInvalid {
+                mission_id,
+                reason: reason.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let args = client_args();
+
+    // --atomic：有任一项失败时全部不写入
+    let atomic = args.iter().any(|x| x == "--atomic");
+
+    let file_path = match args.iter().find(|x| !x.starts_with("--")) {
+        Some(x) => x,
+        None => {
+            return Err(ClientError::Other(
+                "usage: set_mission_invalid <file> [--atomic]".to_string(),
+            ))
+        }
+    };
+
+    let file_content =
+        fs::read_to_string(file_path).map_err(|e| format!("cannot read {}: {}", file_path, e))?;
+
+    let invalid_list = parse_invalid_list(&file_content)?;
+
+    if invalid_list.is_empty() {
+        println!("no mission to set invalid");
+        return Ok(());
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let body = serde_json::to_vec(&invalid_list)
+        .map_err(|e| format!("cannot serialize mission invalid list: {}", e))?;
+
+    let path = match atomic {
+        true => "/admin/set_mission_invalid_bulk?atomic=true",
+        false => "/admin/set_mission_invalid_bulk",
+    };
+
+    let result_list: Vec<SetMissionInvalidResult> = post_api(
+        &http_client,
+        &config.endpoint_url,
+        path,
+        body,
+        config.max_retries(),
+    )?;
+
+    for result in result_list.iter().filter(|result| !result.success) {
+        println!("mission {}: {}", result.mission_id, result.message);
+    }
+
+    let success_count = result_list.iter().filter(|result| result.success).count();
+
+    println!(
+        "set {} of {} missions invalid",
+        success_count,
+        result_list.len()
+    );
+
+    // 无效任务不参与全局KPI基准的计算，服务端会在后台更新GlobalKPIState
+    if success_count > 0 {
+        println!("global kpi state update scheduled on server");
+    }
+
+    Ok(())
+}
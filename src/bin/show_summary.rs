@@ -0,0 +1,325 @@
+use mission_backend_rs::client::*;
+use mission_backend_rs::RecentWindowInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+const TOP_PLAYER_COUNT: usize = 5;
+const TOP_WEAPON_COUNT: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+struct DeltaValue {
+    prev: f64,
+    recent: f64,
+    total: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GeneralSummary {
+    #[serde(rename = "gameCount")]
+    game_count: i32,
+    #[serde(rename = "validRate")]
+    valid_rate: f64,
+    #[serde(rename = "totalMissionTime")]
+    total_mission_time: i64,
+    #[serde(rename = "uniquePlayerCount")]
+    unique_player_count: i32,
+    #[serde(rename = "averageMissionTime")]
+    average_mission_time: DeltaValue,
+    #[serde(rename = "passRate")]
+    pass_rate: DeltaValue,
+    #[serde(rename = "averageDifficulty")]
+    average_difficulty: DeltaValue,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlayerKPISummary {
+    #[serde(rename = "playerIndex")]
+    player_index: f64,
+    #[serde(rename = "playerKPI")]
+    player_kpi: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WeaponSummary {
+    damage: f64,
+    #[serde(rename = "friendlyFire")]
+    friendly_fire: f64,
+    #[serde(rename = "heroGameId")]
+    hero_game_id: String,
+    #[serde(rename = "mappedName")]
+    mapped_name: String,
+    #[serde(rename = "validGameCount")]
+    valid_game_count: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CharacterChoiceSummary {
+    #[serde(rename = "characterChoiceCount")]
+    character_choice_count: HashMap<String, i32>,
+    #[serde(rename = "characterMapping")]
+    character_mapping: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct TopPlayer {
+    #[serde(rename = "playerName")]
+    player_name: String,
+    #[serde(rename = "playerKPI")]
+    player_kpi: f64,
+    #[serde(rename = "playerIndex")]
+    player_index: f64,
+}
+
+#[derive(Serialize)]
+struct TopWeapon {
+    #[serde(rename = "weaponGameId")]
+    weapon_game_id: String,
+    #[serde(flatten)]
+    info: WeaponSummary,
+}
+
+#[derive(Serialize)]
+struct CharacterDistribution {
+    #[serde(rename = "characterGameId")]
+    character_game_id: String,
+    #[serde(rename = "mappedName")]
+    mapped_name: String,
+    count: i32,
+    rate: f64,
+}
+
+#[derive(Serialize)]
+struct SummaryReport {
+    general: GeneralSummary,
+    #[serde(rename = "recentWindow")]
+    recent_window: RecentWindowInfo,
+    #[serde(rename = "topPlayer")]
+    top_player: Vec<TopPlayer>,
+    #[serde(rename = "topWeapon")]
+    top_weapon: Vec<TopWeapon>,
+    #[serde(rename = "characterDistribution")]
+    character_distribution: Vec<CharacterDistribution>,
+}
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    let output_format = OutputFormat::from_args(client_args().into_iter())?;
+
+    if output_format == OutputFormat::Human {
+        author_info();
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+    let endpoint_url = &config.endpoint_url;
+    let max_retries = config.max_retries();
+
+    let general: GeneralSummary = fetch_api(&http_client, endpoint_url, "/general/", max_retries)?;
+    let player_kpi: HashMap<String, PlayerKPISummary> =
+        fetch_api(&http_client, endpoint_url, "/kpi/player_kpi", max_retries)?;
+    let weapon: HashMap<String, WeaponSummary> =
+        fetch_api(&http_client, endpoint_url, "/damage/weapon", max_retries)?;
+    let character_choice: CharacterChoiceSummary = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/general/character_info",
+        max_retries,
+    )?;
+
+    // 使用服务端的“近期”窗口定义，保证显示的recent局数与服务端计算一致
+    let recent_window: RecentWindowInfo = fetch_api(
+        &http_client,
+        endpoint_url,
+        "/info/recent_window",
+        max_retries,
+    )?;
+
+    let report = generate_report(general, recent_window, player_kpi, weapon, character_choice);
+
+    if output_format.write_json(&report)? {
+        return Ok(());
+    }
+
+    print_report(&report);
+
+    Ok(())
+}
+
+fn generate_report(
+    general: GeneralSummary,
+    recent_window: RecentWindowInfo,
+    player_kpi: HashMap<String, PlayerKPISummary>,
+    weapon: HashMap<String, WeaponSummary>,
+    character_choice: CharacterChoiceSummary,
+) -> SummaryReport {
+    let mut top_player = player_kpi
+        .into_iter()
+        .map(|(player_name, info)| TopPlayer {
+            player_name,
+            player_kpi: info.player_kpi,
+            player_index: info.player_index,
+        })
+        .collect::<Vec<_>>();
+
+    top_player.sort_unstable_by(|a, b| {
+        b.player_kpi
+            .total_cmp(&a.player_kpi)
+            .then_with(|| a.player_name.cmp(&b.player_name))
+    });
+    top_player.truncate(TOP_PLAYER_COUNT);
+
+    let mut top_weapon = weapon
+        .into_iter()
+        .map(|(weapon_game_id, info)| TopWeapon {
+            weapon_game_id,
+            info,
+        })
+        .collect::<Vec<_>>();
+
+    top_weapon.sort_unstable_by(|a, b| {
+        b.info
+            .valid_game_count
+            .cmp(&a.info.valid_game_count)
+            .then_with(|| b.info.damage.total_cmp(&a.info.damage))
+            .then_with(|| a.weapon_game_id.cmp(&b.weapon_game_id))
+    });
+    top_weapon.truncate(TOP_WEAPON_COUNT);
+
+    let total_choice_count = character_choice
+        .character_choice_count
+        .values()
+        .sum::<i32>();
+
+    let mut character_distribution = character_choice
+        .character_choice_count
+        .into_iter()
+        .map(|(character_game_id, count)| CharacterDistribution {
+            mapped_name: character_choice
+                .character_mapping
+                .get(&character_game_id)
+                .unwrap_or(&character_game_id)
+                .clone(),
+            character_game_id,
+            count,
+            rate: match total_choice_count {
+                0 => 0.0,
+                x => count as f64 / x as f64,
+            },
+        })
+        .collect::<Vec<_>>();
+
+    character_distribution.sort_unstable_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.character_game_id.cmp(&b.character_game_id))
+    });
+
+    SummaryReport {
+        general,
+        recent_window,
+        top_player,
+        top_weapon,
+        character_distribution,
+    }
+}
+
+fn print_report(report: &SummaryReport) {
+    let general = &report.general;
+
+    println!("[Overview]");
+    println!("{:<24}{}", "game count", general.game_count);
+    println!("{:<24}{:.2}%", "valid rate", general.valid_rate * 100.0);
+    println!(
+        "{:<24}{:.1}h",
+        "total mission time",
+        general.total_mission_time as f64 / 3600.0
+    );
+    println!("{:<24}{}", "unique players", general.unique_player_count);
+
+    let valid_game_count = (general.game_count as f64 * general.valid_rate).round() as usize;
+    println!(
+        "{:<24}last {} of {} valid games (prev: first {}%, at least {})",
+        "recent window",
+        report.recent_window.recent_count(valid_game_count),
+        valid_game_count,
+        report.recent_window.prev_percent,
+        report.recent_window.prev_min_count
+    );
+    println!(
+        "{:<24}{}",
+        "average mission time",
+        format_delta(&general.average_mission_time, |x| format!("{:.0}s", x))
+    );
+    println!(
+        "{:<24}{}",
+        "pass rate",
+        format_delta(&general.pass_rate, |x| format!("{:.2}%", x * 100.0))
+    );
+    println!(
+        "{:<24}{}",
+        "average difficulty",
+        format_delta(&general.average_difficulty, |x| format!("{:.2}", x))
+    );
+    println!();
+
+    println!("[Top {} players by KPI]", TOP_PLAYER_COUNT);
+    println!(
+        "{:<4}{:<24}{:>12}{:>14}",
+        "#", "player", "kpi", "player_index"
+    );
+    for (i, player) in report.top_player.iter().enumerate() {
+        println!(
+            "{:<4}{:<24}{:>12.3}{:>14.2}",
+            i + 1,
+            player.player_name,
+            player.player_kpi,
+            player.player_index
+        );
+    }
+    println!();
+
+    println!("[Most used weapons]");
+    println!(
+        "{:<4}{:<32}{:<12}{:>8}{:>16}",
+        "#", "weapon", "character", "games", "damage"
+    );
+    for (i, weapon) in report.top_weapon.iter().enumerate() {
+        println!(
+            "{:<4}{:<32}{:<12}{:>8}{:>16.0}",
+            i + 1,
+            weapon.info.mapped_name,
+            weapon.info.hero_game_id,
+            weapon.info.valid_game_count,
+            weapon.info.damage
+        );
+    }
+    println!();
+
+    println!("[Character distribution]");
+    println!(
+        "{:<16}{:<16}{:>8}{:>10}",
+        "character", "name", "count", "rate"
+    );
+    for character in &report.character_distribution {
+        println!(
+            "{:<16}{:<16}{:>8}{:>9.2}%",
+            character.character_game_id,
+            character.mapped_name,
+            character.count,
+            character.rate * 100.0
+        );
+    }
+}
+
+fn format_delta(value: &DeltaValue, format_value: impl Fn(f64) -> String) -> String {
+    format!(
+        "{} (prev: {}, recent: {})",
+        format_value(value.total),
+        format_value(value.prev),
+        format_value(value.recent)
+    )
+}
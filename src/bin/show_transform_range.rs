@@ -0,0 +1,72 @@
+use mission_backend_rs::client::*;
+use mission_backend_rs::kpi::IndexTransformRange;
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+// kpi_character_type -> kpi_component -> Vec<IndexTransformRange>
+type TransformRangeInfo = HashMap<String, HashMap<String, Vec<IndexTransformRange>>>;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+fn run() -> Result<(), ClientError> {
+    let output_format = OutputFormat::from_args(client_args().into_iter())?;
+
+    if output_format == OutputFormat::Human {
+        author_info();
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let transform_range_info: TransformRangeInfo = fetch_api(
+        &http_client,
+        &config.endpoint_url,
+        "/kpi/transform_range_info",
+        config.max_retries(),
+    )?;
+
+    if output_format.write_json(&transform_range_info)? {
+        return Ok(());
+    }
+
+    let mut character_list = transform_range_info.iter().collect::<Vec<_>>();
+    character_list.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    for (character_kpi_type, component_info) in character_list {
+        println!("[{}]", character_kpi_type);
+        println!(
+            "{:<16}{:<20}{:<20}{:<20}{:<24}{:>8}",
+            "component", "rank_range", "source_range", "transform_range", "coefficient", "players"
+        );
+
+        let mut component_list = component_info.iter().collect::<Vec<_>>();
+        component_list.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        for (kpi_component, range_list) in component_list {
+            for range in range_list {
+                println!(
+                    "{:<16}{:<20}{:<20}{:<20}{:<24}{:>8}",
+                    kpi_component,
+                    format_range(range.rank_range),
+                    format_range(range.source_range),
+                    format_range(range.transform_range),
+                    format!(
+                        "y = {:.3}x + {:.3}",
+                        range.transform_cofficient.0, range.transform_cofficient.1
+                    ),
+                    range.player_count
+                );
+            }
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
+fn format_range(range: (f64, f64)) -> String {
+    format!("[{:.3}, {:.3}]", range.0, range.1)
+}
@@ -0,0 +1,52 @@
+use mission_backend_rs::client::*;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    exit_with(run())
+}
+
+// 输出当前配置，并实际登录服务端检查Access Token与session是否有效
+fn run() -> Result<(), ClientError> {
+    author_info();
+
+    let config_path = client_config_path()?;
+    let config = load_client_config()?;
+
+    println!("config: {}", config_path.to_string_lossy());
+    println!("endpoint: {}", config.endpoint_url);
+    println!(
+        "access token: {}",
+        match config.access_token {
+            Some(_) => "set",
+            None => "not set",
+        }
+    );
+
+    let result = build_http_client(&config).and_then(|http_client| {
+        fetch_api::<()>(
+            &http_client,
+            &config.endpoint_url,
+            "/admin/check_session",
+            config.max_retries(),
+        )
+    });
+
+    match result {
+        Ok(()) => {
+            println!("session: valid");
+            Ok(())
+        }
+        Err(ClientError::NotAuthenticated(e)) => {
+            println!("session: invalid");
+            println!(
+                "check access_token in the config file or the {} environment variable, then run again",
+                CLIENT_TOKEN_ENV
+            );
+            Err(ClientError::NotAuthenticated(e))
+        }
+        Err(e) => {
+            println!("session: unknown");
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use mission_backend_rs::client::raw_log::parse_mission_log;
+use mission_backend_rs::client::*;
+use mission_backend_rs::mission::APIMission;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Serialize)]
+struct VerifyResult {
+    // 本地存在但未上传
+    #[serde(rename = "localOnly")]
+    local_only: Vec<i64>,
+    // 已上传但本地不存在
+    #[serde(rename = "remoteOnly")]
+    remote_only: Vec<i64>,
+    matched: Vec<i64>,
+}
+
+// 存在未上传的任务时的退出码，与ClientError对应的退出码区分
+const LOCAL_ONLY_EXIT_CODE: u8 = 6;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(false) => ExitCode::from(LOCAL_ONLY_EXIT_CODE),
+        result => exit_with(result.map(|_| ())),
+    }
+}
+
+// 返回本地任务是否均已上传
+fn run() -> Result<bool, ClientError> {
+    let output_format = OutputFormat::from_args(client_args().into_iter())?;
+
+    if output_format == OutputFormat::Human {
+        author_info();
+    }
+
+    let config = load_client_config()?;
+    let http_client = build_http_client(&config)?;
+
+    let remote_mission_list: Vec<APIMission> = fetch_api(
+        &http_client,
+        &config.endpoint_url,
+        "/mission/api_mission_list",
+        config.max_retries(),
+    )?;
+
+    let local_mission_list = parse_mission_log(Path::new("./raw_log"), &config.timestamp_check())?;
+
+    let remote_timestamp_set = remote_mission_list
+        .iter()
+        .map(|mission| mission.begin_timestamp)
+        .collect::<HashSet<_>>();
+
+    let local_timestamp_set = local_mission_list
+        .iter()
+        .map(|mission| mission.mission_info.begin_timestamp)
+        .collect::<HashSet<_>>();
+
+    let result = verify(&local_timestamp_set, &remote_timestamp_set);
+
+    if !output_format.write_json(&result)? {
+        print_result(&result);
+    }
+
+    Ok(result.local_only.is_empty())
+}
+
+fn verify(local: &HashSet<i64>, remote: &HashSet<i64>) -> VerifyResult {
+    let mut local_only = local.difference(remote).copied().collect::<Vec<_>>();
+    let mut remote_only = remote.difference(local).copied().collect::<Vec<_>>();
+    let mut matched = local.intersection(remote).copied().collect::<Vec<_>>();
+
+    local_only.sort_unstable();
+    remote_only.sort_unstable();
+    matched.sort_unstable();
+
+    VerifyResult {
+        local_only,
+        remote_only,
+        matched,
+    }
+}
+
+fn print_result(result: &VerifyResult) {
+    println!("[Local only: {}]", result.local_only.len());
+    for begin_timestamp in &result.local_only {
+        println!("{}", begin_timestamp);
+    }
+    println!();
+
+    println!("[Remote only: {}]", result.remote_only.len());
+    for begin_timestamp in &result.remote_only {
+        println!("{}", begin_timestamp);
+    }
+    println!();
+
+    println!("[Matched: {}]", result.matched.len());
+    for begin_timestamp in &result.matched {
+        println!("{}", begin_timestamp);
+    }
+    println!();
+
+    if result.local_only.is_empty() {
+        println!("All local missions are uploaded. Rock and stone!");
+    } else {
+        println!(
+            "{} local missions are not uploaded, run load_mission to upload them",
+            result.local_only.len()
+        );
+    }
+}
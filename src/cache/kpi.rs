@@ -1,4 +1,5 @@
 use crate::cache::mission::{MissionCachedInfo, MissionKPICachedInfo};
+use crate::cache::{encode_cache, get_from_redis, GLOBAL_KPI_STATE_CACHE_KEY};
 use crate::kpi::*;
 use crate::{
     CORRECTION_ITEMS, KPI_CALCULATION_PLAYER_INDEX, NITRA_GAME_ID, TRANSFORM_KPI_COMPONENTS,
@@ -39,17 +40,50 @@ struct CharacterMissionInfo {
     pub resource: f64,
 }
 
+// 生成GlobalKPIState时所需的、来自mapping与KPIConfig的玩家信息
+pub struct GlobalKPIPlayerInfo<'a> {
+    pub player_id_to_name: &'a HashMap<i16, String>,
+    pub character_id_to_game_id: &'a HashMap<i16, String>,
+    pub scout_special_player_set: &'a HashSet<String>,
+    // 不计入基准值的玩家名称
+    pub baseline_excluded_player_set: HashSet<String>,
+}
+
+impl<'a> GlobalKPIPlayerInfo<'a> {
+    pub fn new(
+        player_id_to_name: &'a HashMap<i16, String>,
+        character_id_to_game_id: &'a HashMap<i16, String>,
+        scout_special_player_set: &'a HashSet<String>,
+        kpi_config: &KPIConfig,
+    ) -> Self {
+        GlobalKPIPlayerInfo {
+            player_id_to_name,
+            character_id_to_game_id,
+            scout_special_player_set,
+            baseline_excluded_player_set: kpi_config
+                .baseline_excluded_player
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
 impl CachedGlobalKPIState {
     pub fn generate(
         cached_mission_list: &[MissionCachedInfo],
         cached_mission_kpi_list: &[MissionKPICachedInfo],
         invalid_mission_id_list: &[i32],
         kpi_config: &KPIConfig,
-        player_id_to_name: &HashMap<i16, String>,
-        character_id_to_game_id: &HashMap<i16, String>,
-        scout_special_player_set: &HashSet<String>,
+        player_info: &GlobalKPIPlayerInfo,
     ) -> (Self, Duration) {
         let begin = Instant::now();
+        let GlobalKPIPlayerInfo {
+            player_id_to_name,
+            character_id_to_game_id,
+            scout_special_player_set,
+            baseline_excluded_player_set,
+        } = player_info;
 
         let cached_mission_kpi_set = cached_mission_kpi_list
             .into_iter()
@@ -87,6 +121,11 @@ impl CachedGlobalKPIState {
                 let player_index = *mission.player_index.get(&player_info.player_id).unwrap();
 
                 let player_name = player_id_to_name.get(&player_info.player_id).unwrap();
+
+                if baseline_excluded_player_set.contains(player_name) {
+                    continue;
+                }
+
                 let player_character_game_id = character_id_to_game_id
                     .get(&player_info.character_id)
                     .unwrap();
@@ -112,7 +151,7 @@ impl CachedGlobalKPIState {
                     .iter()
                     .map(|player_info| player_info.iter())
                     .flatten()
-                    .filter(|(_, pack)| pack.taker_type != 1)
+                    .filter(|(_, pack)| !pack.is_friendly_fire())
                     .map(|(taker_game_id, pack)| (taker_game_id.clone(), pack.total_amount))
                     .collect::<HashMap<_, _>>();
 
@@ -311,9 +350,12 @@ impl CachedGlobalKPIState {
                     scout_special_player_set,
                 );
 
-                let correction_data = character_correction_factor
-                    .get(&player_character_kpi_type)
-                    .unwrap();
+                // 该角色的玩家全部被排除出基准时没有修正因子
+                let correction_data =
+                    match character_correction_factor.get(&player_character_kpi_type) {
+                        Some(x) => x,
+                        None => continue,
+                    };
 
                 for (&kpi_component, info) in correction_data {
                     *mission_correction_sum.entry(kpi_component).or_insert(0.0) +=
@@ -347,6 +389,12 @@ impl CachedGlobalKPIState {
                         continue;
                     }
 
+                    if baseline_excluded_player_set
+                        .contains(player_id_to_name.get(&player_info.player_id).unwrap())
+                    {
+                        continue;
+                    }
+
                     character_kpi_type_to_player_id_to_mission_index_list
                         .entry(player_character_kpi_type)
                         .or_default()
@@ -473,6 +521,7 @@ impl CachedGlobalKPIState {
         player_id_to_name: &HashMap<i16, String>,
         character_id_to_game_id: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
     ) -> Result<Self, ()> {
         let begin = Instant::now();
         let cached_mission_list = MissionCachedInfo::get_cached_all(
@@ -492,6 +541,7 @@ impl CachedGlobalKPIState {
             character_id_to_game_id,
             player_id_to_name,
             scout_special_player_set,
+            non_mineral_resource_set,
             &kpi_config,
         )?;
 
@@ -503,9 +553,12 @@ impl CachedGlobalKPIState {
             &cached_mission_kpi_list,
             invalid_mission_id_list,
             &kpi_config,
-            player_id_to_name,
-            character_id_to_game_id,
-            scout_special_player_set,
+            &GlobalKPIPlayerInfo::new(
+                player_id_to_name,
+                character_id_to_game_id,
+                scout_special_player_set,
+                &kpi_config,
+            ),
         )
         .0;
 
@@ -526,22 +579,11 @@ impl CachedGlobalKPIState {
         player_id_to_name: &HashMap<i16, String>,
         character_id_to_game_id: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
     ) -> Result<Self, ()> {
-        let cached_bytes: Option<Vec<u8>> = redis_conn.get("global_kpi_state").ok();
-
-        let cached_content = match cached_bytes {
-            Some(x) => {
-                let decoded: CachedGlobalKPIState = match rmp_serde::from_read(&x[..]) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("cannot decode cached bytes: {}", e);
-                        return Err(());
-                    }
-                };
-
-                decoded
-            }
-            None => {
+        let cached_content = match get_from_redis(redis_conn, GLOBAL_KPI_STATE_CACHE_KEY) {
+            Ok(x) => x,
+            Err(_) => {
                 let cached_mission_list = MissionCachedInfo::get_cached_all(
                     db_conn,
                     redis_conn,
@@ -559,6 +601,7 @@ impl CachedGlobalKPIState {
                     character_id_to_game_id,
                     player_id_to_name,
                     scout_special_player_set,
+                    non_mineral_resource_set,
                     &kpi_config,
                 )?;
 
@@ -567,14 +610,17 @@ impl CachedGlobalKPIState {
                     &cached_mission_kpi_list,
                     invalid_mission_id_list,
                     kpi_config,
-                    player_id_to_name,
-                    character_id_to_game_id,
-                    scout_special_player_set,
+                    &GlobalKPIPlayerInfo::new(
+                        player_id_to_name,
+                        character_id_to_game_id,
+                        scout_special_player_set,
+                        kpi_config,
+                    ),
                 )
                 .0;
 
-                let serialized = rmp_serde::to_vec(&generated).unwrap();
-                match redis_conn.set("global_kpi_state", serialized) {
+                let serialized = encode_cache(&generated);
+                match redis_conn.set(GLOBAL_KPI_STATE_CACHE_KEY, serialized) {
                     Ok(()) => generated,
                     Err(e) => {
                         error!("cannot write data to redis: {}", e);
@@ -587,3 +633,193 @@ impl CachedGlobalKPIState {
         Ok(cached_content)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::mission::tests::cached_mission;
+    use crate::cache::mission::PlayerRawKPIData;
+    use crate::damage::{DamagePack, KillPack};
+    use crate::kpi::tests::valid_kpi_config;
+
+    const CARRY_PLAYER_ID: i16 = 14;
+
+    // 每局4名玩家分别使用4个标准角色，钻机玩家每局不同，最后一局的钻机玩家表现远超其他人
+    fn mission_with_driller(
+        mission_id: i32,
+        driller_player_id: i16,
+        driller_raw_index: f64,
+    ) -> (MissionCachedInfo, MissionKPICachedInfo) {
+        let player_list = [(driller_player_id, 0), (2, 1), (3, 2), (4, 3)];
+
+        let mission = MissionCachedInfo {
+            kill_info: player_list
+                .iter()
+                .map(|&(id, _)| {
+                    (
+                        id,
+                        HashMap::from([(
+                            "Bug".to_string(),
+                            KillPack {
+                                taker_id: 0,
+                                taker_name: "Bug".to_string(),
+                                total_amount: 10,
+                            },
+                        )]),
+                    )
+                })
+                .collect(),
+            damage_info: player_list
+                .iter()
+                .map(|&(id, _)| {
+                    (
+                        id,
+                        HashMap::from([(
+                            "Bug".to_string(),
+                            DamagePack {
+                                taker_id: 0,
+                                taker_type: 0,
+                                weapon_id: 0,
+                                total_amount: 100.0,
+                            },
+                        )]),
+                    )
+                })
+                .collect(),
+            resource_info: player_list
+                .iter()
+                .map(|&(id, _)| (id, HashMap::from([(NITRA_GAME_ID.to_string(), 10.0)])))
+                .collect(),
+            ..cached_mission(mission_id, &player_list)
+        };
+
+        let raw_kpi_data = player_list
+            .iter()
+            .map(|&(id, _)| {
+                let raw_index = if id == driller_player_id {
+                    driller_raw_index
+                } else {
+                    0.25
+                };
+
+                (
+                    id,
+                    CORRECTION_ITEMS
+                        .iter()
+                        .map(|&kpi_component| {
+                            (
+                                kpi_component,
+                                PlayerRawKPIData {
+                                    source_value: 0.0,
+                                    weighted_value: 0.0,
+                                    mission_total_weighted_value: 0.0,
+                                    raw_index,
+                                },
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let mission_kpi = MissionKPICachedInfo {
+            mission_id,
+            damage_map: HashMap::new(),
+            kill_map: HashMap::new(),
+            resource_map: HashMap::new(),
+            total_damage_map: HashMap::new(),
+            total_kill_map: HashMap::new(),
+            total_resource_map: HashMap::new(),
+            player_id_to_kpi_character: HashMap::new(),
+            raw_kpi_data,
+        };
+
+        (mission, mission_kpi)
+    }
+
+    #[test]
+    fn excluding_high_performer_shifts_transform_range() {
+        let (cached_mission_list, cached_mission_kpi_list): (Vec<_>, Vec<_>) = [
+            (1, 11, 0.2),
+            (2, 12, 0.3),
+            (3, 13, 0.4),
+            (4, CARRY_PLAYER_ID, 0.9),
+        ]
+        .into_iter()
+        .map(|(mission_id, player_id, raw_index)| {
+            mission_with_driller(mission_id, player_id, raw_index)
+        })
+        .unzip();
+
+        let kpi_config = KPIConfig {
+            character_component_weight: HashMap::new(),
+            transform_range: vec![IndexTransformRangeConfig {
+                rank_range: (0.0, 0.5),
+                transform_range: (0.0, 0.5),
+            }],
+            ..valid_kpi_config()
+        };
+
+        let player_id_to_name = [11, 12, 13, CARRY_PLAYER_ID, 2, 3, 4]
+            .into_iter()
+            .map(|id| (id, format!("player{}", id)))
+            .collect::<HashMap<_, _>>();
+
+        let character_id_to_game_id = HashMap::from([
+            (0, "DRILLER".to_string()),
+            (1, "ENGINEER".to_string()),
+            (2, "GUNNER".to_string()),
+            (3, "SCOUT".to_string()),
+        ]);
+
+        let scout_special_player_set = HashSet::new();
+
+        let generate = |baseline_excluded_player_set: &HashSet<String>| {
+            CachedGlobalKPIState::generate(
+                &cached_mission_list,
+                &cached_mission_kpi_list,
+                &[],
+                &kpi_config,
+                &GlobalKPIPlayerInfo {
+                    baseline_excluded_player_set: baseline_excluded_player_set.clone(),
+                    ..GlobalKPIPlayerInfo::new(
+                        &player_id_to_name,
+                        &character_id_to_game_id,
+                        &scout_special_player_set,
+                        &kpi_config,
+                    )
+                },
+            )
+            .0
+        };
+
+        let driller_damage_range = |state: &CachedGlobalKPIState| {
+            state
+                .transform_range
+                .get(&CharacterKPIType::Driller)
+                .unwrap()
+                .get(&KPIComponent::Damage)
+                .unwrap()[0]
+        };
+
+        let full = generate(&HashSet::new());
+        let excluded = generate(&HashSet::from([format!("player{}", CARRY_PLAYER_ID)]));
+
+        // 下半区上界：[0.2, 0.3, 0.4, 0.9] -> 0.4；排除后[0.2, 0.3, 0.4] -> 0.3
+        assert_eq!(driller_damage_range(&full).source_range.1, 0.4);
+        assert_eq!(driller_damage_range(&excluded).source_range.1, 0.3);
+        assert_eq!(driller_damage_range(&full).player_count, 2);
+        assert_eq!(driller_damage_range(&excluded).player_count, 1);
+
+        // 其他角色的基准不受影响
+        assert_eq!(
+            full.transform_range.get(&CharacterKPIType::Gunner).unwrap()[&KPIComponent::Damage][0]
+                .source_range,
+            excluded
+                .transform_range
+                .get(&CharacterKPIType::Gunner)
+                .unwrap()[&KPIComponent::Damage][0]
+                .source_range
+        );
+    }
+}
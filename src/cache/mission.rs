@@ -1,15 +1,18 @@
+use super::{encode_cache, get_from_redis, mission_kpi_raw_cache_key, mission_raw_cache_key};
 use crate::damage::{DamagePack, KillPack, SupplyPack, WeaponPack};
 use crate::db::models::*;
 use crate::db::schema::*;
 use crate::kpi::{
     apply_weight_table, friendly_fire_index, CharacterKPIType, KPIComponent, KPIConfig,
 };
-use crate::{FLOAT_EPSILON, NITRA_GAME_ID};
+use crate::{MergedPlayerInfo, FLOAT_EPSILON, NITRA_GAME_ID};
 use diesel::prelude::*;
 use diesel::{PgConnection, RunQueryDsl};
 use log::{debug, error, info, warn};
+use rayon::prelude::*;
 use redis::Commands;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
@@ -17,7 +20,78 @@ use std::time::{Duration, Instant};
 // depends on:
 // - mapping: entity_blacklist, entity_combine, weapon_combine
 
-#[derive(Serialize, Deserialize)]
+// id -> game_id / 玩家名，生成MissionCachedInfo时使用
+pub struct IDMapping {
+    pub id_to_player_name: HashMap<i16, String>,
+    pub id_to_entity_game_id: HashMap<i16, String>,
+    pub id_to_resource_game_id: HashMap<i16, String>,
+    pub id_to_weapon_game_id: HashMap<i16, String>,
+}
+
+impl IDMapping {
+    pub fn load_from_db(conn: &mut PgConnection) -> Result<Self, ()> {
+        let player_list: Vec<Player> = match player::table.load(conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot load player from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let entity_list: Vec<Entity> = match entity::table.load(conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot load entity from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let resource_list: Vec<Resource> = match resource::table.load(conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot load resource from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let weapon_list: Vec<Weapon> = match weapon::table.load(conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot load weapon from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let id_to_player_name = player_list
+            .into_iter()
+            .map(|player| (player.id, player.player_name))
+            .collect::<HashMap<_, _>>();
+
+        let id_to_entity_game_id = entity_list
+            .into_iter()
+            .map(|entity| (entity.id, entity.entity_game_id))
+            .collect::<HashMap<_, _>>();
+
+        let id_to_resource_game_id = resource_list
+            .into_iter()
+            .map(|resource| (resource.id, resource.resource_game_id))
+            .collect::<HashMap<_, _>>();
+
+        let id_to_weapon_game_id = weapon_list
+            .into_iter()
+            .map(|weapon| (weapon.id, weapon.weapon_game_id))
+            .collect::<HashMap<_, _>>();
+
+        Ok(IDMapping {
+            id_to_player_name,
+            id_to_entity_game_id,
+            id_to_resource_game_id,
+            id_to_weapon_game_id,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct MissionCachedInfo {
     pub mission_info: Mission,
     pub player_info: Vec<PlayerInfo>,
@@ -36,9 +110,72 @@ pub struct MissionCachedInfo {
     pub death_count: HashMap<i16, i16>,
     // player_id -> info
     pub supply_info: HashMap<i16, Vec<SupplyPack>>,
+    // player_id -> 该玩家造成伤害时使用过的weapon_game_id（已合并）
+    pub player_weapon_set: HashMap<i16, HashSet<String>>,
+    // weapon_game_id -> entity_game_id -> 击杀数（均已合并）
+    // 击杀记录不含武器，按击杀前该玩家对该敌人最近一次造成伤害的武器归属，为近似值
+    pub kill_weapon_info: HashMap<String, HashMap<String, i32>>,
 }
 
 impl MissionCachedInfo {
+    // 将玩家别名的player_id替换为合并后的player_id，友伤承受者同样替换为主名称
+    // 同一任务中出现同一玩家的多个名称时记录日志，仅保留原player_id最小者的数据
+    pub fn merge_player_alias(&mut self, merged_player_info: &MergedPlayerInfo) {
+        let id_map = &merged_player_info.player_id_to_merged_id;
+
+        // 合并后的player_id -> 保留的原player_id
+        let mut kept_player_id: HashMap<i16, i16> = HashMap::new();
+        for player_info in &self.player_info {
+            let entry = kept_player_id
+                .entry(merged_player_id(id_map, player_info.player_id))
+                .or_insert(player_info.player_id);
+            *entry = (*entry).min(player_info.player_id);
+        }
+
+        let mut collision = HashSet::new();
+
+        self.player_info.retain_mut(|player_info| {
+            let merged_id = merged_player_id(id_map, player_info.player_id);
+
+            if kept_player_id[&merged_id] != player_info.player_id {
+                collision.insert(merged_id);
+                return false;
+            }
+
+            player_info.player_id = merged_id;
+            true
+        });
+
+        collision.extend(merge_player_id_key(&mut self.player_index, id_map));
+        collision.extend(merge_player_id_key(&mut self.kill_info, id_map));
+        collision.extend(merge_player_id_key(&mut self.damage_info, id_map));
+        collision.extend(merge_player_id_key(&mut self.resource_info, id_map));
+        collision.extend(merge_player_id_key(&mut self.revive_count, id_map));
+        collision.extend(merge_player_id_key(&mut self.death_count, id_map));
+        collision.extend(merge_player_id_key(&mut self.supply_info, id_map));
+        collision.extend(merge_player_id_key(&mut self.player_weapon_set, id_map));
+
+        warn_player_id_collision(self.mission_info.id, collision);
+
+        for taker_map in self.damage_info.values_mut() {
+            *taker_map = std::mem::take(taker_map)
+                .into_iter()
+                .map(|(taker_game_id, mut pack)| {
+                    if !pack.is_friendly_fire() {
+                        return (taker_game_id, pack);
+                    }
+
+                    pack.taker_id = merged_player_id(id_map, pack.taker_id);
+
+                    match merged_player_info.player_id_to_name.get(&pack.taker_id) {
+                        Some(name) => (name.clone(), pack),
+                        None => (taker_game_id, pack),
+                    }
+                })
+                .collect();
+        }
+    }
+
     fn generate(
         mission_info: &Mission,
         player_info_list: &Vec<PlayerInfo>,
@@ -111,6 +248,9 @@ impl MissionCachedInfo {
         let mut damage_info = HashMap::with_capacity(player_info_list.len());
 
         let mut weapon_details = HashMap::new();
+        let mut player_weapon_set: HashMap<i16, HashSet<String>> = HashMap::new();
+        // weapon_game_id -> (first_time, last_time)
+        let mut weapon_time_range: HashMap<&String, (i16, i16)> = HashMap::new();
 
         for current_damage_info in raw_damage_info_list {
             // 0→unknown 1→ player 2→enemy
@@ -165,6 +305,11 @@ impl MissionCachedInfo {
                 .get(record_weapon_game_id)
                 .unwrap_or(record_weapon_game_id);
 
+            player_weapon_set
+                .entry(current_damage_info.causer_id)
+                .or_default()
+                .insert(weapon_game_id.clone());
+
             let detail_map = weapon_details
                 .entry(weapon_game_id)
                 .or_insert(HashMap::new());
@@ -177,6 +322,13 @@ impl MissionCachedInfo {
             });
 
             detail_entry.total_amount += current_damage_info.damage;
+
+            let time_range_entry = weapon_time_range
+                .entry(weapon_game_id)
+                .or_insert((current_damage_info.time, current_damage_info.time));
+
+            time_range_entry.0 = time_range_entry.0.min(current_damage_info.time);
+            time_range_entry.1 = time_range_entry.1.max(current_damage_info.time);
         }
 
         let mut resource_info = HashMap::with_capacity(player_info_list.len());
@@ -224,12 +376,18 @@ impl MissionCachedInfo {
                     .map(|(k, v)| (k.clone(), v))
                     .collect::<HashMap<_, _>>();
 
+                let active_span = weapon_time_range
+                    .get(weapon_game_id)
+                    .map(|(first_time, last_time)| last_time - first_time)
+                    .unwrap_or(0);
+
                 (
                     weapon_game_id.clone(),
                     WeaponPack {
                         weapon_id: *weapon_id,
                         total_amount: total_damage,
                         detail: detail_map,
+                        active_span,
                     },
                 )
             })
@@ -277,6 +435,16 @@ impl MissionCachedInfo {
             })
             .collect::<HashMap<_, _>>();
 
+        let kill_weapon_info = attribute_kill_weapon(
+            raw_kill_info_list,
+            raw_damage_info_list,
+            entity_blacklist_set,
+            entity_combine,
+            weapon_combine,
+            id_to_entity_game_id,
+            id_to_weapon_game_id,
+        );
+
         let elapsed = begin.elapsed();
 
         debug!(
@@ -296,6 +464,8 @@ impl MissionCachedInfo {
                 revive_count,
                 death_count,
                 supply_info,
+                player_weapon_set,
+                kill_weapon_info,
             },
             elapsed,
         )
@@ -308,59 +478,28 @@ impl MissionCachedInfo {
         weapon_combine: &HashMap<String, String>,
         mission_id: i32,
     ) -> Result<Self, ()> {
-        let begin = Instant::now();
-
-        let player_list: Vec<Player> = match player::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load player from db: {}", e);
-                return Err(());
-            }
-        };
-
-        let entity_list: Vec<Entity> = match entity::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load entity from db: {}", e);
-                return Err(());
-            }
-        };
-
-        let resource_list: Vec<Resource> = match resource::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load resource from db: {}", e);
-                return Err(());
-            }
-        };
-
-        let weapon_list: Vec<Weapon> = match weapon::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load weapon from db: {}", e);
-                return Err(());
-            }
-        };
+        let id_mapping = IDMapping::load_from_db(conn)?;
 
-        let id_to_player_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
-
-        let id_to_entity_game_id = entity_list
-            .into_iter()
-            .map(|entity| (entity.id, entity.entity_game_id))
-            .collect::<HashMap<_, _>>();
-
-        let id_to_resource_game_id = resource_list
-            .into_iter()
-            .map(|resource| (resource.id, resource.resource_game_id))
-            .collect::<HashMap<_, _>>();
+        Self::from_db_with_mapping(
+            conn,
+            entity_blacklist_set,
+            entity_combine,
+            weapon_combine,
+            &id_mapping,
+            mission_id,
+        )
+    }
 
-        let id_to_weapon_game_id = weapon_list
-            .into_iter()
-            .map(|weapon| (weapon.id, weapon.weapon_game_id))
-            .collect::<HashMap<_, _>>();
+    // 需要生成多个任务时，由调用方加载一次IDMapping并复用
+    pub fn from_db_with_mapping(
+        conn: &mut PgConnection,
+        entity_blacklist_set: &HashSet<String>,
+        entity_combine: &HashMap<String, String>,
+        weapon_combine: &HashMap<String, String>,
+        id_mapping: &IDMapping,
+        mission_id: i32,
+    ) -> Result<Self, ()> {
+        let begin = Instant::now();
 
         let mission_info: Mission = match mission::table
             .filter(mission::id.eq(mission_id))
@@ -444,10 +583,10 @@ impl MissionCachedInfo {
             entity_blacklist_set,
             entity_combine,
             weapon_combine,
-            &id_to_player_name,
-            &id_to_entity_game_id,
-            &id_to_weapon_game_id,
-            &id_to_resource_game_id,
+            &id_mapping.id_to_player_name,
+            &id_mapping.id_to_entity_game_id,
+            &id_mapping.id_to_weapon_game_id,
+            &id_mapping.id_to_resource_game_id,
         );
 
         info!("generated cached mission info from db for {} in {:?}(total) = {:?}(load_from_db) + {:?}(generate)", mission_id, load_from_db_elapsed + generate_elapsed, load_from_db_elapsed, generate_elapsed);
@@ -455,74 +594,76 @@ impl MissionCachedInfo {
         Ok(result)
     }
 
+    // 返回值的bool表示各子表是否均加载成功，为false时生成的是部分数据，不应写入缓存
     pub fn from_db_all(
         conn: &mut PgConnection,
         entity_blacklist_set: &HashSet<String>,
         entity_combine: &HashMap<String, String>,
         weapon_combine: &HashMap<String, String>,
-    ) -> Result<Vec<Self>, ()> {
+    ) -> Result<(Vec<(Self, Duration)>, bool), ()> {
         let begin = Instant::now();
 
-        let player_list: Vec<Player> = match player::table.load(conn) {
+        let all_mission_info = match mission::table.select(Mission::as_select()).load(conn) {
             Ok(x) => x,
             Err(e) => {
-                error!("cannot load player from db: {}", e);
+                error!("cannot load missions from db: {}", e);
                 return Err(());
             }
         };
 
-        let entity_list: Vec<Entity> = match entity::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load entity from db: {}", e);
-                return Err(());
-            }
-        };
+        Self::from_db_mission_list(
+            conn,
+            entity_blacklist_set,
+            entity_combine,
+            weapon_combine,
+            all_mission_info,
+            begin,
+        )
+    }
 
-        let resource_list: Vec<Resource> = match resource::table.load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load resource from db: {}", e);
-                return Err(());
-            }
-        };
+    // 仅生成mission_id_list中的任务，用于新加载任务后的增量缓存更新
+    pub fn from_db_list(
+        conn: &mut PgConnection,
+        entity_blacklist_set: &HashSet<String>,
+        entity_combine: &HashMap<String, String>,
+        weapon_combine: &HashMap<String, String>,
+        mission_id_list: &[i32],
+    ) -> Result<(Vec<(Self, Duration)>, bool), ()> {
+        let begin = Instant::now();
 
-        let weapon_list: Vec<Weapon> = match weapon::table.load(conn) {
+        let mission_info_list = match mission::table
+            .select(Mission::as_select())
+            .filter(mission::id.eq_any(mission_id_list))
+            .load(conn)
+        {
             Ok(x) => x,
             Err(e) => {
-                error!("cannot load weapon from db: {}", e);
+                error!("cannot load missions from db: {}", e);
                 return Err(());
             }
         };
 
-        let id_to_player_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
-
-        let id_to_entity_game_id = entity_list
-            .into_iter()
-            .map(|entity| (entity.id, entity.entity_game_id))
-            .collect::<HashMap<_, _>>();
-
-        let id_to_resource_game_id = resource_list
-            .into_iter()
-            .map(|resource| (resource.id, resource.resource_game_id))
-            .collect::<HashMap<_, _>>();
-
-        let id_to_weapon_game_id = weapon_list
-            .into_iter()
-            .map(|weapon| (weapon.id, weapon.weapon_game_id))
-            .collect::<HashMap<_, _>>();
+        Self::from_db_mission_list(
+            conn,
+            entity_blacklist_set,
+            entity_combine,
+            weapon_combine,
+            mission_info_list,
+            begin,
+        )
+    }
 
-        let all_mission_info = match mission::table.select(Mission::as_select()).load(conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot load missions from db: {}", e);
-                return Err(());
-            }
-        };
+    fn from_db_mission_list(
+        conn: &mut PgConnection,
+        entity_blacklist_set: &HashSet<String>,
+        entity_combine: &HashMap<String, String>,
+        weapon_combine: &HashMap<String, String>,
+        all_mission_info: Vec<Mission>,
+        begin: Instant,
+    ) -> Result<(Vec<(Self, Duration)>, bool), ()> {
+        let id_mapping = IDMapping::load_from_db(conn)?;
 
+        // 玩家信息是生成任务数据的基础，无法加载时整体失败；其余子表见load_child_or_empty
         let all_player_info: Vec<PlayerInfo> =
             match PlayerInfo::belonging_to(&all_mission_info).load(conn) {
                 Ok(x) => x,
@@ -532,41 +673,31 @@ impl MissionCachedInfo {
                 }
             };
 
-        let all_damage_info: Vec<DamageInfo> =
-            match DamageInfo::belonging_to(&all_mission_info).load(conn) {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("cannot load damage info from db: {}", e);
-                    return Err(());
-                }
-            };
+        let mut complete = true;
 
-        let all_kill_info: Vec<KillInfo> =
-            match KillInfo::belonging_to(&all_mission_info).load(conn) {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("cannot load kill info from db: {}", e);
-                    return Err(());
-                }
-            };
+        let all_damage_info: Vec<DamageInfo> = load_child_or_empty(
+            "damage_info",
+            DamageInfo::belonging_to(&all_mission_info).load(conn),
+            &mut complete,
+        );
 
-        let all_resource_info: Vec<ResourceInfo> =
-            match ResourceInfo::belonging_to(&all_mission_info).load(conn) {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("cannot load resource info from db: {}", e);
-                    return Err(());
-                }
-            };
+        let all_kill_info: Vec<KillInfo> = load_child_or_empty(
+            "kill_info",
+            KillInfo::belonging_to(&all_mission_info).load(conn),
+            &mut complete,
+        );
 
-        let all_supply_info: Vec<SupplyInfo> =
-            match SupplyInfo::belonging_to(&all_mission_info).load(conn) {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("cannot load supply info from db: {}", e);
-                    return Err(());
-                }
-            };
+        let all_resource_info: Vec<ResourceInfo> = load_child_or_empty(
+            "resource_info",
+            ResourceInfo::belonging_to(&all_mission_info).load(conn),
+            &mut complete,
+        );
+
+        let all_supply_info: Vec<SupplyInfo> = load_child_or_empty(
+            "supply_info",
+            SupplyInfo::belonging_to(&all_mission_info).load(conn),
+            &mut complete,
+        );
 
         let load_from_db_elapsed = begin.elapsed();
         let begin = Instant::now();
@@ -606,8 +737,9 @@ impl MissionCachedInfo {
             .map(|(children, parent)| (parent.id, children))
             .collect::<HashMap<_, _>>();
 
+        // 各任务的生成相互独立，并行生成；collect保持与all_mission_info相同的顺序
         let result = all_mission_info
-            .iter()
+            .par_iter()
             .map(|mission| {
                 Self::generate(
                     mission,
@@ -619,12 +751,11 @@ impl MissionCachedInfo {
                     &entity_blacklist_set,
                     &entity_combine,
                     &weapon_combine,
-                    &id_to_player_name,
-                    &id_to_entity_game_id,
-                    &id_to_weapon_game_id,
-                    &id_to_resource_game_id,
+                    &id_mapping.id_to_player_name,
+                    &id_mapping.id_to_entity_game_id,
+                    &id_mapping.id_to_weapon_game_id,
+                    &id_mapping.id_to_resource_game_id,
                 )
-                .0
             })
             .collect::<Vec<_>>();
 
@@ -632,7 +763,7 @@ impl MissionCachedInfo {
 
         info!("generated {} cached mission info from db in {:?}(total) = {:?}(load_from_db) + {:?}(generate)", result.len(), load_from_db_elapsed + generate_elapsed, load_from_db_elapsed, generate_elapsed);
 
-        Ok(result)
+        Ok((result, complete))
     }
 
     pub fn get_cached(
@@ -643,22 +774,11 @@ impl MissionCachedInfo {
         weapon_combine: &HashMap<String, String>,
         mission_id: i32,
     ) -> Result<Self, ()> {
-        let cached_bytes: Option<Vec<u8>> =
-            redis_conn.get(format!("mission_raw:{}", mission_id)).ok();
+        let redis_key = mission_raw_cache_key(mission_id);
 
-        let cached_content = match cached_bytes {
-            Some(x) => {
-                let decoded: MissionCachedInfo = match rmp_serde::from_read(&x[..]) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("cannot decode cached bytes: {}", e);
-                        return Err(());
-                    }
-                };
-
-                decoded
-            }
-            None => {
+        let cached_content = match get_from_redis(redis_conn, &redis_key) {
+            Ok(x) => x,
+            Err(_) => {
                 match Self::from_db(
                     db_conn,
                     entity_blacklist_set,
@@ -667,8 +787,8 @@ impl MissionCachedInfo {
                     mission_id,
                 ) {
                     Ok(x) => {
-                        let serialized = rmp_serde::to_vec(&x).unwrap();
-                        match redis_conn.set(format!("mission_raw:{}", mission_id), serialized) {
+                        let serialized = encode_cache(&x);
+                        match redis_conn.set(&redis_key, serialized) {
                             Ok(()) => x,
                             Err(e) => {
                                 error!("cannot write data to redis: {}", e);
@@ -701,32 +821,31 @@ impl MissionCachedInfo {
 
         let mut result = Vec::with_capacity(mission_list.len());
 
+        // 仅在出现缓存未命中时加载，且只加载一次
+        let mut id_mapping = None;
+
         for mission in mission_list {
-            let redis_key = format!("mission_raw:{}", mission.id);
+            let redis_key = mission_raw_cache_key(mission.id);
 
-            let cached_info = match redis_conn.get::<_, Vec<u8>>(&redis_key) {
-                Ok(x) => match rmp_serde::from_slice(&x[..]) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("cannot decode cached bytes: {}", e);
-                        return Err(());
-                    }
-                },
-                Err(e) => {
-                    warn!("cannot get mission {} from redis: {}", mission.id, e);
+            let cached_info = match get_from_redis(redis_conn, &redis_key) {
+                Ok(x) => x,
+                Err(_) => {
+                    let id_mapping = match &id_mapping {
+                        Some(x) => x,
+                        None => id_mapping.insert(IDMapping::load_from_db(db_conn)?),
+                    };
 
-                    match Self::from_db(
+                    match Self::from_db_with_mapping(
                         db_conn,
                         entity_blacklist_set,
                         entity_combine,
                         weapon_combine,
+                        id_mapping,
                         mission.id,
                     ) {
                         Ok(x) => {
-                            let serialized = rmp_serde::to_vec(&x).unwrap();
-                            if redis_conn
-                                .set::<_, Vec<u8>, ()>(&redis_key, serialized)
-                                .is_err()
+                            let serialized = encode_cache(&x);
+                            if let Err(e) = redis_conn.set::<_, Vec<u8>, ()>(&redis_key, serialized)
                             {
                                 error!("cannot write data to redis: {}", e);
                                 return Err(());
@@ -747,6 +866,73 @@ impl MissionCachedInfo {
     }
 }
 
+// 将每次击杀归属于击杀时间及之前该玩家对同一敌人最近一次造成伤害的武器，找不到伤害记录的击杀不计入
+fn attribute_kill_weapon(
+    raw_kill_info_list: &[KillInfo],
+    raw_damage_info_list: &[DamageInfo],
+    entity_blacklist_set: &HashSet<String>,
+    entity_combine: &HashMap<String, String>,
+    weapon_combine: &HashMap<String, String>,
+    id_to_entity_game_id: &HashMap<i16, String>,
+    id_to_weapon_game_id: &HashMap<i16, String>,
+) -> HashMap<String, HashMap<String, i32>> {
+    // (player_id, entity_id) -> [(time, weapon_id)]
+    let mut damage_time_map: HashMap<(i16, i16), Vec<(i16, i16)>> = HashMap::new();
+
+    for current_damage_info in raw_damage_info_list {
+        if current_damage_info.causer_type != 1 || current_damage_info.taker_type == 1 {
+            continue;
+        }
+
+        damage_time_map
+            .entry((current_damage_info.causer_id, current_damage_info.taker_id))
+            .or_default()
+            .push((current_damage_info.time, current_damage_info.weapon_id));
+    }
+
+    let mut result: HashMap<String, HashMap<String, i32>> = HashMap::new();
+
+    for current_kill_info in raw_kill_info_list {
+        let record_entity_game_id = id_to_entity_game_id
+            .get(&current_kill_info.entity_id)
+            .unwrap();
+
+        let killed_entity_game_id = entity_combine
+            .get(record_entity_game_id)
+            .unwrap_or(record_entity_game_id);
+
+        if entity_blacklist_set.contains(killed_entity_game_id) {
+            continue;
+        }
+
+        let weapon_id = match damage_time_map
+            .get(&(current_kill_info.player_id, current_kill_info.entity_id))
+            .and_then(|damage_list| {
+                damage_list
+                    .iter()
+                    .filter(|(time, _)| *time <= current_kill_info.time)
+                    .max_by_key(|(time, _)| *time)
+            }) {
+            Some(&(_, weapon_id)) => weapon_id,
+            None => continue,
+        };
+
+        let record_weapon_game_id = id_to_weapon_game_id.get(&weapon_id).unwrap();
+
+        let weapon_game_id = weapon_combine
+            .get(record_weapon_game_id)
+            .unwrap_or(record_weapon_game_id);
+
+        *result
+            .entry(weapon_game_id.clone())
+            .or_default()
+            .entry(killed_entity_game_id.clone())
+            .or_default() += 1;
+    }
+
+    result
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy)]
 pub struct PlayerRawKPIData {
     pub source_value: f64,
@@ -755,12 +941,116 @@ pub struct PlayerRawKPIData {
     pub raw_index: f64,
 }
 
+// 子表查询失败时记录错误并以空表继续，使缓存重建不会因单个子表的临时错误整体失败。
+// 代价是受影响的任务将以缺失该子表数据的状态写入缓存，直到下一次重新生成缓存；
+// 因此以error级别记录，便于确认是否需要手动重新生成缓存。
+fn merged_player_id(player_id_to_merged_id: &HashMap<i16, i16>, player_id: i16) -> i16 {
+    *player_id_to_merged_id.get(&player_id).unwrap_or(&player_id)
+}
+
+// 多个player_id合并为同一player_id时保留原player_id最小者的数据，返回发生冲突的合并后player_id
+fn merge_player_id_key<V>(
+    map: &mut HashMap<i16, V>,
+    player_id_to_merged_id: &HashMap<i16, i16>,
+) -> HashSet<i16> {
+    let mut entry_list = std::mem::take(map).into_iter().collect::<Vec<_>>();
+    entry_list.sort_unstable_by_key(|(player_id, _)| *player_id);
+
+    let mut collision = HashSet::new();
+
+    for (player_id, value) in entry_list {
+        let merged_id = merged_player_id(player_id_to_merged_id, player_id);
+
+        match map.entry(merged_id) {
+            Entry::Occupied(_) => {
+                collision.insert(merged_id);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+
+    collision
+}
+
+// 同一玩家不应在同一任务中以多个名称出现，出现时仅保留其中一个名称的数据
+fn warn_player_id_collision(mission_id: i32, collision: HashSet<i16>) {
+    if collision.is_empty() {
+        return;
+    }
+
+    let mut collision = collision.into_iter().collect::<Vec<_>>();
+    collision.sort_unstable();
+
+    warn!(
+        "mission {}: multiple aliases of merged player {:?} appear in the same mission, keeping data of the smallest player_id",
+        mission_id, collision
+    );
+}
+
+// 加载失败时以空表继续生成，并将complete置为false
+fn load_child_or_empty<T>(
+    table_name: &str,
+    result: QueryResult<Vec<T>>,
+    complete: &mut bool,
+) -> Vec<T> {
+    match result {
+        Ok(x) => x,
+        Err(e) => {
+            error!(
+                "cannot load {} from db, continuing without it: {}",
+                table_name, e
+            );
+            *complete = false;
+            Vec::new()
+        }
+    }
+}
+
+// player_id -> taker_game_id -> 伤害，友伤不计入
+fn combine_damage_info(
+    damage_info: &HashMap<i16, HashMap<String, DamagePack>>,
+) -> HashMap<i16, HashMap<String, f64>> {
+    damage_info
+        .iter()
+        .map(|(player_id, player_data)| {
+            (
+                *player_id,
+                player_data
+                    .iter()
+                    .filter(|(_, pack)| !pack.is_friendly_fire())
+                    .map(|(k, v)| (k.clone(), v.total_amount))
+                    .collect::<HashMap<_, _>>(),
+            )
+        })
+        .collect()
+}
+
+// player_id -> entity_game_id -> 击杀数，击杀记录不含玩家，全部计入
+fn combine_kill_info(
+    kill_info: &HashMap<i16, HashMap<String, KillPack>>,
+) -> HashMap<i16, HashMap<String, f64>> {
+    kill_info
+        .iter()
+        .map(|(player_id, player_data)| {
+            (
+                *player_id,
+                player_data
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.total_amount as f64))
+                    .collect::<HashMap<_, _>>(),
+            )
+        })
+        .collect()
+}
+
 #[derive(Serialize, Deserialize)]
 
 // depends on:
 // - MissionCachedInfo
 // - KPIConfig
-// - mapping: scout_special_player
+// - mapping: scout_special_player, non_mineral_resource
 pub struct MissionKPICachedInfo {
     pub mission_id: i32,
     pub damage_map: HashMap<i16, HashMap<String, f64>>,
@@ -774,43 +1064,37 @@ pub struct MissionKPICachedInfo {
 }
 
 impl MissionKPICachedInfo {
+    // 与MissionCachedInfo::merge_player_alias相同
+    pub fn merge_player_alias(&mut self, merged_player_info: &MergedPlayerInfo) {
+        let id_map = &merged_player_info.player_id_to_merged_id;
+
+        let mut collision = HashSet::new();
+
+        collision.extend(merge_player_id_key(&mut self.damage_map, id_map));
+        collision.extend(merge_player_id_key(&mut self.kill_map, id_map));
+        collision.extend(merge_player_id_key(&mut self.resource_map, id_map));
+        collision.extend(merge_player_id_key(
+            &mut self.player_id_to_kpi_character,
+            id_map,
+        ));
+        collision.extend(merge_player_id_key(&mut self.raw_kpi_data, id_map));
+
+        warn_player_id_collision(self.mission_id, collision);
+    }
+
     fn generate(
         mission_info: &MissionCachedInfo,
         character_id_to_game_id: &HashMap<i16, String>,
         player_id_to_name: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
         kpi_config: &KPIConfig,
     ) -> (Self, Duration) {
         let begin = Instant::now();
 
-        let damage_map = mission_info
-            .damage_info
-            .iter()
-            .map(|(player_id, player_data)| {
-                (
-                    *player_id,
-                    player_data
-                        .iter()
-                        .filter(|(_, pack)| pack.taker_type != 1)
-                        .map(|(k, v)| (k.clone(), v.total_amount))
-                        .collect::<HashMap<_, _>>(),
-                )
-            })
-            .collect::<HashMap<_, _>>();
+        let damage_map = combine_damage_info(&mission_info.damage_info);
 
-        let kill_map = mission_info
-            .kill_info
-            .iter()
-            .map(|(player_id, player_data)| {
-                (
-                    *player_id,
-                    player_data
-                        .iter()
-                        .map(|(k, v)| (k.clone(), v.total_amount as f64))
-                        .collect::<HashMap<_, _>>(),
-                )
-            })
-            .collect::<HashMap<_, _>>();
+        let kill_map = combine_kill_info(&mission_info.kill_info);
 
         let resource_map = &mission_info.resource_info;
 
@@ -944,7 +1228,9 @@ impl MissionKPICachedInfo {
                 .get(&player_info.player_id)
                 .unwrap_or(&HashMap::new())
                 .iter()
-                .filter(|(_, pack)| pack.taker_type == 1 && pack.taker_id != player_info.player_id)
+                .filter(|(_, pack)| {
+                    pack.is_friendly_fire() && pack.taker_id != player_info.player_id
+                })
                 .map(|(_, pack)| pack.total_amount)
                 .sum::<f64>();
 
@@ -952,7 +1238,10 @@ impl MissionKPICachedInfo {
 
             let player_ff_index = match player_overall_damage {
                 0.0..FLOAT_EPSILON => 1.0,
-                _ => friendly_fire_index(player_friendly_fire / player_overall_damage),
+                _ => friendly_fire_index(
+                    player_friendly_fire / player_overall_damage,
+                    &kpi_config.friendly_fire.unwrap_or_default(),
+                ),
             };
 
             // Nitra
@@ -966,23 +1255,32 @@ impl MissionKPICachedInfo {
             let total_nitra = *total_resource_map.get(NITRA_GAME_ID).unwrap_or(&0.0);
 
             // Minerals
+            // 非矿物资源（如信用点）不计入采集量
 
-            let player_source_minerals = resource_map
+            let player_mineral_map = resource_map
                 .get(&player_info.player_id)
                 .unwrap_or(&HashMap::new())
-                .values()
-                .sum::<f64>();
+                .iter()
+                .filter(|(resource_game_id, _)| {
+                    !non_mineral_resource_set.contains(*resource_game_id)
+                })
+                .map(|(k, &v)| (k.clone(), v))
+                .collect::<HashMap<_, _>>();
 
-            let player_weighted_minerals = apply_weight_table(
-                resource_map
-                    .get(&player_info.player_id)
-                    .unwrap_or(&HashMap::new()),
-                &kpi_config.resource_weight_table,
-            )
-            .values()
-            .sum::<f64>();
+            let player_source_minerals = player_mineral_map.values().sum::<f64>();
+
+            let player_weighted_minerals =
+                apply_weight_table(&player_mineral_map, &kpi_config.resource_weight_table)
+                    .values()
+                    .sum::<f64>();
 
-            let total_weighted_minerals = total_weighted_resource_map.values().sum::<f64>();
+            let total_weighted_minerals = total_weighted_resource_map
+                .iter()
+                .filter(|(resource_game_id, _)| {
+                    !non_mineral_resource_set.contains(*resource_game_id)
+                })
+                .map(|(_, v)| v)
+                .sum::<f64>();
 
             // Supply
 
@@ -1142,8 +1440,9 @@ impl MissionKPICachedInfo {
         character_id_to_game_id: &HashMap<i16, String>,
         player_id_to_name: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
         kpi_config: &KPIConfig,
-    ) -> Result<Vec<Self>, ()> {
+    ) -> Result<Vec<(Self, Duration)>, ()> {
         let begin = Instant::now();
         let mission_list = MissionCachedInfo::get_cached_all(
             db_conn,
@@ -1164,9 +1463,9 @@ impl MissionKPICachedInfo {
                 character_id_to_game_id,
                 player_id_to_name,
                 scout_special_player_set,
+                non_mineral_resource_set,
                 kpi_config,
-            )
-            .0;
+            );
             result.push(generated);
         }
 
@@ -1186,26 +1485,15 @@ impl MissionKPICachedInfo {
         character_id_to_game_id: &HashMap<i16, String>,
         player_id_to_name: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
         kpi_config: &KPIConfig,
         mission_id: i32,
     ) -> Result<Self, ()> {
-        let cached_bytes: Option<Vec<u8>> = redis_conn
-            .get(format!("mission_kpi_raw:{}", mission_id))
-            .ok();
-
-        let cached_content = match cached_bytes {
-            Some(x) => {
-                let decoded: MissionKPICachedInfo = match rmp_serde::from_read(&x[..]) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("cannot decode cached bytes: {}", e);
-                        return Err(());
-                    }
-                };
+        let redis_key = mission_kpi_raw_cache_key(mission_id);
 
-                decoded
-            }
-            None => {
+        let cached_content = match get_from_redis(redis_conn, &redis_key) {
+            Ok(x) => x,
+            Err(_) => {
                 let mission = MissionCachedInfo::get_cached(
                     db_conn,
                     redis_conn,
@@ -1219,11 +1507,12 @@ impl MissionKPICachedInfo {
                     character_id_to_game_id,
                     player_id_to_name,
                     scout_special_player_set,
+                    non_mineral_resource_set,
                     kpi_config,
                 )
                 .0;
-                let serialized = rmp_serde::to_vec(&generated).unwrap();
-                match redis_conn.set(format!("mission_kpi_raw:{}", mission_id), serialized) {
+                let serialized = encode_cache(&generated);
+                match redis_conn.set(&redis_key, serialized) {
                     Ok(()) => generated,
                     Err(e) => {
                         error!("cannot write data to redis: {}", e);
@@ -1245,6 +1534,7 @@ impl MissionKPICachedInfo {
         character_id_to_game_id: &HashMap<i16, String>,
         player_id_to_name: &HashMap<i16, String>,
         scout_special_player_set: &HashSet<String>,
+        non_mineral_resource_set: &HashSet<String>,
         kpi_config: &KPIConfig,
     ) -> Result<Vec<Self>, ()> {
         let mission_list = MissionCachedInfo::get_cached_all(
@@ -1259,33 +1549,22 @@ impl MissionKPICachedInfo {
 
         for mission_info in &mission_list {
             let mission_id = mission_info.mission_info.id;
-            let cached_bytes: Option<Vec<u8>> = redis_conn
-                .get(format!("mission_kpi_raw:{}", mission_id))
-                .ok();
-
-            let cached_content = match cached_bytes {
-                Some(x) => {
-                    let decoded: MissionKPICachedInfo = match rmp_serde::from_read(&x[..]) {
-                        Ok(x) => x,
-                        Err(e) => {
-                            error!("cannot decode cached bytes: {}", e);
-                            return Err(());
-                        }
-                    };
+            let redis_key = mission_kpi_raw_cache_key(mission_id);
 
-                    decoded
-                }
-                None => {
+            let cached_content = match get_from_redis(redis_conn, &redis_key) {
+                Ok(x) => x,
+                Err(_) => {
                     let generated = Self::generate(
                         &mission_info,
                         character_id_to_game_id,
                         player_id_to_name,
                         scout_special_player_set,
+                        non_mineral_resource_set,
                         kpi_config,
                     )
                     .0;
-                    let serialized = rmp_serde::to_vec(&generated).unwrap();
-                    match redis_conn.set(format!("mission_kpi_raw:{}", mission_id), serialized) {
+                    let serialized = encode_cache(&generated);
+                    match redis_conn.set(&redis_key, serialized) {
                         Ok(()) => generated,
                         Err(e) => {
                             error!("cannot write data to redis: {}", e);
@@ -1301,3 +1580,334 @@ impl MissionKPICachedInfo {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::kpi::tests::valid_kpi_config;
+
+    pub(crate) fn player_info(mission_id: i32, player_id: i16, character_id: i16) -> PlayerInfo {
+        PlayerInfo {
+            id: player_id as i32,
+            mission_id,
+            player_id,
+            character_id,
+            player_rank: 0,
+            character_rank: 0,
+            character_promotion: 0,
+            present_time: 600,
+            kill_num: 0,
+            revive_num: 0,
+            death_num: 0,
+            gold_mined: 0.0,
+            minerals_mined: 0.0,
+            player_escaped: true,
+        }
+    }
+
+    pub(crate) fn mission(mission_id: i32) -> Mission {
+        Mission {
+            id: mission_id,
+            begin_timestamp: mission_id as i64,
+            mission_time: 600,
+            mission_type_id: 0,
+            hazard_id: 5,
+            result: 0,
+            reward_credit: 0.0,
+            total_supply_count: 0,
+        }
+    }
+
+    // 每项为(player_id, character_id)，各玩家的player_index均为1.0，其余数据为空
+    pub(crate) fn cached_mission(mission_id: i32, player_list: &[(i16, i16)]) -> MissionCachedInfo {
+        MissionCachedInfo {
+            mission_info: mission(mission_id),
+            player_info: player_list
+                .iter()
+                .map(|&(player_id, character_id)| player_info(mission_id, player_id, character_id))
+                .collect(),
+            player_index: player_list.iter().map(|&(id, _)| (id, 1.0)).collect(),
+            kill_info: HashMap::new(),
+            damage_info: HashMap::new(),
+            weapon_damage_info: HashMap::new(),
+            resource_info: HashMap::new(),
+            revive_count: HashMap::new(),
+            death_count: HashMap::new(),
+            supply_info: HashMap::new(),
+            player_weapon_set: HashMap::new(),
+            kill_weapon_info: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn failing_child_query_yields_empty_table() {
+        let failed: QueryResult<Vec<SupplyInfo>> = Err(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::ClosedConnection,
+            Box::new("connection reset".to_string()),
+        ));
+
+        let mut complete = true;
+        let resource_info_list = load_child_or_empty(
+            "resource_info",
+            Ok(vec![ResourceInfo {
+                id: 1,
+                mission_id: 1,
+                player_id: 1,
+                time: 60,
+                resource_id: 0,
+                amount: 30.0,
+            }]),
+            &mut complete,
+        );
+        assert!(complete);
+
+        let supply_info_list = load_child_or_empty("supply_info", failed, &mut complete);
+        assert!(supply_info_list.is_empty());
+        assert!(!complete);
+
+        // 补给数据缺失时任务仍按其余数据生成
+        let (mission_info, _) = MissionCachedInfo::generate(
+            &mission(1),
+            &vec![player_info(1, 1, 0)],
+            &Vec::new(),
+            &Vec::new(),
+            &resource_info_list,
+            &supply_info_list,
+            &HashSet::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::from([(1, "player".to_string())]),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::from([(0, "RES_VEIN_Croppa".to_string())]),
+        );
+
+        // 部分数据仍写入缓存，读取后保留其余子表数据
+        let cached: MissionCachedInfo =
+            crate::cache::decode_cache(&crate::cache::encode_cache(&mission_info)).unwrap();
+
+        assert_eq!(cached.player_info.len(), 1);
+        assert_eq!(cached.resource_info[&1]["RES_VEIN_Croppa"], 30.0);
+        assert!(cached.supply_info.is_empty());
+    }
+
+    #[test]
+    fn kill_is_attributed_to_latest_prior_damage() {
+        let damage_info = |time: i16, weapon_id: i16| DamageInfo {
+            id: 0,
+            mission_id: 1,
+            time,
+            damage: 10.0,
+            causer_id: 1,
+            taker_id: 0,
+            weapon_id,
+            causer_type: 1,
+            taker_type: 2,
+        };
+        let kill_info = |time: i16| KillInfo {
+            id: 0,
+            mission_id: 1,
+            time,
+            player_id: 1,
+            entity_id: 0,
+        };
+
+        let result = attribute_kill_weapon(
+            &[kill_info(15), kill_info(30), kill_info(5)],
+            // 击杀之后的伤害不参与归属
+            &[damage_info(10, 0), damage_info(20, 1), damage_info(40, 0)],
+            &HashSet::new(),
+            &HashMap::new(),
+            &HashMap::from([("WPN_Pistol_B".to_string(), "WPN_Pistol_A".to_string())]),
+            &HashMap::from([(0, "ED_Spider_Grunt".to_string())]),
+            &HashMap::from([
+                (0, "WPN_Pistol_B".to_string()),
+                (1, "WPN_GrenadeLauncher".to_string()),
+            ]),
+        );
+
+        assert_eq!(result["WPN_Pistol_A"]["ED_Spider_Grunt"], 1);
+        assert_eq!(result["WPN_GrenadeLauncher"]["ED_Spider_Grunt"], 1);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn non_mineral_resource_is_excluded_from_minerals() {
+        let mission = MissionCachedInfo {
+            resource_info: HashMap::from([
+                (
+                    1,
+                    HashMap::from([
+                        ("RES_VEIN_Croppa".to_string(), 30.0),
+                        ("RES_Credits".to_string(), 1000.0),
+                    ]),
+                ),
+                (2, HashMap::from([("RES_VEIN_Croppa".to_string(), 10.0)])),
+            ]),
+            ..cached_mission(1, &[(1, 0), (2, 0)])
+        };
+
+        let kpi_config = KPIConfig {
+            character_component_weight: HashMap::new(),
+            transform_range: Vec::new(),
+            ..valid_kpi_config()
+        };
+
+        let character_id_to_game_id = HashMap::from([(0, "DRILLER".to_string())]);
+        let player_id_to_name =
+            HashMap::from([(1, "player1".to_string()), (2, "player2".to_string())]);
+
+        let minerals = |non_mineral_resource_set: &HashSet<String>| {
+            let (result, _) = MissionKPICachedInfo::generate(
+                &mission,
+                &character_id_to_game_id,
+                &player_id_to_name,
+                &HashSet::new(),
+                non_mineral_resource_set,
+                &kpi_config,
+            );
+            let data = &result.raw_kpi_data[&1][&KPIComponent::Minerals];
+            (data.source_value, data.raw_index)
+        };
+
+        assert_eq!(minerals(&HashSet::new()), (1030.0, 1030.0 / 1040.0));
+        assert_eq!(
+            minerals(&HashSet::from(["RES_Credits".to_string()])),
+            (30.0, 0.75)
+        );
+    }
+
+    #[test]
+    fn friendly_fire_is_excluded_from_damage_but_kill_is_counted() {
+        let damage_pack = |taker_type: i16, total_amount: f64| DamagePack {
+            taker_id: 0,
+            taker_type,
+            weapon_id: 0,
+            total_amount,
+        };
+        let damage_info = HashMap::from([(
+            1,
+            HashMap::from([
+                ("ED_Spider_Grunt".to_string(), damage_pack(2, 100.0)),
+                ("player2".to_string(), damage_pack(1, 30.0)),
+            ]),
+        )]);
+        let kill_info = HashMap::from([(
+            1,
+            HashMap::from([(
+                "ED_Spider_Grunt".to_string(),
+                KillPack {
+                    taker_id: 0,
+                    taker_name: "ED_Spider_Grunt".to_string(),
+                    total_amount: 3,
+                },
+            )]),
+        )]);
+
+        let damage_map = combine_damage_info(&damage_info);
+        let kill_map = combine_kill_info(&kill_info);
+
+        assert_eq!(
+            damage_map[&1],
+            HashMap::from([("ED_Spider_Grunt".to_string(), 100.0)])
+        );
+        assert_eq!(kill_map[&1]["ED_Spider_Grunt"], 3.0);
+    }
+
+    // 玩家1的别名为4，玩家2的别名为5
+    fn merged_player_info() -> MergedPlayerInfo {
+        MergedPlayerInfo {
+            player_id_to_merged_id: HashMap::from([(1, 1), (2, 2), (4, 1), (5, 2)]),
+            player_id_to_name: HashMap::from([
+                (1, "player1".to_string()),
+                (2, "player2".to_string()),
+            ]),
+            watchlist_player_id_list: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn mission_player_alias_is_merged() {
+        let damage_pack = |taker_id: i16, taker_type: i16| DamagePack {
+            taker_id,
+            taker_type,
+            weapon_id: 0,
+            total_amount: 10.0,
+        };
+
+        let mut mission = MissionCachedInfo {
+            player_index: HashMap::from([(4, 1.0), (2, 0.5)]),
+            damage_info: HashMap::from([(
+                2,
+                HashMap::from([
+                    ("player1_old".to_string(), damage_pack(4, 1)),
+                    ("ED_Spider_Grunt".to_string(), damage_pack(0, 2)),
+                ]),
+            )]),
+            revive_count: HashMap::from([(4, 1)]),
+            ..cached_mission(1, &[(4, 0), (2, 0)])
+        };
+
+        mission.merge_player_alias(&merged_player_info());
+
+        assert_eq!(
+            mission
+                .player_info
+                .iter()
+                .map(|x| x.player_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(mission.player_index, HashMap::from([(1, 1.0), (2, 0.5)]));
+        assert_eq!(mission.revive_count, HashMap::from([(1, 1)]));
+
+        // 友伤承受者替换为主名称
+        let taker_map = &mission.damage_info[&2];
+        assert_eq!(taker_map["player1"].taker_id, 1);
+        assert!(!taker_map.contains_key("player1_old"));
+        assert!(taker_map.contains_key("ED_Spider_Grunt"));
+    }
+
+    #[test]
+    fn colliding_alias_in_one_mission_keeps_smallest_player_id() {
+        let mut mission = MissionCachedInfo {
+            player_index: HashMap::from([(5, 1.0), (2, 0.5)]),
+            ..cached_mission(1, &[(5, 0), (2, 0)])
+        };
+
+        mission.merge_player_alias(&merged_player_info());
+
+        assert_eq!(mission.player_info.len(), 1);
+        assert_eq!(mission.player_info[0].player_id, 2);
+        assert_eq!(mission.player_index, HashMap::from([(2, 0.5)]));
+
+        let mut kpi_info = MissionKPICachedInfo {
+            mission_id: 1,
+            damage_map: HashMap::from([
+                (5, HashMap::from([("ED_Spider_Grunt".to_string(), 30.0)])),
+                (2, HashMap::from([("ED_Spider_Grunt".to_string(), 10.0)])),
+                (4, HashMap::from([("ED_Spider_Grunt".to_string(), 20.0)])),
+            ]),
+            kill_map: HashMap::new(),
+            resource_map: HashMap::new(),
+            total_damage_map: HashMap::new(),
+            total_kill_map: HashMap::new(),
+            total_resource_map: HashMap::new(),
+            player_id_to_kpi_character: HashMap::from([
+                (4, CharacterKPIType::Driller),
+                (5, CharacterKPIType::Scout),
+            ]),
+            raw_kpi_data: HashMap::new(),
+        };
+
+        kpi_info.merge_player_alias(&merged_player_info());
+
+        assert_eq!(kpi_info.damage_map[&1]["ED_Spider_Grunt"], 20.0);
+        assert_eq!(kpi_info.damage_map[&2]["ED_Spider_Grunt"], 10.0);
+        assert_eq!(kpi_info.damage_map.len(), 2);
+        assert_eq!(
+            kpi_info.player_id_to_kpi_character,
+            HashMap::from([(1, CharacterKPIType::Driller), (2, CharacterKPIType::Scout)])
+        );
+    }
+}
@@ -3,225 +3,803 @@ pub mod mission;
 
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::kpi::KPIConfig;
+use crate::{
+    api_parse_json_body, canonical_player_id_to_name, APIResponse, AppState, DbPool, Mapping,
+};
 use actix_web::{
-    get,
-    web::{self, Data, Json},
+    get, post,
+    web::{self, Bytes, Data, Json},
+    HttpRequest,
 };
+use derive_more::derive::Display;
 use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
 use kpi::CachedGlobalKPIState;
-use log::error;
+use log::{error, info, warn};
 use mission::{MissionCachedInfo, MissionKPICachedInfo};
 use redis::Commands;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
 
-#[derive(Serialize, Deserialize)]
+// 缓存结构发生不兼容变化时递增，旧版本写入的缓存将被视为未命中并重新生成
+pub const CACHE_FORMAT_VERSION: u32 = 3;
+
+// 缓存更新接口返回的生成耗时最长的任务数
+pub const SLOWEST_MISSION_COUNT: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct APICache {
     pub time: String,
+    // 生成耗时最长的任务：(mission_id, 生成耗时ms)，按耗时降序；不按任务生成的缓存（如global_kpi_state）为空
+    #[serde(default)]
+    pub slowest: Vec<(i32, f64)>,
 }
 
-#[get("/update_mission_raw")]
-async fn update_mission_raw_cache(
-    app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
-    redis_client: Data<redis::Client>,
-) -> Json<APIResponse<APICache>> {
-    let mapping = app_state.mapping.lock().unwrap();
+// 按耗时降序保留前count项，耗时转换为毫秒
+pub fn slowest_item(mut item_time_list: Vec<(i32, Duration)>, count: usize) -> Vec<(i32, f64)> {
+    item_time_list.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    item_time_list.truncate(count);
 
-    let entity_blacklist_set = mapping.entity_blacklist_set.clone();
-    let entity_combine = mapping.entity_combine.clone();
-    let weapon_combine = mapping.weapon_combine.clone();
+    item_time_list
+        .into_iter()
+        .map(|(id, elapsed)| (id, elapsed.as_secs_f64() * 1000.0))
+        .collect()
+}
 
-    let result = web::block(move || {
-        let begin = Instant::now();
-        let mut db_conn = match db_pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!("cannot get db connection from pool: {}", e);
-                return Err(());
-            }
-        };
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CacheUpdateStatus {
+    // 已开始重新生成缓存
+    #[serde(rename = "scheduled")]
+    Scheduled,
+    // 已有缓存更新正在进行，将在其完成后再次重新生成
+    #[serde(rename = "queued")]
+    Queued,
+}
 
-        let mut redis_conn = match redis_client.get_connection() {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get redis connection: {}", e);
-                return Err(());
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum APICacheType {
+    #[serde(rename = "mission_raw")]
+    MissionRaw,
+    #[serde(rename = "mission_kpi_raw")]
+    MissionKPIRaw,
+    #[serde(rename = "global_kpi_state")]
+    GlobalKPIState,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InvalidateCacheRequest {
+    #[serde(rename = "cacheType")]
+    pub cache_type: APICacheType,
+    // MissionRaw、MissionKPIRaw需指定任务，GlobalKPIState不可指定
+    #[serde(rename = "missionId", default)]
+    pub mission_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InvalidateCacheResult {
+    pub key: String,
+    // 该缓存此前是否存在
+    pub deleted: bool,
+}
+
+// 各类缓存的Redis key，读写缓存均应使用以下函数或cache_key
+pub const GLOBAL_KPI_STATE_CACHE_KEY: &str = "global_kpi_state";
+
+pub fn mission_raw_cache_key(mission_id: i32) -> String {
+    format!("mission_raw:{}", mission_id)
+}
+
+pub fn mission_kpi_raw_cache_key(mission_id: i32) -> String {
+    format!("mission_kpi_raw:{}", mission_id)
+}
+
+pub fn cache_key(cache_type: APICacheType, mission_id: Option<i32>) -> Result<String, String> {
+    match (cache_type, mission_id) {
+        (APICacheType::MissionRaw, Some(mission_id)) => Ok(mission_raw_cache_key(mission_id)),
+        (APICacheType::MissionKPIRaw, Some(mission_id)) => {
+            Ok(mission_kpi_raw_cache_key(mission_id))
+        }
+        (APICacheType::GlobalKPIState, None) => Ok(GLOBAL_KPI_STATE_CACHE_KEY.to_string()),
+        (APICacheType::GlobalKPIState, Some(_)) => {
+            Err("missionId must not be set for global_kpi_state".to_string())
+        }
+        (_, None) => Err("missionId is required for mission_raw and mission_kpi_raw".to_string()),
+    }
+}
+
+// 后台缓存更新的状态。更新进行中收到的请求只标记待处理，由正在进行的更新在完成后统一再执行一轮，
+// 因此重复请求不会排队多次重新生成
+#[derive(Default)]
+pub struct CacheUpdateState {
+    // 后台缓存更新是否正在进行
+    running: AtomicBool,
+    // 是否有尚未处理的缓存更新请求
+    pending: AtomicBool,
+}
+
+impl CacheUpdateState {
+    // 返回Scheduled时由调用方启动后台更新
+    pub fn request(&self) -> CacheUpdateStatus {
+        self.pending.store(true, Ordering::SeqCst);
+
+        match self.running.swap(true, Ordering::SeqCst) {
+            true => CacheUpdateStatus::Queued,
+            false => CacheUpdateStatus::Scheduled,
+        }
+    }
+
+    // 开始一轮更新，此前收到的请求均由本轮处理
+    pub fn begin_round(&self) {
+        self.pending.store(false, Ordering::SeqCst);
+    }
+
+    // 结束一轮更新，返回是否需要再执行一轮
+    pub fn finish_round(&self) -> bool {
+        if self.pending.load(Ordering::SeqCst) {
+            return true;
+        }
+
+        self.running.store(false, Ordering::SeqCst);
+
+        // 释放后可能有新的请求在此期间到达
+        self.pending.load(Ordering::SeqCst) && !self.running.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::SeqCst)
+    }
+}
+
+// 按类型重新生成缓存时使用，/cache/update_*与后台缓存更新共用。某类型更新进行中收到的请求等待其完成后只再执行一轮，
+// 等待同一轮的其余请求直接使用该轮的结果
+#[derive(Default)]
+pub struct CacheTypeUpdate {
+    // 已开始的更新轮数
+    started: AtomicU64,
+    // 等待此前的更新完成的请求数
+    waiting: AtomicU64,
+    // 最近一轮的结果，持有该锁即表示正在执行更新
+    last_result: Mutex<Option<Result<APICache, ()>>>,
+}
+
+impl CacheTypeUpdate {
+    // 请求到达时调用，返回值传给run
+    pub(crate) fn ticket(&self) -> u64 {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    // 阻塞至此前的更新完成，需在web::block中调用。ticket之后已开始过一轮时直接返回该轮结果
+    pub(crate) fn run<F>(&self, ticket: u64, f: F) -> Result<APICache, ()>
+    where
+        F: FnOnce() -> Result<APICache, ()>,
+    {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let mut last_result = self
+            .last_result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+
+        if self.started.load(Ordering::SeqCst) > ticket {
+            if let Some(result) = last_result.as_ref() {
+                return result.clone();
             }
-        };
-        let result = match MissionCachedInfo::from_db_all(
-            &mut db_conn,
-            &entity_blacklist_set,
-            &entity_combine,
-            &weapon_combine,
+        }
+
+        self.started.fetch_add(1, Ordering::SeqCst);
+        let result = f();
+        *last_result = Some(result.clone());
+
+        result
+    }
+
+    // 立即请求一轮更新并阻塞至其完成，需在web::block中调用
+    pub(crate) fn run_now<F>(&self, f: F) -> Result<APICache, ()>
+    where
+        F: FnOnce() -> Result<APICache, ()>,
+    {
+        self.run(self.ticket(), f)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.last_result.try_lock(), Err(TryLockError::WouldBlock))
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.waiting.load(Ordering::SeqCst) > 0
+    }
+
+    pub fn status(&self) -> CacheTypeStatus {
+        CacheTypeStatus {
+            working: self.is_running(),
+            pending: self.is_pending(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CacheTypeUpdateState {
+    mission_raw: CacheTypeUpdate,
+    mission_kpi_raw: CacheTypeUpdate,
+    global_kpi_state: CacheTypeUpdate,
+}
+
+impl CacheTypeUpdateState {
+    pub fn get(&self, cache_type: APICacheType) -> &CacheTypeUpdate {
+        match cache_type {
+            APICacheType::MissionRaw => &self.mission_raw,
+            APICacheType::MissionKPIRaw => &self.mission_kpi_raw,
+            APICacheType::GlobalKPIState => &self.global_kpi_state,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct CacheTypeStatus {
+    // 该类型的缓存是否正在重新生成
+    pub working: bool,
+    // 是否有请求在等待当前更新完成
+    pub pending: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CacheStatus {
+    // 后台缓存更新或任一类型的/cache/update_*是否正在进行
+    pub working: bool,
+    // 是否将在当前更新完成后再执行一轮
+    pub pending: bool,
+    // 全部缓存是否已生成，与/ready一致
+    pub ready: bool,
+    pub mission_raw: CacheTypeStatus,
+    pub mission_kpi_raw: CacheTypeStatus,
+    pub global_kpi_state: CacheTypeStatus,
+}
+
+pub fn encode_cache<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut result = CACHE_FORMAT_VERSION.to_le_bytes().to_vec();
+    result.extend(rmp_serde::to_vec(value).unwrap());
+    result
+}
+
+// 读取缓存失败的原因；调用方均重新生成缓存，区分原因仅用于日志与判断缓存是否损坏
+#[derive(Display, Debug, PartialEq)]
+pub enum CacheError {
+    // 缓存尚未生成
+    #[display("cache not found")]
+    NotFound,
+    #[display("redis error: {}", _0)]
+    Redis(String),
+    #[display(
+        "cache format changed (cached: {}, current: {})",
+        _0,
+        CACHE_FORMAT_VERSION
+    )]
+    Outdated(u32),
+    // 缓存内容损坏或无法解码
+    #[display("cannot decode cached bytes: {}", _0)]
+    Decode(String),
+}
+
+impl std::error::Error for CacheError {}
+
+pub fn decode_cache<T: DeserializeOwned>(cached_bytes: &[u8]) -> Result<T, CacheError> {
+    if cached_bytes.len() < 4 {
+        return Err(CacheError::Decode(format!(
+            "expected at least 4 bytes, got {}",
+            cached_bytes.len()
+        )));
+    }
+
+    let (version_bytes, content) = cached_bytes.split_at(4);
+    let version = u32::from_le_bytes(version_bytes.try_into().unwrap());
+
+    if version != CACHE_FORMAT_VERSION {
+        return Err(CacheError::Outdated(version));
+    }
+
+    rmp_serde::from_slice(content).map_err(|e| CacheError::Decode(e.to_string()))
+}
+
+pub fn get_from_redis<T: DeserializeOwned>(
+    redis_conn: &mut redis::Connection,
+    key: &str,
+) -> Result<T, CacheError> {
+    let result = match redis_conn.get::<_, Option<Vec<u8>>>(key) {
+        Ok(Some(x)) => decode_cache(&x),
+        Ok(None) => Err(CacheError::NotFound),
+        Err(e) => Err(CacheError::Redis(e.to_string())),
+    };
+
+    match &result {
+        Ok(_) | Err(CacheError::NotFound) => {}
+        Err(e @ CacheError::Outdated(_)) => warn!("{} for {}, regenerating", e, key),
+        Err(e) => error!("{} for {}, regenerating", e, key),
+    }
+
+    result
+}
+
+fn load_character_id_to_game_id(db_conn: &mut PgConnection) -> Result<HashMap<i16, String>, ()> {
+    let character_list = match character::table
+        .select(Character::as_select())
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get character list from db: {}", e);
+            return Err(());
+        }
+    };
+
+    Ok(character_list
+        .into_iter()
+        .map(|character| (character.id, character.character_game_id))
+        .collect())
+}
+
+fn load_player_id_to_name(
+    db_conn: &mut PgConnection,
+    player_mapping: &HashMap<String, String>,
+) -> Result<HashMap<i16, String>, ()> {
+    let player_list = match player::table.select(Player::as_select()).load(db_conn) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get player list from db: {}", e);
+            return Err(());
+        }
+    };
+
+    Ok(canonical_player_id_to_name(&player_list, player_mapping))
+}
+
+// 返回生成耗时最长的SLOWEST_MISSION_COUNT个任务
+fn write_mission_raw_cache(
+    db_conn: &mut PgConnection,
+    redis_conn: &mut redis::Connection,
+    mapping: &Mapping,
+) -> Result<Vec<(i32, f64)>, ()> {
+    let (result, complete) = match MissionCachedInfo::from_db_all(
+        db_conn,
+        &mapping.entity_blacklist_set,
+        &mapping.entity_combine,
+        &mapping.weapon_combine,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot update mission raw cache");
+            return Err(());
+        }
+    };
+
+    if !complete {
+        error!("some child tables failed to load, mission raw cache written with partial data");
+    }
+
+    let mut item_time_list = Vec::with_capacity(result.len());
+
+    for (cached_info, elapsed) in result {
+        item_time_list.push((cached_info.mission_info.id, elapsed));
+        let seralized = encode_cache(&cached_info);
+        if let Err(e) = redis_conn.set::<String, Vec<u8>, ()>(
+            mission_raw_cache_key(cached_info.mission_info.id),
+            seralized,
         ) {
-            Ok(x) => x,
-            Err(()) => {
-                error!("cannot update mission raw cache");
-                return Err(());
-            }
-        };
+            error!("cannot write data to redis: {}", e);
+            return Err(());
+        }
+    }
 
-        for cached_info in result {
-            let seralized = rmp_serde::to_vec(&cached_info).unwrap();
-            if let Err(e) = redis_conn.set::<String, Vec<u8>, ()>(
-                format!("mission_raw:{}", cached_info.mission_info.id),
-                seralized,
-            ) {
-                error!("cannot write data to redis: {}", e);
-                return Err(());
-            }
+    let _ = redis::cmd("SAVE").exec(redis_conn);
+
+    Ok(slowest_item(item_time_list, SLOWEST_MISSION_COUNT))
+}
+
+// 仅重新生成mission_id_list中任务的mission_raw缓存，其余缓存保持不变
+pub fn write_mission_raw_cache_for(
+    db_conn: &mut PgConnection,
+    redis_conn: &mut redis::Connection,
+    mapping: &Mapping,
+    mission_id_list: &[i32],
+) -> Result<(), ()> {
+    if mission_id_list.is_empty() {
+        return Ok(());
+    }
+
+    let (result, complete) = match MissionCachedInfo::from_db_list(
+        db_conn,
+        &mapping.entity_blacklist_set,
+        &mapping.entity_combine,
+        &mapping.weapon_combine,
+        mission_id_list,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot update mission raw cache for {:?}", mission_id_list);
+            return Err(());
+        }
+    };
+
+    if !complete {
+        error!("some child tables failed to load, mission raw cache written with partial data");
+    }
+
+    for (cached_info, _) in result {
+        let seralized = encode_cache(&cached_info);
+        if let Err(e) = redis_conn.set::<String, Vec<u8>, ()>(
+            mission_raw_cache_key(cached_info.mission_info.id),
+            seralized,
+        ) {
+            error!("cannot write data to redis: {}", e);
+            return Err(());
+        }
+    }
+
+    let _ = redis::cmd("SAVE").exec(redis_conn);
+
+    Ok(())
+}
+
+fn write_mission_kpi_raw_cache(
+    db_conn: &mut PgConnection,
+    redis_conn: &mut redis::Connection,
+    mapping: &Mapping,
+    kpi_config: &KPIConfig,
+) -> Result<Vec<(i32, f64)>, ()> {
+    let character_id_to_game_id = load_character_id_to_game_id(db_conn)?;
+    let player_id_to_name = load_player_id_to_name(db_conn, &mapping.player_mapping)?;
+
+    let result = match MissionKPICachedInfo::from_redis_all(
+        db_conn,
+        redis_conn,
+        &mapping.entity_blacklist_set,
+        &mapping.entity_combine,
+        &mapping.weapon_combine,
+        &character_id_to_game_id,
+        &player_id_to_name,
+        &mapping.scout_special_player_set,
+        &mapping.non_mineral_resource_set,
+        kpi_config,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot update mission kpi cache");
+            return Err(());
+        }
+    };
+
+    let mut item_time_list = Vec::with_capacity(result.len());
+
+    for (cached_info, elapsed) in result {
+        item_time_list.push((cached_info.mission_id, elapsed));
+        let seralized = encode_cache(&cached_info);
+        if let Err(e) = redis_conn.set::<String, Vec<u8>, ()>(
+            mission_kpi_raw_cache_key(cached_info.mission_id),
+            seralized,
+        ) {
+            error!("cannot write data to redis: {}", e);
+            return Err(());
+        }
+    }
+
+    let _ = redis::cmd("SAVE").exec(redis_conn);
+
+    Ok(slowest_item(item_time_list, SLOWEST_MISSION_COUNT))
+}
+
+fn write_global_kpi_state(
+    db_conn: &mut PgConnection,
+    redis_conn: &mut redis::Connection,
+    mapping: &Mapping,
+    kpi_config: KPIConfig,
+) -> Result<(), ()> {
+    let character_id_to_game_id = load_character_id_to_game_id(db_conn)?;
+    let player_id_to_name = load_player_id_to_name(db_conn, &mapping.player_mapping)?;
+
+    let invalid_mission_list = match mission_invalid::table
+        .select(MissionInvalid::as_select())
+        .load(db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get invalid mission list from db: {}", e);
+            return Err(());
         }
+    };
+
+    let invalid_mission_id_list = invalid_mission_list
+        .into_iter()
+        .map(|x| x.mission_id)
+        .collect::<Vec<_>>();
+
+    let result = match CachedGlobalKPIState::from_redis_all(
+        db_conn,
+        redis_conn,
+        &mapping.entity_blacklist_set,
+        &mapping.entity_combine,
+        &mapping.weapon_combine,
+        &invalid_mission_id_list,
+        kpi_config,
+        &player_id_to_name,
+        &character_id_to_game_id,
+        &mapping.scout_special_player_set,
+        &mapping.non_mineral_resource_set,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot update global kpi state");
+            return Err(());
+        }
+    };
+
+    let seralized = encode_cache(&result);
+    if let Err(e) = redis_conn.set::<&str, Vec<u8>, ()>(GLOBAL_KPI_STATE_CACHE_KEY, seralized) {
+        error!("cannot write data to redis: {}", e);
+        return Err(());
+    }
+
+    let _ = redis::cmd("SAVE").exec(redis_conn);
+
+    Ok(())
+}
+
+fn mission_raw_update(
+    db_pool: &DbPool,
+    redis_client: &redis::Client,
+    mapping: &Mapping,
+) -> Result<APICache, ()> {
+    let begin = Instant::now();
+    let (mut db_conn, mut redis_conn) = get_db_redis_conn(db_pool, redis_client)?;
 
-        let _ = redis::cmd("SAVE").exec(&mut redis_conn);
+    let slowest = write_mission_raw_cache(&mut db_conn, &mut redis_conn, mapping)?;
 
-        Ok(begin.elapsed())
+    Ok(APICache {
+        time: format!("{:?}", begin.elapsed()),
+        slowest,
     })
-    .await
-    .unwrap();
+}
 
-    match result {
-        Ok(d) => Json(APIResponse::ok(APICache {
-            time: format!("{:?}", d),
-        })),
+fn mission_kpi_raw_update(
+    db_pool: &DbPool,
+    redis_client: &redis::Client,
+    mapping: &Mapping,
+    kpi_config: &KPIConfig,
+) -> Result<APICache, ()> {
+    let begin = Instant::now();
+    let (mut db_conn, mut redis_conn) = get_db_redis_conn(db_pool, redis_client)?;
 
-        Err(()) => Json(APIResponse::internal_error()),
+    let slowest = write_mission_kpi_raw_cache(&mut db_conn, &mut redis_conn, mapping, kpi_config)?;
+
+    Ok(APICache {
+        time: format!("{:?}", begin.elapsed()),
+        slowest,
+    })
+}
+
+fn global_kpi_state_update(
+    db_pool: &DbPool,
+    redis_client: &redis::Client,
+    mapping: &Mapping,
+    kpi_config: KPIConfig,
+) -> Result<APICache, ()> {
+    let begin = Instant::now();
+    let (mut db_conn, mut redis_conn) = get_db_redis_conn(db_pool, redis_client)?;
+
+    write_global_kpi_state(&mut db_conn, &mut redis_conn, mapping, kpi_config)?;
+
+    Ok(APICache {
+        time: format!("{:?}", begin.elapsed()),
+        slowest: Vec::new(),
+    })
+}
+
+// 依次重新生成MissionRaw、MissionKPIRaw与GlobalKPIState，未加载KPI配置时仅重新生成MissionRaw；
+// 每一步均与同类型的/cache/update_*合并执行，同一类型的缓存不会被同时重新生成
+fn write_all_cache(
+    cache_type_update: &CacheTypeUpdateState,
+    db_pool: &DbPool,
+    redis_client: &redis::Client,
+    mapping: &Mapping,
+    kpi_config: Option<KPIConfig>,
+) -> Result<(), ()> {
+    cache_type_update
+        .get(APICacheType::MissionRaw)
+        .run_now(|| mission_raw_update(db_pool, redis_client, mapping))?;
+
+    if let Some(kpi_config) = kpi_config {
+        cache_type_update
+            .get(APICacheType::MissionKPIRaw)
+            .run_now(|| mission_kpi_raw_update(db_pool, redis_client, mapping, &kpi_config))?;
+        cache_type_update
+            .get(APICacheType::GlobalKPIState)
+            .run_now(|| global_kpi_state_update(db_pool, redis_client, mapping, kpi_config))?;
     }
+
+    Ok(())
 }
 
-#[get("/update_mission_kpi_raw")]
-async fn update_mission_kpi_cache(
+// 在后台重新生成全部缓存。若已有更新正在进行，则标记待处理，由正在进行的更新在完成后再执行一次，
+// 以保证最终使用的是最新的mapping
+pub fn schedule_cache_update(
     app_state: Data<AppState>,
     db_pool: Data<DbPool>,
     redis_client: Data<redis::Client>,
-) -> Json<APIResponse<APICache>> {
-    let mapping = app_state.mapping.lock().unwrap();
+) -> CacheUpdateStatus {
+    let status = app_state.cache_update.request();
 
-    let entity_blacklist_set = mapping.entity_blacklist_set.clone();
-    let entity_combine = mapping.entity_combine.clone();
-    let weapon_combine = mapping.weapon_combine.clone();
+    if status == CacheUpdateStatus::Queued {
+        return status;
+    }
 
-    let scout_special_player_set = mapping.scout_special_player_set.clone();
+    actix_web::rt::spawn(async move {
+        loop {
+            app_state.cache_update.begin_round();
 
-    drop(mapping);
+            let mapping = app_state.mapping.lock().unwrap().clone();
+            let kpi_config = app_state.kpi_config.lock().unwrap().clone();
 
-    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
-        Some(x) => x,
-        None => {
-            return Json(APIResponse::config_required("kpi_config"));
-        }
-    };
+            let block_app_state = app_state.clone();
+            let db_pool = db_pool.clone();
+            let redis_client = redis_client.clone();
 
-    let result = web::block(move || {
-        let begin = Instant::now();
-        let mut db_conn = match db_pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!("cannot get db connection from pool: {}", e);
-                return Err(());
-            }
-        };
+            let result = web::block(move || {
+                let begin = Instant::now();
+                write_all_cache(
+                    &block_app_state.cache_type_update,
+                    &db_pool,
+                    &redis_client,
+                    &mapping,
+                    kpi_config,
+                )?;
+                Ok::<_, ()>(begin.elapsed())
+            })
+            .await
+            .unwrap();
 
-        let mut redis_conn = match redis_client.get_connection() {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get redis connection: {}", e);
-                return Err(());
+            match result {
+                Ok(d) => {
+                    info!("scheduled cache update finished in {:?}", d);
+                    app_state.cache_ready.store(true, Ordering::SeqCst);
+                }
+                Err(()) => error!("scheduled cache update failed"),
             }
-        };
 
-        let character_list = match character::table
-            .select(Character::as_select())
-            .load(&mut db_conn)
-        {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get character list from db: {}", e);
-                return Err(());
+            if !app_state.cache_update.finish_round() {
+                break;
             }
-        };
+        }
+    });
 
-        let character_id_to_game_id = character_list
-            .into_iter()
-            .map(|character| (character.id, character.character_game_id))
-            .collect::<HashMap<_, _>>();
+    status
+}
 
-        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get player list from db: {}", e);
-                return Err(());
-            }
-        };
+#[get("/status")]
+async fn get_cache_status(app_state: Data<AppState>) -> Json<APIResponse<CacheStatus>> {
+    let cache_type_update = &app_state.cache_type_update;
+    let mission_raw = cache_type_update.get(APICacheType::MissionRaw).status();
+    let mission_kpi_raw = cache_type_update.get(APICacheType::MissionKPIRaw).status();
+    let global_kpi_state = cache_type_update.get(APICacheType::GlobalKPIState).status();
+    let type_status_list = [mission_raw, mission_kpi_raw, global_kpi_state];
 
-        let player_id_to_game_id = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
-
-        let result = match MissionKPICachedInfo::from_redis_all(
-            &mut db_conn,
-            &mut redis_conn,
-            &entity_blacklist_set,
-            &entity_combine,
-            &weapon_combine,
-            &character_id_to_game_id,
-            &player_id_to_game_id,
-            &scout_special_player_set,
-            &kpi_config,
-        ) {
-            Ok(x) => x,
-            Err(()) => {
-                error!("cannot update mission kpi cache");
-                return Err(());
-            }
-        };
+    Json(APIResponse::ok(CacheStatus {
+        working: app_state.cache_update.is_running() || type_status_list.iter().any(|x| x.working),
+        pending: app_state.cache_update.is_pending() || type_status_list.iter().any(|x| x.pending),
+        ready: app_state.cache_ready.load(Ordering::SeqCst),
+        mission_raw,
+        mission_kpi_raw,
+        global_kpi_state,
+    }))
+}
 
-        for cached_info in result {
-            let seralized = rmp_serde::to_vec(&cached_info).unwrap();
-            if let Err(e) = redis_conn.set::<String, Vec<u8>, ()>(
-                format!("mission_kpi_raw:{}", cached_info.mission_id),
-                seralized,
-            ) {
-                error!("cannot write data to redis: {}", e);
-                return Err(());
-            }
+fn get_db_redis_conn(
+    db_pool: &DbPool,
+    redis_client: &redis::Client,
+) -> Result<
+    (
+        PooledConnection<ConnectionManager<PgConnection>>,
+        redis::Connection,
+    ),
+    (),
+> {
+    let db_conn = match db_pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            error!("cannot get db connection from pool: {}", e);
+            return Err(());
         }
+    };
 
-        let _ = redis::cmd("SAVE").exec(&mut redis_conn);
+    let redis_conn = match redis_client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get redis connection: {}", e);
+            return Err(());
+        }
+    };
 
-        Ok(begin.elapsed())
-    })
-    .await
-    .unwrap();
+    Ok((db_conn, redis_conn))
+}
 
-    match result {
-        Ok(d) => Json(APIResponse::ok(APICache {
-            time: format!("{:?}", d),
-        })),
+// 同一类型的并发更新请求合并执行，见CacheTypeUpdate
+async fn run_cache_update<F>(
+    app_state: Data<AppState>,
+    cache_type: APICacheType,
+    f: F,
+) -> Json<APIResponse<APICache>>
+where
+    F: FnOnce() -> Result<APICache, ()> + Send + 'static,
+{
+    let ticket = app_state.cache_type_update.get(cache_type).ticket();
+
+    let result = web::block(move || app_state.cache_type_update.get(cache_type).run(ticket, f))
+        .await
+        .unwrap();
 
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
 
-#[get("/update_global_kpi_state")]
-async fn update_global_kpi_state(
+#[get("/update_mission_raw")]
+async fn update_mission_raw_cache(
     app_state: Data<AppState>,
     db_pool: Data<DbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<APICache>> {
-    let mapping = app_state.mapping.lock().unwrap();
+    let mapping = app_state.mapping.lock().unwrap().clone();
+
+    run_cache_update(app_state, APICacheType::MissionRaw, move || {
+        mission_raw_update(&db_pool, &redis_client, &mapping)
+    })
+    .await
+}
 
-    let entity_blacklist_set = mapping.entity_blacklist_set.clone();
-    let entity_combine = mapping.entity_combine.clone();
-    let weapon_combine = mapping.weapon_combine.clone();
+#[get("/update_mission_kpi_raw")]
+async fn update_mission_kpi_cache(
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<APICache>> {
+    let mapping = app_state.mapping.lock().unwrap().clone();
+
+    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
+        Some(x) => x,
+        None => {
+            return Json(APIResponse::config_required("kpi_config"));
+        }
+    };
+
+    run_cache_update(app_state, APICacheType::MissionKPIRaw, move || {
+        mission_kpi_raw_update(&db_pool, &redis_client, &mapping, &kpi_config)
+    })
+    .await
+}
 
-    let scout_special_player_set = mapping.scout_special_player_set.clone();
+#[get("/update_global_kpi_state")]
+async fn update_global_kpi_state(
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<APICache>> {
+    rebuild_global_kpi_state(app_state, db_pool, redis_client).await
+}
 
-    drop(mapping);
+async fn rebuild_global_kpi_state(
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<APICache>> {
+    let mapping = app_state.mapping.lock().unwrap().clone();
 
     let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
         Some(x) => x,
@@ -230,106 +808,88 @@ async fn update_global_kpi_state(
         }
     };
 
-    let result = web::block(move || {
-        let begin = Instant::now();
-        let mut db_conn = match db_pool.get() {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!("cannot get db connection from pool: {}", e);
-                return Err(());
-            }
-        };
+    run_cache_update(app_state, APICacheType::GlobalKPIState, move || {
+        global_kpi_state_update(&db_pool, &redis_client, &mapping, kpi_config)
+    })
+    .await
+}
 
-        let mut redis_conn = match redis_client.get_connection() {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get redis connection: {}", e);
-                return Err(());
-            }
-        };
+// 无效任务标记变化后在后台重新生成GlobalKPIState，与/cache/update_global_kpi_state合并执行；
+// 未加载KPI配置时不生成
+pub fn schedule_global_kpi_state_update(
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+) {
+    actix_web::rt::spawn(async move {
+        let response = rebuild_global_kpi_state(app_state, db_pool, redis_client).await;
 
-        let character_list = match character::table
-            .select(Character::as_select())
-            .load(&mut db_conn)
-        {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get character list from db: {}", e);
-                return Err(());
-            }
-        };
+        match response.code {
+            200 => info!(
+                "scheduled global kpi state update finished in {}",
+                response.data.as_ref().map_or("", |x| x.time.as_str())
+            ),
+            _ => warn!(
+                "scheduled global kpi state update skipped: {} {}",
+                response.code, response.message
+            ),
+        }
+    });
+}
 
-        let character_id_to_game_id = character_list
-            .into_iter()
-            .map(|character| (character.id, character.character_game_id))
-            .collect::<HashMap<_, _>>();
+// 删除单个缓存，下次访问时重新生成
+#[post("/invalidate")]
+async fn invalidate_cache(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    redis_client: Data<redis::Client>,
+    body: Bytes,
+) -> Json<APIResponse<InvalidateCacheResult>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
 
-        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get player list from db: {}", e);
-                return Err(());
-            }
-        };
+    let request: InvalidateCacheRequest = match api_parse_json_body(&body) {
+        Ok(x) => x,
+        Err(e) => {
+            warn!("cannot parse invalidate cache request: {}", e);
+            return Json(APIResponse::bad_request(&e));
+        }
+    };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+    let key = match cache_key(request.cache_type, request.mission_id) {
+        Ok(x) => x,
+        Err(e) => return Json(APIResponse::bad_request(&e)),
+    };
 
-        let invalid_mission_list = match mission_invalid::table
-            .select(MissionInvalid::as_select())
-            .load(&mut db_conn)
-        {
+    let result = web::block(move || {
+        let mut redis_conn = match redis_client.get_connection() {
             Ok(x) => x,
             Err(e) => {
-                error!("cannot get invalid mission list from db: {}", e);
+                error!("cannot get redis connection: {}", e);
                 return Err(());
             }
         };
 
-        let invalid_mission_id_list = invalid_mission_list
-            .into_iter()
-            .map(|x| x.mission_id)
-            .collect::<Vec<_>>();
-
-        let result = match CachedGlobalKPIState::from_redis_all(
-            &mut db_conn,
-            &mut redis_conn,
-            &entity_blacklist_set,
-            &entity_combine,
-            &weapon_combine,
-            &invalid_mission_id_list,
-            kpi_config,
-            &player_id_to_name,
-            &character_id_to_game_id,
-            &scout_special_player_set,
-        ) {
-            Ok(x) => x,
-            Err(()) => {
-                error!("cannot update global kpi state");
-                return Err(());
+        match redis_conn.del::<_, i32>(&key) {
+            Ok(deleted_count) => {
+                info!("invalidated cache {}", key);
+                Ok(InvalidateCacheResult {
+                    key,
+                    deleted: deleted_count > 0,
+                })
+            }
+            Err(e) => {
+                error!("cannot delete {} from redis: {}", key, e);
+                Err(())
             }
-        };
-
-        let seralized = rmp_serde::to_vec(&result).unwrap();
-        if let Err(e) = redis_conn.set::<&str, Vec<u8>, ()>("global_kpi_state", seralized) {
-            error!("cannot write data to redis: {}", e);
-            return Err(());
         }
-
-        let _ = redis::cmd("SAVE").exec(&mut redis_conn);
-
-        Ok(begin.elapsed())
     })
     .await
     .unwrap();
 
     match result {
-        Ok(d) => Json(APIResponse::ok(APICache {
-            time: format!("{:?}", d),
-        })),
-
+        Ok(x) => Json(APIResponse::ok(x)),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
@@ -338,4 +898,162 @@ pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(update_mission_raw_cache);
     cfg.service(update_mission_kpi_cache);
     cfg.service(update_global_kpi_state);
+    cfg.service(invalidate_cache);
+    cfg.service(get_cache_status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slowest_item_keeps_longest_in_descending_order() {
+        let item_time_list = vec![
+            (1, Duration::from_millis(5)),
+            (3, Duration::from_millis(30)),
+            (2, Duration::from_millis(30)),
+            (4, Duration::from_millis(10)),
+        ];
+
+        // 耗时相同时按id升序
+        assert_eq!(
+            slowest_item(item_time_list, 3),
+            vec![(2, 30.0), (3, 30.0), (4, 10.0)]
+        );
+        assert!(slowest_item(Vec::new(), 3).is_empty());
+    }
+
+    #[test]
+    fn repeated_request_is_coalesced_into_one_round() {
+        let state = CacheUpdateState::default();
+
+        assert_eq!(state.request(), CacheUpdateStatus::Scheduled);
+        assert_eq!(state.request(), CacheUpdateStatus::Queued);
+
+        state.begin_round();
+        assert!(!state.finish_round());
+        assert!(!state.is_running());
+
+        // 更新进行中收到的多个请求只再执行一轮
+        assert_eq!(state.request(), CacheUpdateStatus::Scheduled);
+        state.begin_round();
+        assert_eq!(state.request(), CacheUpdateStatus::Queued);
+        assert_eq!(state.request(), CacheUpdateStatus::Queued);
+        assert!(state.finish_round());
+        state.begin_round();
+        assert!(!state.finish_round());
+        assert!(!state.is_running());
+    }
+
+    #[test]
+    fn concurrent_update_of_same_type_is_coalesced() {
+        let state = CacheTypeUpdateState::default();
+        let update = state.get(APICacheType::MissionRaw);
+        let round_count = AtomicU64::new(0);
+
+        let round = |time: &'static str| {
+            let round_count = &round_count;
+            move || {
+                round_count.fetch_add(1, Ordering::SeqCst);
+                Ok(APICache {
+                    time: time.to_string(),
+                    slowest: Vec::new(),
+                })
+            }
+        };
+
+        std::thread::scope(|scope| {
+            // 第一轮在收到释放信号前不结束
+            let (started_tx, started_rx) = std::sync::mpsc::channel();
+            let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+            let first_round = round("first");
+            let first_ticket = update.ticket();
+            let first = scope.spawn(move || {
+                update.run(first_ticket, || {
+                    started_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    first_round()
+                })
+            });
+            started_rx.recv().unwrap();
+            assert_eq!(
+                update.status(),
+                CacheTypeStatus {
+                    working: true,
+                    pending: false
+                }
+            );
+
+            // 第一轮进行中到达的请求合并为一轮，其他类型不受影响
+            let second_ticket = update.ticket();
+            let third_ticket = update.ticket();
+            let second_round = round("second");
+            let third_round = round("third");
+            let second = scope.spawn(move || update.run(second_ticket, second_round));
+            let third = scope.spawn(move || update.run(third_ticket, third_round));
+
+            let other_type = state.get(APICacheType::GlobalKPIState);
+            let other_type = other_type.run(other_type.ticket(), round("other_type"));
+            assert_eq!(other_type.unwrap().time, "other_type");
+
+            // 等待第一轮完成的请求标记为待处理
+            while !update.is_pending() {
+                std::thread::yield_now();
+            }
+            assert!(!state.get(APICacheType::GlobalKPIState).is_running());
+
+            release_tx.send(()).unwrap();
+
+            let first = first.join().unwrap().unwrap();
+            let second = second.join().unwrap().unwrap();
+            let third = third.join().unwrap().unwrap();
+
+            assert_eq!(first.time, "first");
+            assert_eq!(second, third);
+        });
+
+        assert_eq!(round_count.load(Ordering::SeqCst), 3);
+        assert!(!update.is_running());
+        assert!(!update.is_pending());
+    }
+
+    #[test]
+    fn cache_key_requires_mission_id_only_for_per_mission_cache() {
+        assert_eq!(
+            cache_key(APICacheType::MissionRaw, Some(42)),
+            Ok("mission_raw:42".to_string())
+        );
+        assert_eq!(
+            cache_key(APICacheType::MissionKPIRaw, Some(42)),
+            Ok("mission_kpi_raw:42".to_string())
+        );
+        assert_eq!(
+            cache_key(APICacheType::GlobalKPIState, None),
+            Ok("global_kpi_state".to_string())
+        );
+        assert!(cache_key(APICacheType::MissionRaw, None).is_err());
+        assert!(cache_key(APICacheType::GlobalKPIState, Some(42)).is_err());
+    }
+
+    #[test]
+    fn outdated_and_corrupt_cache_are_distinguished() {
+        let encoded = encode_cache(&vec![1i32, 2, 3]);
+        assert_eq!(decode_cache::<Vec<i32>>(&encoded), Ok(vec![1, 2, 3]));
+
+        let mut outdated = encoded.clone();
+        outdated[..4].copy_from_slice(&(CACHE_FORMAT_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            decode_cache::<Vec<i32>>(&outdated),
+            Err(CacheError::Outdated(CACHE_FORMAT_VERSION + 1))
+        );
+
+        assert!(matches!(
+            decode_cache::<Vec<i32>>(&encoded[..2]),
+            Err(CacheError::Decode(_))
+        ));
+        assert!(matches!(
+            decode_cache::<String>(&encoded),
+            Err(CacheError::Decode(_))
+        ));
+    }
 }
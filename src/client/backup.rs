@@ -0,0 +1,104 @@
+use crate::db::mission_log::LogContent;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// 备份格式发生不兼容变化时递增
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const MAPPING_FILE: &str = "mapping.json";
+pub const KPI_CONFIG_FILE: &str = "kpi_config.json";
+pub const WATCHLIST_FILE: &str = "watchlist.json";
+// 无效任务标记，按begin_timestamp关联任务，恢复后任务id可能不同
+pub const MISSION_INVALID_FILE: &str = "mission_invalid.json";
+// zstd压缩的msgpack格式Vec<LogContent>，与load_mission上传的格式一致
+pub const MISSION_FILE: &str = "mission.msgpack.zst";
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    // 生成备份的工具版本
+    pub tool_version: String,
+    // 备份时服务端的KPI版本
+    pub kpi_version: String,
+    // 备份时间，Unix时间戳
+    pub created_at: i64,
+    pub endpoint_url: String,
+    pub mission_count: usize,
+    pub watchlist_count: usize,
+    pub has_kpi_config: bool,
+    // 无效任务数，旧版本备份中不含无效任务标记时为None
+    #[serde(default)]
+    pub mission_invalid_count: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BackupMissionInvalid {
+    pub begin_timestamp: i64,
+    pub reason: String,
+}
+
+impl BackupManifest {
+    pub fn load(backup_dir: &Path) -> Result<Self, String> {
+        let manifest_path = backup_dir.join(MANIFEST_FILE);
+        let content = fs::read(&manifest_path)
+            .map_err(|e| format!("cannot read {}: {}", manifest_path.to_string_lossy(), e))?;
+
+        let manifest: BackupManifest = serde_json::from_slice(&content)
+            .map_err(|e| format!("cannot parse {}: {}", manifest_path.to_string_lossy(), e))?;
+
+        if manifest.format_version != BACKUP_FORMAT_VERSION {
+            return Err(format!(
+                "unsupported backup format version {}, expected {}",
+                manifest.format_version, BACKUP_FORMAT_VERSION
+            ));
+        }
+
+        let mut required_file_list = vec![MAPPING_FILE, WATCHLIST_FILE, MISSION_FILE];
+        if manifest.has_kpi_config {
+            required_file_list.push(KPI_CONFIG_FILE);
+        }
+        if manifest.mission_invalid_count.is_some() {
+            required_file_list.push(MISSION_INVALID_FILE);
+        }
+
+        for file_name in required_file_list {
+            if !backup_dir.join(file_name).is_file() {
+                return Err(format!("missing backup file: {}", file_name));
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+pub fn decode_mission_dump(data: &[u8]) -> Result<Vec<LogContent>, String> {
+    let decompressed =
+        zstd::decode_all(data).map_err(|e| format!("cannot decompress mission dump: {}", e))?;
+
+    rmp_serde::from_slice(&decompressed).map_err(|e| format!("cannot decode mission dump: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_without_mission_invalid_count_is_accepted() {
+        let content = br#"{
+            "format_version": 1,
+            "tool_version": "0.1.0",
+            "kpi_version": "0.3.0",
+            "created_at": 0,
+            "endpoint_url": "http://host/api",
+            "mission_count": 1,
+            "watchlist_count": 1,
+            "has_kpi_config": false
+        }"#;
+
+        let manifest: BackupManifest = serde_json::from_slice(content).unwrap();
+
+        assert_eq!(manifest.mission_invalid_count, None);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::Mapping;
+use std::collections::{HashMap, HashSet};
+
+// 与load_mapping读取的mapping目录中的文件一致
+pub const MAPPING_FILE_LIST: [&str; 12] = [
+    "character.txt",
+    "entity.txt",
+    "entity_blacklist.txt",
+    "entity_combine.txt",
+    "mission_type.txt",
+    "resource.txt",
+    "weapon.txt",
+    "weapon_combine.txt",
+    "weapon_hero.txt",
+    "scout_special.txt",
+    "non_mineral_resource.txt",
+    "player.txt",
+];
+
+pub struct MappingFiles {
+    // (文件名, 文件内容)，顺序与MAPPING_FILE_LIST相同
+    pub file_list: Vec<(&'static str, String)>,
+    // 无法以文本格式还原而被跳过的条目，格式为`<文件名>: <条目>`
+    pub skipped: Vec<String>,
+}
+
+// 将mapping还原为load_mapping读取的*.txt文件，各文件中的行按字典序排列
+pub fn mapping_to_files(mapping: &Mapping) -> MappingFiles {
+    let mut skipped = Vec::new();
+
+    let file_list = vec![
+        pair_file("character.txt", &mapping.character_mapping, &mut skipped),
+        pair_file("entity.txt", &mapping.entity_mapping, &mut skipped),
+        list_file(
+            "entity_blacklist.txt",
+            &mapping.entity_blacklist_set,
+            &mut skipped,
+        ),
+        pair_file("entity_combine.txt", &mapping.entity_combine, &mut skipped),
+        pair_file(
+            "mission_type.txt",
+            &mapping.mission_type_mapping,
+            &mut skipped,
+        ),
+        pair_file("resource.txt", &mapping.resource_mapping, &mut skipped),
+        pair_file("weapon.txt", &mapping.weapon_mapping, &mut skipped),
+        pair_file("weapon_combine.txt", &mapping.weapon_combine, &mut skipped),
+        pair_file("weapon_hero.txt", &mapping.weapon_character, &mut skipped),
+        list_file(
+            "scout_special.txt",
+            &mapping.scout_special_player_set,
+            &mut skipped,
+        ),
+        list_file(
+            "non_mineral_resource.txt",
+            &mapping.non_mineral_resource_set,
+            &mut skipped,
+        ),
+        pair_file("player.txt", &mapping.player_mapping, &mut skipped),
+    ];
+
+    skipped.sort_unstable();
+
+    MappingFiles { file_list, skipped }
+}
+
+// 每行为`<key>|<value>`
+fn pair_file(
+    file_name: &'static str,
+    map: &HashMap<String, String>,
+    skipped: &mut Vec<String>,
+) -> (&'static str, String) {
+    let mut line_list = Vec::with_capacity(map.len());
+
+    for (k, v) in map {
+        if is_plain_line(k) && is_plain_line(v) && !k.contains('|') && !v.contains('|') {
+            line_list.push(format!("{}|{}", k, v));
+        } else {
+            skipped.push(format!("{}: {}|{}", file_name, k, v));
+        }
+    }
+
+    (file_name, join_sorted(line_list))
+}
+
+// 每行一项
+fn list_file(
+    file_name: &'static str,
+    set: &HashSet<String>,
+    skipped: &mut Vec<String>,
+) -> (&'static str, String) {
+    let mut line_list = Vec::with_capacity(set.len());
+
+    for item in set {
+        if is_plain_line(item) {
+            line_list.push(item.clone());
+        } else {
+            skipped.push(format!("{}: {}", file_name, item));
+        }
+    }
+
+    (file_name, join_sorted(line_list))
+}
+
+// 读取时会去除行首尾空白并跳过空行与`#`开头的注释行，这些条目无法原样还原
+fn is_plain_line(s: &str) -> bool {
+    !s.is_empty() && s.trim() == s && !s.starts_with('#') && !s.contains(['\n', '\r'])
+}
+
+fn join_sorted(mut line_list: Vec<String>) -> String {
+    line_list.sort_unstable();
+    line_list
+        .into_iter()
+        .map(|line| line + "\n")
+        .collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_files_follow_load_mapping_format() {
+        let mapping = Mapping {
+            entity_mapping: HashMap::from([
+                ("ED_B".to_string(), "b".to_string()),
+                ("ED_A".to_string(), "a".to_string()),
+                ("ED|C".to_string(), "c".to_string()),
+            ]),
+            scout_special_player_set: HashSet::from(["player".to_string(), "#x".to_string()]),
+            ..Default::default()
+        };
+
+        let mapping_files = mapping_to_files(&mapping);
+
+        assert_eq!(
+            mapping_files
+                .file_list
+                .iter()
+                .map(|(file_name, _)| *file_name)
+                .collect::<Vec<_>>(),
+            MAPPING_FILE_LIST
+        );
+
+        let file_content = |file_name: &str| {
+            mapping_files
+                .file_list
+                .iter()
+                .find(|(x, _)| *x == file_name)
+                .unwrap()
+                .1
+                .clone()
+        };
+
+        assert_eq!(file_content("entity.txt"), "ED_A|a\nED_B|b\n");
+        assert_eq!(file_content("scout_special.txt"), "player\n");
+        assert_eq!(file_content("player.txt"), "");
+        assert_eq!(
+            mapping_files.skipped,
+            vec!["entity.txt: ED|C|c", "scout_special.txt: #x"]
+        );
+    }
+}
@@ -1,9 +1,138 @@
+pub mod backup;
+pub mod mapping;
+pub mod raw_log;
+
 use crate::cache::APICache;
-use crate::APIResponse;
+use crate::info::version::VersionInfo;
+use crate::{APIResponse, ClientConfig, KPI_VERSION};
 use actix_web::web::Buf;
-use reqwest::blocking::Client;
-use reqwest::StatusCode;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::cookie::Jar;
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::{env, fs};
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+pub const DEFAULT_UPLOAD_BATCH_SIZE: usize = 50;
+
+// 无法建立连接时避免客户端一直阻塞；请求的整体超时默认不限制，以免上传大批任务等耗时请求被中断
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const CLIENT_TOKEN_ENV: &str = "MISSION_MONITOR_TOKEN";
+
+pub const DEFAULT_LAST_UPLOAD_STATE_PATH: &str = "./last_upload.json";
+
+// 请求服务端失败的原因，各类原因对应不同的退出码，便于脚本区分
+#[derive(Debug)]
+pub enum ClientError {
+    // 无法连接服务端或读取响应
+    Network(String),
+    // access token无效（403）
+    NotAuthenticated(String),
+    // 服务端尚未加载所需配置（1001）
+    ConfigRequired(String),
+    // 服务端暂时无法处理，如正在生成缓存（503）
+    Busy(String),
+    Other(String),
+}
+
+impl ClientError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ClientError::Other(_) => 1,
+            ClientError::Network(_) => 2,
+            ClientError::NotAuthenticated(_) => 3,
+            ClientError::ConfigRequired(_) => 4,
+            ClientError::Busy(_) => 5,
+        }
+    }
+
+    fn from_code(code: i32, message: String) -> Self {
+        match code {
+            403 => ClientError::NotAuthenticated(message),
+            1001 => ClientError::ConfigRequired(message),
+            503 => ClientError::Busy(message),
+            _ => ClientError::Other(message),
+        }
+    }
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Network(x)
+            | ClientError::NotAuthenticated(x)
+            | ClientError::ConfigRequired(x)
+            | ClientError::Busy(x)
+            | ClientError::Other(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl From<String> for ClientError {
+    fn from(value: String) -> Self {
+        ClientError::Other(value)
+    }
+}
+
+impl From<ClientError> for String {
+    fn from(value: ClientError) -> Self {
+        value.to_string()
+    }
+}
+
+// 供工具的main使用：输出错误并按错误类型设置退出码
+pub fn exit_with(result: Result<(), ClientError>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+// 无法建立连接或服务端暂时无法处理（503，如正在生成缓存）时按指数退避重试；其余错误状态码、API错误不重试
+pub fn send_with_retry(
+    build_request: impl Fn() -> RequestBuilder,
+    max_retries: u32,
+) -> reqwest::Result<Response> {
+    let mut retry_count = 0;
+
+    loop {
+        let reason = match build_request().send() {
+            Err(e) if e.is_connect() && retry_count < max_retries => {
+                format!("failed connecting to server: {}", e)
+            }
+            Ok(response)
+                if response.status() == StatusCode::SERVICE_UNAVAILABLE
+                    && retry_count < max_retries =>
+            {
+                "server is busy".to_string()
+            }
+            result => return result,
+        };
+
+        let delay = RETRY_BASE_DELAY * 2u32.pow(retry_count);
+        retry_count += 1;
+        println!(
+            "{}, retrying in {:?} ({}/{})",
+            reason, delay, retry_count, max_retries
+        );
+        thread::sleep(delay);
+    }
+}
 
 #[derive(Clone, Copy)]
 pub enum CacheType {
@@ -36,10 +165,11 @@ fn update_specific_cache(
     cache_type: CacheType,
     endpoint_url: &str,
     http_client: &Client,
+    max_retries: u32,
 ) -> Result<APICache, String> {
     let update_url = format!("{}{}", endpoint_url, cache_type.url_path());
 
-    match http_client.get(&update_url).send() {
+    match send_with_retry(|| http_client.get(&update_url), max_retries) {
         Ok(response) => match response.status() {
             StatusCode::OK => {
                 let body = response.bytes().expect("failed fetching response body");
@@ -72,9 +202,10 @@ pub fn update_cache(
     cache_type_list: &[CacheType],
     endpoint_url: &str,
     http_client: &Client,
+    max_retries: u32,
 ) -> Result<(), String> {
     for &cache_type in cache_type_list {
-        if let Err(e) = update_specific_cache(cache_type, endpoint_url, http_client) {
+        if let Err(e) = update_specific_cache(cache_type, endpoint_url, http_client, max_retries) {
             return Err(format!("failed updating cache {}: {}", cache_type, e));
         }
     }
@@ -82,6 +213,573 @@ pub fn update_cache(
     Ok(())
 }
 
+// 配置文件路径，默认为./config.json，可通过CONFIG_PATH环境变量设置
+pub fn client_config_path() -> Result<PathBuf, String> {
+    match env::var("CONFIG_PATH") {
+        Ok(val) => PathBuf::from_str(&val).map_err(|e| format!("invalid CONFIG_PATH: {}", e)),
+        Err(_) => Ok(PathBuf::from_str("./config.json").unwrap()),
+    }
+}
+
+pub fn load_client_config() -> Result<ClientConfig, String> {
+    let config_file_path = client_config_path()?;
+
+    let file_content = fs::read(&config_file_path).map_err(|e| {
+        format!(
+            "cannot read config file {}: {}",
+            config_file_path.to_string_lossy(),
+            e
+        )
+    })?;
+
+    let config: ClientConfig = serde_json::from_slice(&file_content[..]).map_err(|e| {
+        format!(
+            "cannot parse config file {}: {}",
+            config_file_path.to_string_lossy(),
+            e
+        )
+    })?;
+
+    let mut config = config.normalize().map_err(|e| {
+        format!(
+            "invalid config file {}: {}",
+            config_file_path.to_string_lossy(),
+            e
+        )
+    })?;
+
+    // 优先级：配置文件中的access_token > 环境变量MISSION_MONITOR_TOKEN
+    if config.access_token.is_none() {
+        config.access_token = env::var(CLIENT_TOKEN_ENV)
+            .ok()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty());
+    }
+
+    // 优先级：命令行参数--timeout > 配置文件中的timeout
+    if let Some(timeout) = timeout_from_args(env::args().skip(1))? {
+        config.timeout = Some(timeout);
+    }
+
+    // 优先级：命令行参数--retries > 配置文件中的max_retries
+    if let Some(retries) = retries_from_args(env::args().skip(1))? {
+        config.max_retries = Some(retries);
+    }
+
+    Ok(config)
+}
+
+// 写入客户端的状态文件，文件不存在时创建、存在时覆盖，所在目录不存在时一并创建
+pub fn write_state_file(file_path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = file_path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("cannot create {}: {}", parent.to_string_lossy(), e))?;
+    }
+
+    fs::write(file_path, content)
+        .map_err(|e| format!("cannot write {}: {}", file_path.to_string_lossy(), e))
+}
+
+// 各工具共用的命令行参数，覆盖配置文件中的对应值
+const CONFIG_ARGS: [&str; 2] = ["--timeout", "--retries"];
+
+// 读取命令行参数`<name> <value>`或`<name>=<value>`，多次指定时以最后一次为准
+fn value_from_args<T: FromStr>(
+    mut args: impl Iterator<Item = String>,
+    name: &str,
+) -> Result<Option<T>, String>
+where
+    T::Err: Display,
+{
+    let mut result = None;
+
+    while let Some(arg) = args.next() {
+        let value = if arg == name {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))?
+        } else if let Some(value) = arg.strip_prefix(name).and_then(|x| x.strip_prefix('=')) {
+            value.to_string()
+        } else {
+            continue;
+        };
+
+        let parsed = value
+            .parse::<T>()
+            .map_err(|e| format!("invalid value for {}: {}: {}", name, value, e))?;
+
+        result = Some(parsed);
+    }
+
+    Ok(result)
+}
+
+// 读取命令行参数`--timeout <secs>`或`--timeout=<secs>`，未指定时返回None
+pub fn timeout_from_args(args: impl Iterator<Item = String>) -> Result<Option<u64>, String> {
+    let result = value_from_args::<u64>(args, "--timeout")?;
+
+    if result == Some(0) {
+        return Err("--timeout must be greater than 0".to_string());
+    }
+
+    Ok(result)
+}
+
+// 读取命令行参数`--retries <n>`或`--retries=<n>`，未指定时返回None；为0时不重试
+pub fn retries_from_args(args: impl Iterator<Item = String>) -> Result<Option<u32>, String> {
+    value_from_args(args, "--retries")
+}
+
+// 去除--timeout、--retries及其值后的命令行参数（不含程序名），供各工具解析自身的参数
+pub fn client_args() -> Vec<String> {
+    let mut result = Vec::new();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if CONFIG_ARGS.contains(&arg.as_str()) {
+            args.next();
+        } else if !CONFIG_ARGS
+            .iter()
+            .any(|x| arg.strip_prefix(x).is_some_and(|x| x.starts_with('=')))
+        {
+            result.push(arg);
+        }
+    }
+
+    result
+}
+
+// access_token对整个服务端有效，login返回的session也保存在同一个cookie_jar中，供之后的请求使用
+fn new_cookie_jar(endpoint_url: &Url, access_token: &str) -> Arc<Jar> {
+    let cookie_jar = Arc::new(Jar::default());
+
+    cookie_jar.add_cookie_str(
+        format!("access_token = {}; Path=/", access_token).as_str(),
+        endpoint_url,
+    );
+
+    cookie_jar
+}
+
+// 所有工具均通过此函数构建Client，以确保login前后使用同一个cookie_provider
+pub fn build_cookie_client(
+    endpoint_url: &str,
+    access_token: &str,
+    timeout: Option<Duration>,
+) -> Result<Client, String> {
+    let endpoint_url = endpoint_url
+        .parse::<Url>()
+        .map_err(|e| format!("failed parsing endpoint url: {}", e))?;
+
+    let mut builder = ClientBuilder::new()
+        .cookie_provider(new_cookie_jar(&endpoint_url, access_token))
+        .connect_timeout(CONNECT_TIMEOUT);
+
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("failed building http client: {}", e))
+}
+
+// 设置了access_token时，构建后即通过/admin/login换取session
+pub fn build_http_client(config: &ClientConfig) -> Result<Client, ClientError> {
+    if config.access_token.is_none() {
+        println!("warning: no access token specified!");
+    }
+
+    let access_token = config
+        .access_token
+        .clone()
+        .unwrap_or("Rock and stone!".to_string());
+
+    let http_client = build_cookie_client(&config.endpoint_url, &access_token, config.timeout())?;
+
+    if config.access_token.is_some() {
+        login(&http_client, &config.endpoint_url, config.max_retries())?;
+    }
+
+    Ok(http_client)
+}
+
+// 使用cookie中的access_token换取session，服务端通过Set-Cookie返回，由cookie_provider保存
+pub fn login(
+    http_client: &Client,
+    endpoint_url: &str,
+    max_retries: u32,
+) -> Result<(), ClientError> {
+    post_api::<()>(
+        http_client,
+        endpoint_url,
+        "/admin/login",
+        Vec::new(),
+        max_retries,
+    )
+}
+
+pub fn fetch_api<T: DeserializeOwned>(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    max_retries: u32,
+) -> Result<T, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(|| http_client.get(&url), max_retries)
+        .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    parse_api_response(&url, response)
+}
+
+pub fn post_api<T: DeserializeOwned>(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    body: Vec<u8>,
+    max_retries: u32,
+) -> Result<T, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(|| http_client.post(&url).body(body.clone()), max_retries)
+        .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    parse_api_response(&url, response)
+}
+
+// 上传zstd压缩的数据（如load_mission），并通过Content-Encoding告知服务端压缩方式
+pub fn post_zstd_api<T: DeserializeOwned>(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    body: Vec<u8>,
+    max_retries: u32,
+) -> Result<T, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(
+        || {
+            http_client
+                .post(&url)
+                .header(reqwest::header::CONTENT_ENCODING, "zstd")
+                .body(body.clone())
+        },
+        max_retries,
+    )
+    .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    parse_api_response(&url, response)
+}
+
+// 获取非JSON格式的响应体（如导出的任务数据）；服务端出错时返回的是JSON格式的APIResponse
+pub fn fetch_raw(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    max_retries: u32,
+) -> Result<Vec<u8>, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(|| http_client.get(&url), max_retries)
+        .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.starts_with("application/json"))
+        .unwrap_or(false);
+
+    if is_json {
+        return parse_api_response::<()>(&url, response).and_then(|_| {
+            Err(ClientError::Other(format!(
+                "unexpected json response from {}",
+                url
+            )))
+        });
+    }
+
+    check_status(&url, &response)?;
+
+    response
+        .bytes()
+        .map(|x| x.to_vec())
+        .map_err(|e| ClientError::Network(format!("failed fetching response body: {}", e)))
+}
+
+// 服务端尚未加载对应配置（返回码1001）时返回None
+pub fn fetch_api_optional<T: DeserializeOwned>(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    max_retries: u32,
+) -> Result<Option<T>, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(|| http_client.get(&url), max_retries)
+        .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    let api_response: APIResponse<T> = read_api_response(&url, response)?;
+
+    match api_response.code {
+        1001 => Ok(None),
+        _ => unwrap_api_response(&url, api_response).map(Some),
+    }
+}
+
+// 旧版本服务端没有该接口（HTTP 404）时返回None，由调用方回退到旧的获取方式
+pub fn fetch_api_if_supported<T: DeserializeOwned>(
+    http_client: &Client,
+    endpoint_url: &str,
+    path: &str,
+    max_retries: u32,
+) -> Result<Option<T>, ClientError> {
+    let url = format!("{}{}", endpoint_url, path);
+
+    let response = send_with_retry(|| http_client.get(&url), max_retries)
+        .map_err(|e| ClientError::Network(format!("failed sending request to {}: {}", url, e)))?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    parse_api_response(&url, response).map(Some)
+}
+
+// 服务端未就绪（如启动时正在生成缓存）时返回503
+fn check_status(url: &str, response: &Response) -> Result<(), ClientError> {
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => {
+            let message = format!("unexpected status code from {}: {}", url, status);
+            Err(match status {
+                StatusCode::SERVICE_UNAVAILABLE => ClientError::Busy(message),
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                    ClientError::NotAuthenticated(message)
+                }
+                _ => ClientError::Other(message),
+            })
+        }
+    }
+}
+
+fn read_api_response<T: DeserializeOwned>(
+    url: &str,
+    response: Response,
+) -> Result<APIResponse<T>, ClientError> {
+    check_status(url, &response)?;
+
+    let body = response
+        .bytes()
+        .map_err(|e| ClientError::Network(format!("failed fetching response body: {}", e)))?;
+
+    serde_json::from_reader(body.reader())
+        .map_err(|e| ClientError::Other(format!("failed parsing response body: {}", e)))
+}
+
+fn unwrap_api_response<T: DeserializeOwned>(
+    url: &str,
+    api_response: APIResponse<T>,
+) -> Result<T, ClientError> {
+    match api_response.code {
+        // 对于无返回数据的接口（如load_kpi），data为null，此时T为()
+        200 => api_response
+            .data
+            .or_else(|| serde_json::from_value(serde_json::Value::Null).ok())
+            .ok_or(ClientError::Other(format!(
+                "empty response data from {}",
+                url
+            ))),
+        code => Err(ClientError::from_code(
+            code,
+            format!(
+                "request to {} failed: {} {}",
+                url, code, api_response.message
+            ),
+        )),
+    }
+}
+
+fn parse_api_response<T: DeserializeOwned>(
+    url: &str,
+    response: Response,
+) -> Result<T, ClientError> {
+    let api_response = read_api_response(url, response)?;
+    unwrap_api_response(url, api_response)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!(
+                "invalid output format: {}, expected human or json",
+                other
+            )),
+        }
+    }
+}
+
+impl OutputFormat {
+    // 解析`--output <human|json>`（或`--output=<...>`），`--json`等价于`--output json`
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = OutputFormat::default();
+
+        while let Some(arg) = args.next() {
+            if arg == "--json" {
+                result = OutputFormat::Json;
+            } else if arg == "--output" {
+                let value = args.next().ok_or("missing value for --output")?;
+                result = value.parse()?;
+            } else if let Some(value) = arg.strip_prefix("--output=") {
+                result = value.parse()?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    // Json模式下将原始API数据输出到stdout，返回true；Human模式下不输出，返回false
+    pub fn write_json<T: Serialize>(&self, value: &T) -> Result<bool, String> {
+        if *self != OutputFormat::Json {
+            return Ok(false);
+        }
+
+        let stdout = std::io::stdout();
+        let mut writer = stdout.lock();
+        serde_json::to_writer_pretty(&mut writer, value)
+            .map_err(|e| format!("failed writing json output: {}", e))?;
+        writeln!(writer).map_err(|e| format!("failed writing json output: {}", e))?;
+
+        Ok(true)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeUnit {
+    // 1024进制：KiB、MiB、GiB、TiB
+    #[default]
+    Binary,
+    // 1000进制：kB、MB、GB、TB，用于网络传输相关的大小
+    Decimal,
+}
+
+// 服务端KPI版本与本工具不一致时，KPI配置的含义可能不同；未指定--force时拒绝继续
+pub fn check_kpi_version(
+    http_client: &Client,
+    endpoint_url: &str,
+    max_retries: u32,
+    force: bool,
+) -> Result<(), String> {
+    let server_kpi_version =
+        match fetch_api::<VersionInfo>(http_client, endpoint_url, "/info/version", max_retries) {
+            Ok(x) => x.kpi_version,
+            Err(e) if force => {
+                println!(
+                    "warning: cannot get server version: {}, continuing due to --force",
+                    e
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(format!(
+                    "cannot get server version: {}, pass --force to continue anyway",
+                    e
+                ))
+            }
+        };
+
+    if server_kpi_version == KPI_VERSION {
+        return Ok(());
+    }
+
+    println!("warning: ================================================");
+    println!(
+        "warning: server KPI version {} differs from client KPI version {}",
+        server_kpi_version, KPI_VERSION
+    );
+    println!("warning: KPI config semantics may differ, update the client or the server");
+    println!("warning: ================================================");
+
+    if !force {
+        return Err("KPI version mismatch, pass --force to continue anyway".to_string());
+    }
+
+    println!("warning: continuing due to --force");
+
+    Ok(())
+}
+
+pub fn format_size(size: usize) -> String {
+    format_size_with_unit(size, SizeUnit::Binary)
+}
+
+pub fn format_size_with_unit(size: usize, unit: SizeUnit) -> String {
+    let (base, suffix_list) = match unit {
+        SizeUnit::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB"]),
+        SizeUnit::Decimal => (1000.0, ["kB", "MB", "GB", "TB"]),
+    };
+
+    if (size as f64) < base {
+        return format!("{}B", size);
+    }
+
+    let mut value = size as f64 / base;
+    let mut suffix_idx = 0;
+
+    while value >= base && suffix_idx < suffix_list.len() - 1 {
+        value /= base;
+        suffix_idx += 1;
+    }
+
+    format!("{:.2}{}", value, suffix_list[suffix_idx])
+}
+
+// 命令行中的时间：unix时间戳（秒）或RFC3339字符串（如`2024-10-01T20:00:00+08:00`）
+pub fn parse_timestamp(value: &str) -> Result<i64, String> {
+    if let Ok(x) = value.parse::<i64>() {
+        return Ok(x);
+    }
+
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|x| x.timestamp())
+        .map_err(|e| {
+            format!(
+                "invalid time: {}, expected unix timestamp or RFC3339: {}",
+                value, e
+            )
+        })
+}
+
+// 以UTC显示的任务开始时间
+pub fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|x| x.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or(timestamp.to_string())
+}
+
+pub fn format_mission_time(mission_time: i16) -> String {
+    format!("{}:{:02}", mission_time / 60, mission_time % 60)
+}
+
+pub fn format_mission_result(mission_result: i16) -> &'static str {
+    match mission_result {
+        0 => "success",
+        1 => "failed",
+        2 => "aborted",
+        _ => "unknown",
+    }
+}
+
 pub fn author_info() {
     println!("Mission Monitor backend toolset");
     println!("made by saitewasreset with love");
@@ -91,3 +789,130 @@ pub fn author_info() {
     println!("Afraid of the dark? No need, you got me!");
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_error_code_maps_to_distinct_exit_code() {
+        let unwrap = |code| {
+            unwrap_api_response::<()>("url", APIResponse::new(code, String::new(), None))
+                .unwrap_err()
+                .exit_code()
+        };
+
+        assert_eq!(unwrap(403), 3);
+        assert_eq!(unwrap(1001), 4);
+        assert_eq!(unwrap(503), 5);
+        assert_eq!(unwrap(500), 1);
+    }
+
+    #[test]
+    fn binary_size_boundaries() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1024), "1.00KiB");
+        assert_eq!(format_size(1024 * 1024 - 1), "1024.00KiB");
+        assert_eq!(format_size(1024 * 1024), "1.00MiB");
+        assert_eq!(format_size(1024 * 1024 * 1024), "1.00GiB");
+        assert_eq!(format_size(1024 * 1024 * 1024 * 1024), "1.00TiB");
+        assert_eq!(format_size(2048 * 1024 * 1024 * 1024 * 1024), "2048.00TiB");
+    }
+
+    #[test]
+    fn decimal_size_boundaries() {
+        let format = |size| format_size_with_unit(size, SizeUnit::Decimal);
+
+        assert_eq!(format(999), "999B");
+        assert_eq!(format(1000), "1.00kB");
+        assert_eq!(format(1_000_000), "1.00MB");
+        assert_eq!(format(1_000_000_000), "1.00GB");
+        assert_eq!(format(1_000_000_000_000), "1.00TB");
+        assert_eq!(format(1_500_000_000_000_000), "1500.00TB");
+    }
+
+    #[test]
+    fn timestamp_accepts_epoch_and_rfc3339() {
+        assert_eq!(parse_timestamp("1727784000"), Ok(1727784000));
+        assert_eq!(parse_timestamp("2024-10-01T20:00:00+08:00"), Ok(1727784000));
+        assert_eq!(parse_timestamp("2024-10-01T12:00:00Z"), Ok(1727784000));
+        assert!(parse_timestamp("2024-10-01").is_err());
+    }
+
+    #[test]
+    fn timeout_arg_is_parsed() {
+        let args = |x: &[&str]| {
+            x.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+
+        assert_eq!(timeout_from_args(args(&["--full"])), Ok(None));
+        assert_eq!(timeout_from_args(args(&["--timeout", "10"])), Ok(Some(10)));
+        assert_eq!(
+            timeout_from_args(args(&["--timeout=5", "--full"])),
+            Ok(Some(5))
+        );
+        assert!(timeout_from_args(args(&["--timeout"])).is_err());
+        assert!(timeout_from_args(args(&["--timeout", "ten"])).is_err());
+        assert!(timeout_from_args(args(&["--timeout=0"])).is_err());
+
+        assert_eq!(retries_from_args(args(&["--timeout", "10"])), Ok(None));
+        assert_eq!(retries_from_args(args(&["--retries", "0"])), Ok(Some(0)));
+        assert_eq!(
+            retries_from_args(args(&["--retries=5", "--full"])),
+            Ok(Some(5))
+        );
+        assert!(retries_from_args(args(&["--retries"])).is_err());
+        assert!(retries_from_args(args(&["--retries=-1"])).is_err());
+    }
+
+    #[test]
+    fn session_from_login_is_sent_with_later_request() {
+        use reqwest::cookie::CookieStore;
+        use reqwest::header::HeaderValue;
+
+        let endpoint_url = "http://localhost:8080/api".parse::<Url>().unwrap();
+        let cookie_jar = new_cookie_jar(&endpoint_url, "Rock and stone!");
+
+        // /admin/login通过Set-Cookie返回session
+        let login_url = "http://localhost:8080/api/admin/login"
+            .parse::<Url>()
+            .unwrap();
+        let set_cookie = HeaderValue::from_static("session_id=abc; Path=/; HttpOnly");
+        cookie_jar.set_cookies(&mut [&set_cookie].into_iter(), &login_url);
+
+        let mission_url = "http://localhost:8080/api/mission/load_mission"
+            .parse::<Url>()
+            .unwrap();
+        let cookie = cookie_jar.cookies(&mission_url).unwrap();
+        let cookie = cookie.to_str().unwrap();
+
+        assert!(cookie.contains("session_id=abc"));
+        assert!(cookie.contains("access_token=Rock and stone!"));
+    }
+
+    #[test]
+    fn state_file_is_created_with_parent_dir() {
+        let base_path =
+            std::env::temp_dir().join(format!("client_state_test_{}", std::process::id()));
+        let file_path = base_path.join("state").join("last_upload.json");
+
+        write_state_file(&file_path, b"{\"max_begin_timestamp\":1}").unwrap();
+        write_state_file(&file_path, b"{}").unwrap();
+
+        assert_eq!(fs::read(&file_path).unwrap(), b"{}");
+
+        fs::remove_dir_all(&base_path).unwrap();
+    }
+
+    #[test]
+    fn mission_fields_are_formatted() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_mission_time(605), "10:05");
+        assert_eq!(format_mission_result(1), "failed");
+        assert_eq!(format_mission_result(9), "unknown");
+    }
+}
@@ -0,0 +1,702 @@
+use crate::db::mission_log::*;
+use encoding_rs::{DecoderResult, UTF_16LE, UTF_8};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+const MAX_LOG_LENGTH: usize = 64 * 1024 * 1024;
+
+const LOG_PART_COUNT: usize = 6;
+
+// 只解析文件名中时间戳晚于此范围的日志时，额外向前包含的时间，保证深潜的前几层也被解析以正确识别
+const SINCE_FILE_MARGIN: i64 = 3 * 3600;
+
+// since不为None时，跳过文件名中时间戳早于since - SINCE_FILE_MARGIN的日志；
+// 日志在任务结束时写入，文件名中的时间戳不早于任务的begin_timestamp
+fn get_log_file_list(base_path: &Path, since: Option<i64>) -> Vec<PathBuf> {
+    let re = Regex::new("MissionMonitor_([0-9]+).txt").unwrap();
+    std::fs::read_dir(base_path)
+        .unwrap()
+        .into_iter()
+        .filter(|r| {
+            let file_name = r.as_ref().unwrap().file_name();
+            let file_name = file_name.as_os_str().to_str().unwrap();
+
+            match re.captures(file_name) {
+                Some(captures) => match (since, captures[1].parse::<i64>()) {
+                    (Some(since), Ok(file_timestamp)) => {
+                        file_timestamp >= since - SINCE_FILE_MARGIN
+                    }
+                    _ => true,
+                },
+                None => false,
+            }
+        })
+        .map(|r| r.unwrap().path())
+        .collect()
+}
+
+// 默认的begin_timestamp下限（2018-01-01 00:00:00 UTC），早于游戏发布时间
+pub const DEFAULT_MIN_BEGIN_TIMESTAMP: i64 = 1514764800;
+
+// 允许begin_timestamp超前当前时间的范围，容忍时区及少量时钟误差
+const MAX_FUTURE_SKEW: i64 = 24 * 3600;
+
+// 深潜相邻两层之间，前一层结束到后一层开始的最大间隔
+const DEEP_DIVE_LAYER_MAX_GAP: i64 = 600;
+
+// 记录任务的机器时钟错误时，begin_timestamp可能明显不合理，排序后会打乱深潜各层的相邻关系
+#[derive(Clone, Copy)]
+pub struct TimestampCheck {
+    pub min_begin_timestamp: i64,
+    pub max_begin_timestamp: i64,
+    // 识别深潜时跳过时间异常的任务，避免其影响相邻任务的难度标记
+    pub exclude_from_deep_dive: bool,
+}
+
+impl TimestampCheck {
+    pub fn new(min_begin_timestamp: i64, exclude_from_deep_dive: bool) -> Self {
+        TimestampCheck {
+            min_begin_timestamp,
+            max_begin_timestamp: chrono::Utc::now().timestamp() + MAX_FUTURE_SKEW,
+            exclude_from_deep_dive,
+        }
+    }
+
+    pub fn is_skewed(&self, begin_timestamp: i64) -> bool {
+        begin_timestamp < self.min_begin_timestamp || begin_timestamp > self.max_begin_timestamp
+    }
+}
+
+impl Default for TimestampCheck {
+    fn default() -> Self {
+        TimestampCheck::new(DEFAULT_MIN_BEGIN_TIMESTAMP, true)
+    }
+}
+
+pub fn parse_mission_log(
+    base_path: &Path,
+    timestamp_check: &TimestampCheck,
+) -> Result<Vec<LogContent>, String> {
+    parse_mission_log_filtered(base_path, timestamp_check, None)
+}
+
+// 仅返回begin_timestamp晚于since的任务，并尽量跳过更早的日志文件以减少解析时间
+pub fn parse_mission_log_since(
+    base_path: &Path,
+    timestamp_check: &TimestampCheck,
+    since: i64,
+) -> Result<Vec<LogContent>, String> {
+    let mission_list = parse_mission_log_filtered(base_path, timestamp_check, Some(since))?;
+
+    Ok(mission_list
+        .into_iter()
+        .filter(|mission| mission.mission_info.begin_timestamp > since)
+        .collect())
+}
+
+fn parse_mission_log_filtered(
+    base_path: &Path,
+    timestamp_check: &TimestampCheck,
+    since: Option<i64>,
+) -> Result<Vec<LogContent>, String> {
+    let file_path_list = get_log_file_list(base_path, since);
+
+    // 单个日志损坏时跳过该日志，不影响其余日志的解析
+    let mut parsed_mission_list = Vec::new();
+    for file_path in file_path_list {
+        match get_file_content_parted(&file_path) {
+            Ok(x) => parsed_mission_list.push(x),
+            Err(e) => println!(
+                "warning: skipping log {}: {}",
+                file_path.to_string_lossy(),
+                e
+            ),
+        }
+    }
+
+    parsed_mission_list.sort_unstable_by(|a, b| {
+        a.mission_info
+            .begin_timestamp
+            .cmp(&b.mission_info.begin_timestamp)
+    });
+
+    let skewed_timestamp_list = find_skewed_mission(&parsed_mission_list, timestamp_check);
+
+    for begin_timestamp in &skewed_timestamp_list {
+        println!(
+            "warning: mission {} has an implausible begin timestamp (expected {} - {}), check the clock of the recording machine",
+            begin_timestamp, timestamp_check.min_begin_timestamp, timestamp_check.max_begin_timestamp
+        );
+    }
+
+    let deep_dive_skip_list = match timestamp_check.exclude_from_deep_dive {
+        true => skewed_timestamp_list,
+        false => Vec::new(),
+    };
+
+    fix_deep_dive(&mut parsed_mission_list, &deep_dive_skip_list);
+
+    Ok(parsed_mission_list)
+}
+
+// 返回begin_timestamp不合理的任务，结果有序
+pub fn find_skewed_mission(
+    mission_list: &[LogContent],
+    timestamp_check: &TimestampCheck,
+) -> Vec<i64> {
+    let mut result = mission_list
+        .iter()
+        .map(|mission| mission.mission_info.begin_timestamp)
+        .filter(|&begin_timestamp| timestamp_check.is_skewed(begin_timestamp))
+        .collect::<Vec<_>>();
+
+    result.sort_unstable();
+
+    result
+}
+
+// mission_list需按begin_timestamp排序；skip_list中的任务不参与深潜识别，其前后的任务视为相邻
+fn fix_deep_dive(mission_list: &mut [LogContent], skip_list: &[i64]) {
+    let mut deep_dive_mission_list = Vec::new();
+
+    for mission in mission_list.iter() {
+        let first_player_join_time = mission
+            .player_info
+            .iter()
+            .map(|p| p.join_mission_time)
+            .min()
+            .unwrap();
+
+        if first_player_join_time > 0 {
+            deep_dive_mission_list.push(mission.mission_info.begin_timestamp);
+        }
+    }
+
+    let considered_idx_list = mission_list
+        .iter()
+        .enumerate()
+        .filter(|(_, mission)| {
+            skip_list
+                .binary_search(&mission.mission_info.begin_timestamp)
+                .is_err()
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    for window in considered_idx_list.windows(2) {
+        let (prev_idx, current_idx) = (window[0], window[1]);
+
+        // prev_idx < current_idx
+        let (left, right) = mission_list.split_at_mut(current_idx);
+        let prev_mission = &mut left[prev_idx];
+        let current_mission = &mut right[0];
+
+        // 对于深潜，第一层对应的first_player_join_time为0，而二、三层不为0
+        // 对于普通深潜，每一层的难度都显示为0.75（3）
+        if deep_dive_mission_list
+            .binary_search(&current_mission.mission_info.begin_timestamp)
+            .is_ok()
+        {
+            // 若当前任务first_player_join_time不为0，但前一任务为0，说明当前是第二层，前一任务是第一层
+            // 若当前任务first_player_join_time不为0，前一任务也不为0，说明当前是第三层，前一任务是第二层
+            // 注：除非在第一层手动放弃任务，否则不论第二层是否胜利，都会有第二层的数据
+            // 若在第一层手动放弃任务，则第一层无法识别为深潜
+            // 若前一任务与当前任务不相邻（如缺少前一层的日志），无法确定当前是第几层，
+            // 标记为层数未知的深潜（普通：106，精英：107），且不修改前一任务
+            if !is_adjacent_layer(prev_mission, current_mission) {
+                current_mission.mission_info.hazard_id =
+                    match current_mission.mission_info.hazard_id {
+                        3 => 106,
+                        _ => 107,
+                    };
+                continue;
+            }
+
+            match deep_dive_mission_list.binary_search(&prev_mission.mission_info.begin_timestamp) {
+                Ok(_) if prev_mission.mission_info.hazard_id >= 106 => {
+                    // 前一层层数未知，当前层同样未知
+                    current_mission.mission_info.hazard_id = prev_mission.mission_info.hazard_id;
+                }
+                Ok(_) => {
+                    // 前一层是第二层，当前是第三层
+                    if prev_mission.mission_info.hazard_id == 3
+                        || prev_mission.mission_info.hazard_id == 101
+                    {
+                        // 普通深潜
+                        prev_mission.mission_info.hazard_id = 101;
+                        current_mission.mission_info.hazard_id = 102;
+                    } else {
+                        // 精英深潜
+                        prev_mission.mission_info.hazard_id = 104;
+                        current_mission.mission_info.hazard_id = 105;
+                    }
+                }
+                Err(_) => {
+                    // 前一层是第一层，当前是第二层
+                    if prev_mission.mission_info.hazard_id == 3
+                        || prev_mission.mission_info.hazard_id == 100
+                    {
+                        // 普通深潜
+                        prev_mission.mission_info.hazard_id = 100;
+                        current_mission.mission_info.hazard_id = 101;
+                    } else {
+                        // 精英深潜
+                        prev_mission.mission_info.hazard_id = 103;
+                        current_mission.mission_info.hazard_id = 104;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 前一任务结束后不久当前任务即开始，且两者显示的难度相同（深潜各层显示的难度相同）
+// prev可能已被识别为深潜的某一层，此时不比较难度
+fn is_adjacent_layer(prev: &LogContent, current: &LogContent) -> bool {
+    let prev_end_timestamp =
+        prev.mission_info.begin_timestamp + prev.mission_info.mission_time as i64;
+    let same_hazard = prev.mission_info.hazard_id >= 100
+        || prev.mission_info.hazard_id == current.mission_info.hazard_id;
+
+    current.mission_info.begin_timestamp - prev_end_timestamp <= DEEP_DIVE_LAYER_MAX_GAP
+        && same_hazard
+}
+
+fn get_file_content_parted(file_path: &Path) -> Result<LogContent, Box<dyn std::error::Error>> {
+    let raw_file_content = std::fs::read(file_path)?;
+
+    let mut file_content = String::with_capacity(MAX_LOG_LENGTH);
+
+    if raw_file_content.starts_with(&[0xFF, 0xFE]) {
+        // UTF-16-LE
+        let mut decoder = UTF_16LE.new_decoder();
+
+        let (result, _) = decoder.decode_to_string_without_replacement(
+            &raw_file_content,
+            &mut file_content,
+            false,
+        );
+        if let DecoderResult::Malformed(_, _) = result {
+            return Err(format!(
+                "cannot decode input: {} with UTF-16-LE",
+                file_path.to_string_lossy()
+            )
+            .into());
+        }
+    } else {
+        let mut decoder = UTF_8.new_decoder();
+        let (result, _) = decoder.decode_to_string_without_replacement(
+            &raw_file_content,
+            &mut file_content,
+            true,
+        );
+        if let DecoderResult::Malformed(_, _) = result {
+            return Err(format!(
+                "cannot decode input: {} with UTF-8",
+                file_path.to_string_lossy()
+            )
+            .into());
+        }
+    }
+
+    file_content.shrink_to_fit();
+
+    let (log_version, file_content) = split_log_version(&file_content)?;
+
+    if log_version != SUPPORTED_LOG_VERSION {
+        return Err(format!(
+            "log {} has format version {}, supported version is {}",
+            file_path.to_string_lossy(),
+            log_version,
+            SUPPORTED_LOG_VERSION
+        )
+        .into());
+    }
+
+    let file_part_list = file_content.split("______").collect::<Vec<&str>>();
+
+    // 任务、玩家、伤害、击杀、资源、补给共6部分，截断的日志可能缺少后面的部分
+    if file_part_list.len() < LOG_PART_COUNT {
+        return Err(format!(
+            "log {} has {} parts, expected at least {}",
+            file_path.to_string_lossy(),
+            file_part_list.len(),
+            LOG_PART_COUNT
+        )
+        .into());
+    }
+
+    let mission_info =
+        LogMissionInfo::try_from(file_content).map_err(|e| format!("load mission info: {}", e))?;
+
+    let player_info_part = file_part_list[1];
+
+    let mut player_info: Vec<LogPlayerInfo> = Vec::new();
+
+    for player_info_line in player_info_part.lines() {
+        if player_info_line.trim().len() == 0 {
+            continue;
+        }
+        player_info.push(
+            player_info_line
+                .try_into()
+                .map_err(|e| format!("load player info: {}", e))?,
+        );
+    }
+
+    let damage_info_part = file_part_list[2];
+
+    let mut damage_info: Vec<LogDamageInfo> = Vec::new();
+
+    for damage_info_line in damage_info_part.lines() {
+        if damage_info_line.trim().len() == 0 {
+            continue;
+        }
+        damage_info.push(
+            damage_info_line
+                .try_into()
+                .map_err(|e| format!("load player info: {}", e))?,
+        );
+    }
+
+    let mut range_begin_idx: usize = 0;
+    let mut range_begin_item = &damage_info[range_begin_idx];
+
+    let mut combined_damage_info: Vec<LogDamageInfo> = Vec::with_capacity(damage_info.len());
+
+    for (i, current_damage_info) in damage_info.iter().enumerate() {
+        if !current_damage_info.combine_eq(range_begin_item) {
+            let range_end_idx = i;
+
+            let damage_sum = damage_info[range_begin_idx..range_end_idx]
+                .iter()
+                .map(|item| item.damage)
+                .sum::<f64>();
+
+            combined_damage_info.push(LogDamageInfo {
+                mission_time: range_begin_item.mission_time,
+                damage: damage_sum,
+                taker: range_begin_item.taker.clone(),
+                causer: range_begin_item.causer.clone(),
+                weapon: range_begin_item.weapon.clone(),
+                causer_type: range_begin_item.causer_type,
+                taker_type: range_begin_item.taker_type,
+            });
+
+            range_begin_idx = i;
+            range_begin_item = &damage_info[range_begin_idx];
+        }
+    }
+
+    let range_end_idx = damage_info.len();
+
+    let damage_sum = damage_info[range_begin_idx..range_end_idx]
+        .iter()
+        .map(|item| item.damage)
+        .sum::<f64>();
+
+    combined_damage_info.push(LogDamageInfo {
+        mission_time: range_begin_item.mission_time,
+        damage: damage_sum,
+        taker: range_begin_item.taker.clone(),
+        causer: range_begin_item.causer.clone(),
+        weapon: range_begin_item.weapon.clone(),
+        causer_type: range_begin_item.causer_type,
+        taker_type: range_begin_item.taker_type,
+    });
+
+    let kill_info_part = file_part_list[3];
+
+    let mut kill_info: Vec<LogKillInfo> = Vec::new();
+
+    for kill_info_line in kill_info_part.lines() {
+        if kill_info_line.trim().len() == 0 {
+            continue;
+        }
+        kill_info.push(
+            kill_info_line
+                .try_into()
+                .map_err(|e| format!("load kill info: {}", e))?,
+        );
+    }
+
+    let resource_info_part = file_part_list[4];
+
+    let mut resource_info: Vec<LogResourceInfo> = Vec::new();
+
+    for resource_info_line in resource_info_part.lines() {
+        if resource_info_line.trim().len() == 0 {
+            continue;
+        }
+        resource_info.push(
+            resource_info_line
+                .try_into()
+                .map_err(|e| format!("load resource info: {}", e))?,
+        );
+    }
+
+    let supply_info_part = file_part_list[5];
+    let mut supply_info: Vec<LogSupplyInfo> = Vec::new();
+
+    for supply_info_line in supply_info_part.lines() {
+        if supply_info_line.trim().len() == 0 {
+            continue;
+        }
+        supply_info.push(
+            supply_info_line
+                .try_into()
+                .map_err(|e| format!("load supply info: {}", e))?,
+        );
+    }
+
+    let mission_time = mission_info.mission_time;
+
+    // Fix total present time
+
+    for current_player_info in &mut player_info {
+        if current_player_info.total_present_time == 0 {
+            current_player_info.total_present_time = mission_time;
+        }
+    }
+
+    let first_player_join_time = player_info
+        .iter()
+        .map(|player| player.join_mission_time)
+        .min()
+        .ok_or(String::from("player count is 0"))?;
+
+    // Fix time for damage info, killed info, resource info, supply info
+
+    for current_damage_info in &mut damage_info {
+        current_damage_info.mission_time -= first_player_join_time;
+    }
+
+    for current_kill_info in &mut kill_info {
+        current_kill_info.mission_time -= first_player_join_time;
+    }
+
+    for current_resource_info in &mut resource_info {
+        current_resource_info.mission_time -= first_player_join_time;
+    }
+
+    for current_supply_info in &mut supply_info {
+        current_supply_info.mission_time -= first_player_join_time;
+    }
+
+    Ok(LogContent {
+        mission_info,
+        player_info,
+        damage_info: combined_damage_info,
+        kill_info,
+        resource_info,
+        supply_info,
+    })
+
+    // Identify Deep Dive in get_mission_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mission(begin_timestamp: i64, hazard_id: i16, join_mission_time: i16) -> LogContent {
+        LogContent {
+            mission_info: LogMissionInfo {
+                begin_timestamp,
+                mission_time: 600,
+                mission_type_id: "MI_DeepDive".to_string(),
+                hazard_id,
+                result: 0,
+                reward_credit: 0.0,
+                total_supply_count: 0,
+            },
+            player_info: vec![LogPlayerInfo {
+                player_name: "player".to_string(),
+                character: "DRILLER".to_string(),
+                player_rank: 1,
+                character_rank: 1,
+                character_promotion: 0,
+                join_mission_time,
+                left_mission_time: 600,
+                total_present_time: 600,
+                kill_num: 0,
+                revive_num: 0,
+                death_num: 0,
+                gold_mined: 0.0,
+                minerals_mined: 0.0,
+                player_escaped: true,
+            }],
+            damage_info: Vec::new(),
+            kill_info: Vec::new(),
+            resource_info: Vec::new(),
+            supply_info: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn future_mission_does_not_break_deep_dive() {
+        let timestamp_check = TimestampCheck {
+            min_begin_timestamp: DEFAULT_MIN_BEGIN_TIMESTAMP,
+            max_begin_timestamp: 1700100000,
+            exclude_from_deep_dive: true,
+        };
+
+        // 普通深潜的三层，以及一个时钟超前、排序后紧随第三层的任务
+        let future_timestamp = 1800000000;
+        let mut mission_list = vec![
+            mission(1700000000, 3, 0),
+            mission(1700000700, 3, 600),
+            mission(1700001400, 3, 1200),
+            mission(future_timestamp, 3, 30),
+        ];
+
+        let skewed_list = find_skewed_mission(&mission_list, &timestamp_check);
+        assert_eq!(skewed_list, vec![future_timestamp]);
+
+        fix_deep_dive(&mut mission_list, &skewed_list);
+
+        let hazard_id_list = mission_list
+            .iter()
+            .map(|mission| mission.mission_info.hazard_id)
+            .collect::<Vec<_>>();
+        assert_eq!(hazard_id_list, vec![100, 101, 102, 3]);
+
+        // 不跳过时，超前的任务与第三层不相邻，被标记为层数未知的深潜，第三层不受影响
+        let mut mission_list = vec![
+            mission(1700000000, 3, 0),
+            mission(1700000700, 3, 600),
+            mission(1700001400, 3, 1200),
+            mission(future_timestamp, 3, 30),
+        ];
+
+        fix_deep_dive(&mut mission_list, &[]);
+
+        assert_eq!(mission_list[2].mission_info.hazard_id, 102);
+        assert_eq!(mission_list[3].mission_info.hazard_id, 106);
+    }
+
+    fn hazard_id_list(mission_list: &[LogContent]) -> Vec<i16> {
+        mission_list
+            .iter()
+            .map(|mission| mission.mission_info.hazard_id)
+            .collect()
+    }
+
+    #[test]
+    fn deep_dive_without_first_layer_is_unknown_layer() {
+        // 缺少第一层的日志：第二、三层之前是一小时前的普通任务
+        let mut mission_list = vec![
+            mission(1700000000, 3, 0),
+            mission(1700004200, 3, 600),
+            mission(1700004900, 3, 1200),
+        ];
+
+        fix_deep_dive(&mut mission_list, &[]);
+
+        assert_eq!(hazard_id_list(&mission_list), vec![3, 106, 106]);
+    }
+
+    #[test]
+    fn adjacent_mission_with_other_hazard_is_not_first_layer() {
+        // 紧接在难度5任务之后的精英深潜第二层，前一任务不是第一层
+        let mut mission_list = vec![
+            mission(1700000000, 5, 0),
+            mission(1700000700, 4, 600),
+            mission(1700001400, 4, 1200),
+        ];
+
+        fix_deep_dive(&mut mission_list, &[]);
+
+        assert_eq!(hazard_id_list(&mission_list), vec![5, 107, 107]);
+
+        // 完整的精英深潜
+        let mut mission_list = vec![
+            mission(1700000000, 4, 0),
+            mission(1700000700, 4, 600),
+            mission(1700001400, 4, 1200),
+        ];
+
+        fix_deep_dive(&mut mission_list, &[]);
+
+        assert_eq!(hazard_id_list(&mission_list), vec![103, 104, 105]);
+    }
+
+    #[test]
+    fn empty_and_bom_only_log_is_skipped() {
+        let base_path = std::env::temp_dir().join(format!("raw_log_test_{}", std::process::id()));
+        std::fs::create_dir_all(&base_path).unwrap();
+
+        let empty_path = base_path.join("MissionMonitor_1700000000.txt");
+        let bom_only_path = base_path.join("MissionMonitor_1700000700.txt");
+        std::fs::write(&empty_path, []).unwrap();
+        std::fs::write(&bom_only_path, [0xFF, 0xFE]).unwrap();
+
+        assert!(get_file_content_parted(&empty_path).is_err());
+        assert!(get_file_content_parted(&bom_only_path).is_err());
+
+        let mission_list = parse_mission_log(&base_path, &TimestampCheck::default());
+
+        std::fs::remove_dir_all(&base_path).unwrap();
+
+        assert!(mission_list.unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_log_reports_part_count() {
+        let base_path =
+            std::env::temp_dir().join(format!("raw_log_truncated_test_{}", std::process::id()));
+        std::fs::create_dir_all(&base_path).unwrap();
+
+        let file_path = base_path.join("MissionMonitor_1700000000.txt");
+        std::fs::write(
+            &file_path,
+            "1700000000|600|MI_Egg|3|0|0|0\n______\n______\n",
+        )
+        .unwrap();
+
+        let result = get_file_content_parted(&file_path);
+
+        std::fs::remove_dir_all(&base_path).unwrap();
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("MissionMonitor_1700000000.txt"));
+        assert!(message.contains("has 3 parts"));
+    }
+
+    #[test]
+    fn unsupported_log_version_is_rejected() {
+        assert_eq!(
+            split_log_version("1700000000|600"),
+            Ok((1, "1700000000|600"))
+        );
+        assert_eq!(
+            split_log_version("version|1\n1700000000|600"),
+            Ok((1, "1700000000|600"))
+        );
+        assert!(split_log_version("version|x\n").is_err());
+
+        let base_path =
+            std::env::temp_dir().join(format!("raw_log_version_test_{}", std::process::id()));
+        std::fs::create_dir_all(&base_path).unwrap();
+
+        let file_path = base_path.join("MissionMonitor_1700000000.txt");
+        std::fs::write(&file_path, "version|2\n1700000000|600|MI_Egg|3|0|0|0\n").unwrap();
+
+        let result = get_file_content_parted(&file_path);
+
+        std::fs::remove_dir_all(&base_path).unwrap();
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("format version 2"));
+        assert!(message.contains(&format!("supported version is {}", SUPPORTED_LOG_VERSION)));
+    }
+
+    #[test]
+    fn timestamp_before_epoch_is_skewed() {
+        let timestamp_check = TimestampCheck::default();
+
+        assert!(timestamp_check.is_skewed(0));
+        assert!(timestamp_check.is_skewed(DEFAULT_MIN_BEGIN_TIMESTAMP - 1));
+        assert!(!timestamp_check.is_skewed(DEFAULT_MIN_BEGIN_TIMESTAMP));
+    }
+}
@@ -1,7 +1,8 @@
 use super::{CharacterDamageInfo, CharacterFriendlyFireInfo};
 use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -14,7 +15,7 @@ use std::time::Instant;
 #[get("/character")]
 async fn get_damage_character(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<HashMap<String, CharacterDamageInfo>>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -23,6 +24,7 @@ async fn get_damage_character(
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
     let character_game_id_to_name = mapping.character_mapping.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -45,7 +47,7 @@ async fn get_damage_character(
             }
         };
 
-        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -83,10 +85,7 @@ async fn get_damage_character(
 
         let character_id_to_game_id = character_list.into_iter().collect::<HashMap<_, _>>();
 
-        let player_list: Vec<(i16, String)> = match player::table
-            .select((player::id, player::player_name))
-            .load(&mut db_conn)
-        {
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
             Ok(x) => x,
             Err(e) => {
                 error!("cannot get player list from db: {}", e);
@@ -94,7 +93,12 @@ async fn get_damage_character(
             }
         };
 
-        let player_id_to_name = player_list.into_iter().collect::<HashMap<_, _>>();
+        // 合并别名后友伤承受者为主名称
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
 
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
@@ -104,7 +108,7 @@ async fn get_damage_character(
             &invalid_mission_id_list,
             &character_id_to_game_id,
             &character_game_id_to_name,
-            &player_id_to_name,
+            &merged_player_info.player_id_to_name,
         );
 
         debug!("character damage info generated in {:?}", begin.elapsed());
@@ -167,13 +171,17 @@ fn generate(
 
             let damage = player_damage_info
                 .values()
-                .filter(|&item| item.taker_type != 1)
+                .filter(|&item| !item.is_friendly_fire())
                 .map(|item| item.total_amount)
                 .sum::<f64>();
 
             for (taker_game_id, pack) in player_damage_info {
-                if pack.taker_type == 1 && pack.taker_id != player_id {
-                    let take_player_id = *player_name_to_id.get(taker_game_id).unwrap();
+                if pack.is_friendly_fire() && pack.taker_id != player_id {
+                    // 承受者不在玩家列表中（如Bot）时忽略该友伤记录
+                    let take_player_id = match player_name_to_id.get(taker_game_id) {
+                        Some(&x) if player_id_to_character_id.contains_key(&x) => x,
+                        _ => continue,
+                    };
 
                     let take_entry = player_ff_take_map.entry(take_player_id).or_default();
 
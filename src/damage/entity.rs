@@ -1,7 +1,7 @@
 use super::EntityDamageInfo;
 use crate::cache::mission::MissionCachedInfo;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -14,7 +14,7 @@ use std::time::Instant;
 #[get("/entity")]
 async fn get_damage_entity(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<EntityDamageInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -92,6 +92,101 @@ async fn get_damage_entity(
     }
 }
 
+// 各武器对该敌人造成的伤害，按伤害降序：(weapon_game_id, 武器名称, 伤害)
+#[get("/entity/{entity_game_id}/weapons")]
+async fn get_damage_entity_weapon(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    path: web::Path<String>,
+) -> Json<APIResponse<Vec<(String, String, f64)>>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine, weapon_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.weapon_mapping.clone(),
+        )
+    };
+
+    // 缓存中的敌人已按entity_combine合并，查询被合并的敌人时使用合并后的id
+    let entity_game_id = path.into_inner();
+    let entity_game_id = entity_combine
+        .get(&entity_game_id)
+        .cloned()
+        .unwrap_or(entity_game_id);
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate_entity_weapon(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &entity_game_id,
+            &weapon_mapping,
+        );
+
+        debug!(
+            "entity weapon damage info generated in {:?}",
+            begin.elapsed()
+        );
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
 fn generate(
     cached_mission_list: &[MissionCachedInfo],
     invalid_mission_id_list: &[i32],
@@ -113,7 +208,7 @@ fn generate(
     for mission in cached_mission_list {
         for data in mission.damage_info.values() {
             for (entity_game_id, pack) in data {
-                if pack.taker_type != 1 {
+                if !pack.is_friendly_fire() {
                     let entry = damage_map.entry(entity_game_id).or_default();
                     *entry += pack.total_amount;
                 }
@@ -137,3 +232,45 @@ fn generate(
         entity_mapping: entity_game_id_to_name,
     }
 }
+
+fn generate_entity_weapon(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    entity_game_id: &str,
+    weapon_game_id_to_name: &HashMap<String, String>,
+) -> Vec<(String, String, f64)> {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut damage_map: HashMap<&String, f64> = HashMap::new();
+
+    for mission in cached_mission_list
+        .iter()
+        .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+    {
+        for (weapon_game_id, weapon_pack) in &mission.weapon_damage_info {
+            if let Some(pack) = weapon_pack.detail.get(entity_game_id) {
+                if !pack.is_friendly_fire() {
+                    *damage_map.entry(weapon_game_id).or_default() += pack.total_amount;
+                }
+            }
+        }
+    }
+
+    let mut result = damage_map
+        .into_iter()
+        .map(|(weapon_game_id, damage)| {
+            let weapon_name = weapon_game_id_to_name
+                .get(weapon_game_id)
+                .unwrap_or(weapon_game_id)
+                .clone();
+            (weapon_game_id.clone(), weapon_name, damage)
+        })
+        .collect::<Vec<_>>();
+
+    result.sort_unstable_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+
+    result
+}
@@ -0,0 +1,178 @@
+use super::general::collect_mission_ff_info;
+use super::{FriendlyFireDetailInfo, FriendlyFireDetailQuery, MissionFriendlyFireDetail};
+use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
+use crate::db::schema::*;
+use crate::{canonical_player_name, APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+};
+use diesel::prelude::*;
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+#[get("/ff")]
+async fn get_friendly_fire_detail(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<FriendlyFireDetailQuery>,
+) -> Json<APIResponse<FriendlyFireDetailInfo>> {
+    let FriendlyFireDetailQuery { causer, taker } = query.into_inner();
+
+    let (entity_blacklist_set, entity_combine, weapon_combine, player_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get player list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        let player_id_to_name = merged_player_info.player_id_to_name;
+
+        // 使用别名查询时同样按其主名称匹配
+        let causer = canonical_player_name(&player_mapping, causer);
+        let taker = canonical_player_name(&player_mapping, taker);
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &player_id_to_name,
+            &causer,
+            &taker,
+        );
+
+        debug!("friendly fire detail generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(Some(x)) => Json(APIResponse::ok(x)),
+        Ok(None) => Json(APIResponse::not_found()),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 任一玩家不存在时返回None
+fn generate(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    player_id_to_name: &HashMap<i16, String>,
+    causer_name: &str,
+    taker_name: &str,
+) -> Option<FriendlyFireDetailInfo> {
+    let player_name_to_id = player_id_to_name
+        .iter()
+        .map(|(k, v)| (v, *k))
+        .collect::<HashMap<_, _>>();
+
+    let causer_id = *player_name_to_id.get(&causer_name.to_string())?;
+    let taker_id = *player_name_to_id.get(&taker_name.to_string())?;
+
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut mission = Vec::new();
+
+    for cached_mission_info in cached_mission_list
+        .iter()
+        .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+    {
+        let damage =
+            collect_mission_ff_info(cached_mission_info, player_id_to_name, &player_name_to_id)
+                .into_iter()
+                .filter(|info| info.causer_id == causer_id && info.taker_id == taker_id)
+                .map(|info| info.total_amount)
+                .sum::<f64>();
+
+        if damage > 0.0 {
+            mission.push(MissionFriendlyFireDetail {
+                mission_id: cached_mission_info.mission_info.id,
+                begin_timestamp: cached_mission_info.mission_info.begin_timestamp,
+                damage,
+            });
+        }
+    }
+
+    mission.sort_unstable_by(|a, b| {
+        a.begin_timestamp
+            .cmp(&b.begin_timestamp)
+            .then_with(|| a.mission_id.cmp(&b.mission_id))
+    });
+
+    Some(FriendlyFireDetailInfo {
+        total_damage: mission.iter().map(|item| item.damage).sum(),
+        game_count: mission.len() as i32,
+        mission,
+    })
+}
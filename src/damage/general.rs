@@ -2,7 +2,7 @@ use super::{FriendlyFireData, OverallDamageInfo, PlayerDamageInfo, PlayerFriendl
 use crate::cache::mission::MissionCachedInfo;
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool, RecentWindowInfo};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -13,7 +13,7 @@ use log::error;
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
-struct MissionFriendlyFireInfo {
+pub(super) struct MissionFriendlyFireInfo {
     pub causer_id: i16,
     pub taker_id: i16,
     pub causer_name: String,
@@ -24,7 +24,7 @@ struct MissionFriendlyFireInfo {
 #[get("/")]
 async fn get_overall_damage_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<OverallDamageInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -32,10 +32,13 @@ async fn get_overall_damage_info(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
     let entity_mapping = mapping.entity_mapping.clone();
 
     drop(mapping);
 
+    let recent_window = app_state.recent_window;
+
     let result = web::block(move || {
         let begin = Instant::now();
 
@@ -55,7 +58,7 @@ async fn get_overall_damage_info(
             }
         };
 
-        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -89,16 +92,17 @@ async fn get_overall_damage_info(
                 }
             };
 
-        let player_id_list = player_list
-            .iter()
-            .filter(|item| item.friend)
-            .map(|item| item.id)
-            .collect::<Vec<_>>();
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
 
-        let player_id_to_name = player_list
-            .iter()
-            .map(|item| (item.id, item.player_name.clone()))
-            .collect::<HashMap<_, _>>();
+        let MergedPlayerInfo {
+            watchlist_player_id_list: player_id_list,
+            player_id_to_name,
+            ..
+        } = merged_player_info;
 
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
@@ -108,6 +112,7 @@ async fn get_overall_damage_info(
             &invalid_mission_id_list,
             &player_id_list,
             &player_id_to_name,
+            recent_window,
         );
 
         debug!("overall damage info generated in {:?}", begin.elapsed());
@@ -132,6 +137,7 @@ fn generate_for_mission_list(
     invalid_mission_id_list: &[i32],
     player_id_list: &[i16],
     player_id_to_name: &HashMap<i16, String>,
+    recent_window: RecentWindowInfo,
 ) -> (
     HashMap<String, PlayerDamageInfo>,
     HashMap<String, PlayerDamageInfo>,
@@ -168,13 +174,7 @@ fn generate_for_mission_list(
     for (player_id, player_mission_list) in mission_by_player {
         let overall_list = &player_mission_list[..];
 
-        let mut recent_count = player_mission_list.len() / 10;
-
-        if recent_count < 10 {
-            recent_count = 10.min(player_mission_list.len());
-        }
-
-        let prev_limit = player_mission_list.len() - recent_count;
+        let prev_limit = recent_window.prev_count(player_mission_list.len());
 
         let prev_list = &player_mission_list[..prev_limit];
 
@@ -192,6 +192,35 @@ fn generate_for_mission_list(
     (prev, overall)
 }
 
+// 某一任务中玩家之间的友伤记录
+pub(super) fn collect_mission_ff_info(
+    cached_mission_info: &MissionCachedInfo,
+    player_id_to_name: &HashMap<i16, String>,
+    player_name_to_id: &HashMap<&String, i16>,
+) -> Vec<MissionFriendlyFireInfo> {
+    let mut result = Vec::new();
+
+    for (causer_player_id, taker_map) in &cached_mission_info.damage_info {
+        let causer_player_name = player_id_to_name.get(causer_player_id).unwrap();
+        for (taker_name, pack) in taker_map.iter().filter(|(_, pack)| pack.is_friendly_fire()) {
+            // 承受者不在玩家列表中（如Bot）时忽略该友伤记录
+            let taker_id = match player_name_to_id.get(taker_name) {
+                Some(&x) => x,
+                None => continue,
+            };
+            result.push(MissionFriendlyFireInfo {
+                causer_id: *causer_player_id,
+                taker_id,
+                causer_name: causer_player_name.clone(),
+                taker_name: taker_name.clone(),
+                total_amount: pack.total_amount,
+            });
+        }
+    }
+
+    result
+}
+
 fn generate_for_player(
     player_id: i16,
     friend_player_id_set: &HashSet<i16>,
@@ -215,7 +244,7 @@ fn generate_for_player(
         if let Some(damage_by_entity) = cached_mission_info.damage_info.get(&player_id) {
             damage_by_entity
                 .iter()
-                .filter(|(_, &pack)| pack.taker_type != 1)
+                .filter(|(_, &pack)| !pack.is_friendly_fire())
                 .for_each(|(entity_game_id, &pack)| {
                     let entry = damage_map.entry(entity_game_id.clone()).or_default();
 
@@ -231,27 +260,14 @@ fn generate_for_player(
             })
         }
 
-        cached_mission_info
-            .damage_info
-            .iter()
-            .for_each(|(causer_player_id, taker_map)| {
-                let causer_player_name = player_id_to_name.get(causer_player_id).unwrap();
-                taker_map
-                    .iter()
-                    .filter(|(_, &pack)| pack.taker_type == 1)
-                    .for_each(|(taker_name, pack)| {
-                        let mission_ff_list = mission_ff_map
-                            .entry(cached_mission_info.mission_info.id)
-                            .or_default();
-                        mission_ff_list.push(MissionFriendlyFireInfo {
-                            causer_id: *causer_player_id,
-                            taker_id: *player_name_to_id.get(&taker_name).unwrap(),
-                            causer_name: causer_player_name.clone(),
-                            taker_name: taker_name.clone(),
-                            total_amount: pack.total_amount,
-                        });
-                    })
-            })
+        mission_ff_map
+            .entry(cached_mission_info.mission_info.id)
+            .or_default()
+            .extend(collect_mission_ff_info(
+                cached_mission_info,
+                player_id_to_name,
+                &player_name_to_id,
+            ));
     }
 
     for (_, ff_info_list) in mission_ff_map {
@@ -317,3 +333,37 @@ fn generate_for_player(
         valid_game_count: player_cached_mission_list.len() as i32,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::mission::tests::cached_mission;
+    use crate::damage::DamagePack;
+
+    #[test]
+    fn friendly_fire_against_unknown_player_is_skipped() {
+        let mission = MissionCachedInfo {
+            damage_info: HashMap::from([(
+                1,
+                HashMap::from([(
+                    "UnknownBot".to_string(),
+                    DamagePack {
+                        taker_id: 99,
+                        taker_type: 1,
+                        weapon_id: 0,
+                        total_amount: 10.0,
+                    },
+                )]),
+            )]),
+            ..cached_mission(1, &[(1, 0)])
+        };
+
+        let player_id_to_name = HashMap::from([(1, "player".to_string())]);
+        let friend_player_id_set = HashSet::from([1]);
+
+        let result = generate_for_player(1, &friend_player_id_set, &player_id_to_name, &[&mission]);
+
+        assert!(result.ff.cause.is_empty());
+        assert!(result.ff.take.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod character;
 pub mod entity;
+pub mod friendly_fire;
 pub mod general;
 pub mod weapon;
 use actix_web::web;
@@ -79,19 +80,30 @@ pub struct DamagePack {
     pub total_amount: f64,
 }
 
-#[derive(Serialize, Deserialize)]
+impl DamagePack {
+    // taker_type: 0→unknown 1→player 2→enemy
+    // 对玩家（含Bot）造成的伤害为友伤，不计入伤害统计
+    pub fn is_friendly_fire(&self) -> bool {
+        self.taker_type == 1
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct KillPack {
     pub taker_id: i16,
     pub taker_name: String,
     pub total_amount: i32,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct WeaponPack {
     pub weapon_id: i16,
     // 含友伤
     pub total_amount: f64,
     pub detail: HashMap<String, DamagePack>,
+    // 该武器第一次与最后一次造成伤害的任务时间之差
+    #[serde(default)]
+    pub active_span: i16,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
@@ -114,6 +126,37 @@ pub struct WeaponDamageInfo {
     pub valid_game_count: i32,
 }
 
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OverallWeaponDamageInfo {
+    #[serde(rename = "weaponGameId")]
+    pub weapon_game_id: String,
+    // 不含友伤
+    pub damage: f64,
+    #[serde(rename = "friendlyFire")]
+    pub friendly_fire: f64,
+    #[serde(rename = "characterGameId")]
+    pub character_game_id: String,
+    #[serde(rename = "mappedName")]
+    pub mapped_name: String,
+    #[serde(rename = "missionCount")]
+    pub mission_count: i32,
+}
+
+#[derive(Serialize)]
+pub struct WeaponKillInfo {
+    // 击杀记录不含武器，按击杀前最近一次伤害归属，恒为true
+    pub approximate: bool,
+    // weapon_game_id -> entity_game_id -> 击杀数
+    pub kill: HashMap<String, HashMap<String, i32>>,
+}
+
+#[derive(Deserialize)]
+pub struct WeaponDamageQuery {
+    // 单局中伤害（含友伤）低于该值的武器不计入该局
+    #[serde(default)]
+    pub min_damage: f64,
+}
+
 #[derive(Serialize)]
 pub struct CharacterFriendlyFireInfo {
     cause: f64,
@@ -131,6 +174,33 @@ pub struct CharacterDamageInfo {
     pub mapped_name: String,
 }
 
+#[derive(Deserialize)]
+pub struct FriendlyFireDetailQuery {
+    // 造成友伤的玩家
+    pub causer: String,
+    // 承受友伤的玩家
+    pub taker: String,
+}
+
+#[derive(Serialize)]
+pub struct MissionFriendlyFireDetail {
+    #[serde(rename = "missionId")]
+    pub mission_id: i32,
+    #[serde(rename = "beginTimestamp")]
+    pub begin_timestamp: i64,
+    pub damage: f64,
+}
+
+#[derive(Serialize)]
+pub struct FriendlyFireDetailInfo {
+    #[serde(rename = "totalDamage")]
+    pub total_damage: f64,
+    #[serde(rename = "gameCount")]
+    pub game_count: i32,
+    // 按任务开始时间排序
+    pub mission: Vec<MissionFriendlyFireDetail>,
+}
+
 #[derive(Serialize)]
 pub struct EntityDamageInfo {
     pub damage: HashMap<String, f64>,
@@ -142,6 +212,10 @@ pub struct EntityDamageInfo {
 pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(general::get_overall_damage_info);
     cfg.service(weapon::get_damage_weapon);
+    cfg.service(weapon::get_damage_weapon_overall);
+    cfg.service(weapon::get_damage_weapon_kill);
     cfg.service(character::get_damage_character);
     cfg.service(entity::get_damage_entity);
+    cfg.service(entity::get_damage_entity_weapon);
+    cfg.service(friendly_fire::get_friendly_fire_detail);
 }
@@ -1,7 +1,8 @@
-use super::WeaponDamageInfo;
+use super::{OverallWeaponDamageInfo, WeaponDamageInfo, WeaponDamageQuery, WeaponKillInfo};
 use crate::cache::mission::MissionCachedInfo;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::info::weapon_meta::UNKNOWN_WEAPON_ORDER;
+use crate::{APIResponse, AppState, ReadDbPool, WEAPON_ORDER};
 use actix_web::web;
 use actix_web::{
     get,
@@ -15,9 +16,11 @@ use std::time::Instant;
 #[get("/weapon")]
 async fn get_damage_weapon(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
+    query: web::Query<WeaponDamageQuery>,
 ) -> Json<APIResponse<HashMap<String, WeaponDamageInfo>>> {
+    let min_damage = query.min_damage;
     let mapping = app_state.mapping.lock().unwrap();
 
     let weapon_game_id_to_character_game_id = mapping.weapon_character.clone();
@@ -80,6 +83,7 @@ async fn get_damage_weapon(
             &invalid_mission_id_list,
             &weapon_game_id_to_character_game_id,
             &weapon_mapping,
+            min_damage,
         );
 
         debug!("weapon damage info generated in {:?}", begin.elapsed());
@@ -95,63 +99,445 @@ async fn get_damage_weapon(
     }
 }
 
+// weapon_game_id -> (damage, friendly_fire, valid_game_count)
+// 单局中伤害（含友伤）低于min_damage的武器不计入该局
+fn aggregate_weapon_damage<'a>(
+    cached_mission_list: &'a [MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    min_damage: f64,
+) -> HashMap<&'a String, (f64, f64, i32)> {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut result: HashMap<&String, (f64, f64, i32)> = HashMap::new();
+
+    for mission in cached_mission_list
+        .iter()
+        .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+    {
+        for (weapon_game_id, pack) in &mission.weapon_damage_info {
+            let mut damage = 0.0;
+            let mut friendly_fire = 0.0;
+
+            for detail in pack.detail.values() {
+                if detail.is_friendly_fire() {
+                    friendly_fire += detail.total_amount;
+                } else {
+                    damage += detail.total_amount;
+                }
+            }
+
+            if damage + friendly_fire < min_damage {
+                continue;
+            }
+
+            let entry = result.entry(weapon_game_id).or_default();
+
+            entry.0 += damage;
+            entry.1 += friendly_fire;
+            entry.2 += 1;
+        }
+    }
+
+    result
+}
+
 fn generate(
     cached_mission_list: &[MissionCachedInfo],
     invalid_mission_id_list: &[i32],
     weapon_game_id_to_character_game_id: &HashMap<String, String>,
     weapon_mapping: &HashMap<String, String>,
+    min_damage: f64,
 ) -> HashMap<String, WeaponDamageInfo> {
+    aggregate_weapon_damage(cached_mission_list, invalid_mission_id_list, min_damage)
+        .into_iter()
+        .map(
+            |(weapon_game_id, (damage, friendly_fire, valid_game_count))| {
+                (
+                    weapon_game_id.clone(),
+                    WeaponDamageInfo {
+                        damage,
+                        friendly_fire,
+                        hero_game_id: weapon_game_id_to_character_game_id
+                            .get(weapon_game_id)
+                            .cloned()
+                            .unwrap_or(String::from("Unknown")),
+                        mapped_name: weapon_mapping
+                            .get(weapon_game_id)
+                            .unwrap_or(weapon_game_id)
+                            .clone(),
+                        valid_game_count,
+                    },
+                )
+            },
+        )
+        .collect()
+}
+
+#[get("/weapon_overall")]
+async fn get_damage_weapon_overall(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<WeaponDamageQuery>,
+) -> Json<APIResponse<Vec<OverallWeaponDamageInfo>>> {
+    let min_damage = query.min_damage;
+    let (
+        weapon_game_id_to_character_game_id,
+        weapon_mapping,
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+    ) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.weapon_character.clone(),
+            mapping.weapon_mapping.clone(),
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate_overall(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &weapon_game_id_to_character_game_id,
+            &weapon_mapping,
+            min_damage,
+        );
+
+        debug!(
+            "overall weapon damage info generated in {:?}",
+            begin.elapsed()
+        );
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 按WEAPON_ORDER排序，未定义顺序的武器排在最后
+fn generate_overall(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    weapon_game_id_to_character_game_id: &HashMap<String, String>,
+    weapon_mapping: &HashMap<String, String>,
+    min_damage: f64,
+) -> Vec<OverallWeaponDamageInfo> {
+    let weapon_damage_map =
+        aggregate_weapon_damage(cached_mission_list, invalid_mission_id_list, min_damage);
+
+    let weapon_order = WEAPON_ORDER;
+
+    let mut result = weapon_damage_map
+        .into_iter()
+        .map(
+            |(weapon_game_id, (damage, friendly_fire, mission_count))| OverallWeaponDamageInfo {
+                weapon_game_id: weapon_game_id.clone(),
+                damage,
+                friendly_fire,
+                character_game_id: weapon_game_id_to_character_game_id
+                    .get(weapon_game_id)
+                    .cloned()
+                    .unwrap_or(String::from("Unknown")),
+                mapped_name: weapon_mapping
+                    .get(weapon_game_id)
+                    .unwrap_or(weapon_game_id)
+                    .clone(),
+                mission_count,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    result.sort_unstable_by(|a, b| {
+        let a_order = weapon_order
+            .get(a.weapon_game_id.as_str())
+            .copied()
+            .unwrap_or(UNKNOWN_WEAPON_ORDER);
+        let b_order = weapon_order
+            .get(b.weapon_game_id.as_str())
+            .copied()
+            .unwrap_or(UNKNOWN_WEAPON_ORDER);
+
+        a_order
+            .cmp(&b_order)
+            .then_with(|| a.weapon_game_id.cmp(&b.weapon_game_id))
+    });
+
+    result
+}
+
+#[get("/weapon_kill")]
+async fn get_damage_weapon_kill(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<WeaponKillInfo>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate_kill(&cached_mission_list, &invalid_mission_id_list);
+
+        debug!("weapon kill info generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+fn generate_kill(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+) -> WeaponKillInfo {
     let invalid_mission_id_set = invalid_mission_id_list
         .iter()
         .copied()
         .collect::<HashSet<_>>();
 
-    let cached_mission_list = cached_mission_list
-        .into_iter()
+    let mut kill: HashMap<String, HashMap<String, i32>> = HashMap::new();
+
+    for mission in cached_mission_list
+        .iter()
         .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
-        .collect::<Vec<_>>();
+    {
+        for (weapon_game_id, entity_kill_map) in &mission.kill_weapon_info {
+            let weapon_entry = kill.entry(weapon_game_id.clone()).or_default();
 
-    let mut result = HashMap::new();
+            for (entity_game_id, &kill_count) in entity_kill_map {
+                *weapon_entry.entry(entity_game_id.clone()).or_default() += kill_count;
+            }
+        }
+    }
 
-    for mission in cached_mission_list {
-        for (weapon_game_id, pack) in &mission.weapon_damage_info {
-            let damage = pack
-                .detail
-                .values()
-                .filter(|&val| val.taker_type != 1)
-                .map(|val| val.total_amount)
-                .sum::<f64>();
-
-            let friendly_fire = pack
-                .detail
-                .values()
-                .filter(|&val| val.taker_type == 1)
-                .map(|val| val.total_amount)
-                .sum::<f64>();
-
-            let hero_game_id = weapon_game_id_to_character_game_id
-                .get(weapon_game_id)
-                .map(|inner| inner.clone())
-                .unwrap_or(String::from("Unknown"));
-
-            let mapped_name = weapon_mapping
-                .get(weapon_game_id)
-                .map(|inner| inner.clone())
-                .unwrap_or(weapon_game_id.clone());
-
-            let entry = result.entry(weapon_game_id).or_insert(WeaponDamageInfo {
-                damage,
-                friendly_fire,
-                hero_game_id,
-                mapped_name,
-                valid_game_count: 0,
-            });
-
-            entry.damage += damage;
-            entry.friendly_fire += friendly_fire;
-            entry.valid_game_count += 1;
+    WeaponKillInfo {
+        approximate: true,
+        kill,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::mission::tests::cached_mission;
+    use crate::damage::{DamagePack, WeaponPack};
+
+    // 每项为(weapon_game_id, 伤害, 友伤)
+    fn weapon_damage_mission(
+        mission_id: i32,
+        weapon_damage: &[(&str, f64, f64)],
+    ) -> MissionCachedInfo {
+        let damage_pack = |taker_type: i16, total_amount: f64| DamagePack {
+            taker_id: 0,
+            taker_type,
+            weapon_id: 0,
+            total_amount,
+        };
+
+        MissionCachedInfo {
+            weapon_damage_info: weapon_damage
+                .iter()
+                .map(|&(weapon_game_id, damage, friendly_fire)| {
+                    (
+                        weapon_game_id.to_string(),
+                        WeaponPack {
+                            weapon_id: 0,
+                            total_amount: damage + friendly_fire,
+                            detail: HashMap::from([
+                                ("ED_Spider_Grunt".to_string(), damage_pack(2, damage)),
+                                ("player".to_string(), damage_pack(1, friendly_fire)),
+                            ]),
+                            active_span: 0,
+                        },
+                    )
+                })
+                .collect(),
+            ..cached_mission(mission_id, &[])
         }
     }
 
-    result.into_iter().map(|(k, v)| (k.clone(), v)).collect()
+    #[test]
+    fn overall_weapon_damage_is_summed_once_per_mission() {
+        let cached_mission_list = vec![
+            weapon_damage_mission(
+                1,
+                &[("WPN_Pistol_A", 100.0, 10.0), ("WPN_Unknown", 5.0, 0.0)],
+            ),
+            weapon_damage_mission(
+                2,
+                &[("WPN_Pistol_A", 50.0, 0.0), ("WPN_FlameThrower", 1.0, 0.0)],
+            ),
+            // 无效任务不计入
+            weapon_damage_mission(3, &[("WPN_Pistol_A", 1000.0, 0.0)]),
+        ];
+
+        let result = generate_overall(
+            &cached_mission_list,
+            &[3],
+            &HashMap::new(),
+            &HashMap::new(),
+            0.0,
+        );
+
+        assert_eq!(
+            result
+                .iter()
+                .map(|x| (
+                    x.weapon_game_id.as_str(),
+                    x.damage,
+                    x.friendly_fire,
+                    x.mission_count
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("WPN_FlameThrower", 1.0, 0.0, 1),
+                ("WPN_Pistol_A", 150.0, 10.0, 2),
+                ("WPN_Unknown", 5.0, 0.0, 1),
+            ]
+        );
+
+        // 单局伤害（含友伤）低于min_damage的武器不计入该局
+        let result = generate_overall(
+            &cached_mission_list,
+            &[3],
+            &HashMap::new(),
+            &HashMap::new(),
+            60.0,
+        );
+
+        assert_eq!(
+            result
+                .iter()
+                .map(|x| (x.weapon_game_id.as_str(), x.damage, x.mission_count))
+                .collect::<Vec<_>>(),
+            vec![("WPN_Pistol_A", 100.0, 1)]
+        );
+
+        // 与/weapon使用相同的统计
+        let per_weapon = generate(
+            &cached_mission_list,
+            &[3],
+            &HashMap::new(),
+            &HashMap::new(),
+            0.0,
+        );
+        assert_eq!(per_weapon["WPN_Pistol_A"].damage, 150.0);
+        assert_eq!(per_weapon["WPN_Pistol_A"].valid_game_count, 2);
+    }
 }
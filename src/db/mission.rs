@@ -476,7 +476,7 @@ impl NewSupplyInfo {
     }
 }
 
-pub fn load_mission(log: LogContent, db: &mut PgConnection) -> Result<(), DbError> {
+pub fn load_mission(log: LogContent, db: &mut PgConnection) -> Result<i32, DbError> {
     let player_list: Vec<Player> = player::table.load(db).map_err(|e| {
         DbError::UnexpectedError(format!(
             "load_mission: db error while fetching player: {}",
@@ -669,5 +669,5 @@ pub fn load_mission(log: LogContent, db: &mut PgConnection) -> Result<(), DbErro
             DbError::UnexpectedError(format!("db error while inserting supply info: {}", e))
         })?;
 
-    Ok(())
+    Ok(inserted_mission_id)
 }
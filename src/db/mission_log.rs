@@ -1,5 +1,27 @@
 use serde::{Deserialize, Serialize};
 
+// 支持的日志格式版本，日志格式变化时递增
+pub const SUPPORTED_LOG_VERSION: i32 = 1;
+
+// 日志第一行可为`version|<n>`，标明日志格式版本；未标明版本的日志为版本1
+const LOG_VERSION_PREFIX: &str = "version|";
+
+// 返回日志格式版本及去除版本行后的日志内容
+pub fn split_log_version(file_content: &str) -> Result<(i32, &str), String> {
+    let (first_line, rest) = file_content.split_once('\n').unwrap_or((file_content, ""));
+
+    match first_line.trim().strip_prefix(LOG_VERSION_PREFIX) {
+        Some(version) => {
+            let version = version
+                .trim()
+                .parse::<i32>()
+                .map_err(|e| format!("cannot parse log version: {}: {}", version, e))?;
+            Ok((version, rest))
+        }
+        None => Ok((1, file_content)),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LogMissionInfo {
     pub begin_timestamp: i64,
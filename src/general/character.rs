@@ -1,8 +1,12 @@
-use super::{CharacterChoiceInfo, CharacterGeneralData, CharacterGeneralInfo};
+use super::{
+    minerals_mined, CharacterChoiceInfo, CharacterChoicePoint, CharacterChoiceTimeline,
+    CharacterChoiceTimelineQuery, CharacterChoiceTrendInfo, CharacterGeneralData,
+    CharacterGeneralInfo, DeltaData,
+};
 use crate::cache::mission::MissionCachedInfo;
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool, RecentWindowInfo};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -15,7 +19,7 @@ use std::time::Instant;
 #[get("/character")]
 async fn get_character_general_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<CharacterGeneralInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -24,6 +28,7 @@ async fn get_character_general_info(
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
     let character_game_id_to_name = mapping.character_mapping.clone();
+    let non_mineral_resource_set = mapping.non_mineral_resource_set.clone();
     drop(mapping);
 
     let result = web::block(move || {
@@ -94,6 +99,7 @@ async fn get_character_general_info(
             &invalid_mission_id_list,
             &character_id_to_game_id,
             character_game_id_to_name,
+            &non_mineral_resource_set,
         );
 
         debug!("character general info generated in {:?}", begin.elapsed());
@@ -112,7 +118,7 @@ async fn get_character_general_info(
 #[get("/character_info")]
 async fn get_character_choice_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<CharacterChoiceInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -206,11 +212,265 @@ async fn get_character_choice_info(
     }
 }
 
+#[get("/character_choice_trend")]
+async fn get_character_choice_trend(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<CharacterChoiceTrendInfo>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine, character_game_id_to_name) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.character_mapping.clone(),
+        )
+    };
+
+    let recent_window = app_state.recent_window;
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let character_list = match character::table
+            .select(Character::as_select())
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get character list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let character_id_to_game_id = character_list
+            .into_iter()
+            .map(|x| (x.id, x.character_game_id))
+            .collect::<HashMap<_, _>>();
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate_choice_trend(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &character_id_to_game_id,
+            character_game_id_to_name,
+            recent_window,
+        );
+
+        debug!("character choice trend generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[get("/character_choice_timeline")]
+async fn get_character_choice_timeline(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<CharacterChoiceTimelineQuery>,
+) -> Json<APIResponse<CharacterChoiceTimeline>> {
+    let (
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+        character_game_id_to_name,
+        player_mapping,
+    ) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.character_mapping.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let player_name = query.into_inner().player;
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get player list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        // 玩家的全部别名合并计算
+        let player_id = match merged_player_info.find_player_id(&player_mapping, player_name) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let character_list = match character::table
+            .select(Character::as_select())
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get character list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let character_id_to_game_id = character_list
+            .into_iter()
+            .map(|x| (x.id, x.character_game_id))
+            .collect::<HashMap<_, _>>();
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let invalid_mission_id_set = invalid_mission_id_list.into_iter().collect::<HashSet<_>>();
+
+        let choice_list = cached_mission_list
+            .iter()
+            .filter(|info| !invalid_mission_id_set.contains(&info.mission_info.id))
+            .filter_map(|info| {
+                let player_info = info
+                    .player_info
+                    .iter()
+                    .find(|player_info| player_info.player_id == player_id)?;
+                let character_game_id = character_id_to_game_id.get(&player_info.character_id)?;
+
+                Some((
+                    info.mission_info.id,
+                    info.mission_info.begin_timestamp,
+                    character_game_id.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let result = CharacterChoiceTimeline {
+            timeline: generate_choice_timeline(choice_list),
+            character_mapping: character_game_id_to_name,
+        };
+
+        debug!(
+            "character choice timeline generated in {:?}",
+            begin.elapsed()
+        );
+
+        Ok(Some(result))
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(Some(x)) => Json(APIResponse::ok(x)),
+        Ok(None) => Json(APIResponse::not_found()),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
 fn generate(
     cached_mission_list: &[MissionCachedInfo],
     invalid_mission_id_list: &[i32],
     character_id_to_game_id: &HashMap<i16, String>,
     character_game_id_to_name: HashMap<String, String>,
+    non_mineral_resource_set: &HashSet<String>,
 ) -> CharacterGeneralInfo {
     let invalid_mission_id_set = invalid_mission_id_list
         .iter()
@@ -257,7 +517,7 @@ fn generate(
                 .entry(character_game_id)
                 .or_insert_with(Vec::new)
                 .push(match mission.resource_info.get(&player_info.player_id) {
-                    Some(x) => x.values().sum::<f64>(),
+                    Some(x) => minerals_mined(x, non_mineral_resource_set),
                     None => 0.0,
                 });
             supply_count_list_by_character
@@ -356,3 +616,156 @@ fn generate_choice_info(
         character_mapping: character_game_id_to_name,
     }
 }
+
+fn count_character_choice<'a>(
+    mission_list: &[&'a MissionCachedInfo],
+    character_id_to_game_id: &'a HashMap<i16, String>,
+) -> (HashMap<&'a String, i32>, i32) {
+    let mut character_choice_count = HashMap::new();
+    let mut total_player_count = 0;
+
+    for mission in mission_list {
+        for player_info in &mission.player_info {
+            let character_game_id = match character_id_to_game_id.get(&player_info.character_id) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            *character_choice_count.entry(character_game_id).or_default() += 1;
+            total_player_count += 1;
+        }
+    }
+
+    (character_choice_count, total_player_count)
+}
+
+fn generate_choice_trend(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    character_id_to_game_id: &HashMap<i16, String>,
+    character_game_id_to_name: HashMap<String, String>,
+    recent_window: RecentWindowInfo,
+) -> CharacterChoiceTrendInfo {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut cached_mission_list = cached_mission_list
+        .iter()
+        .filter(|info| !invalid_mission_id_set.contains(&info.mission_info.id))
+        .collect::<Vec<_>>();
+
+    cached_mission_list.sort_unstable_by(|a, b| {
+        a.mission_info
+            .begin_timestamp
+            .cmp(&b.mission_info.begin_timestamp)
+            .then_with(|| a.mission_info.id.cmp(&b.mission_info.id))
+    });
+
+    let valid_game_count = cached_mission_list.len();
+
+    let prev_count = recent_window.prev_count(valid_game_count);
+
+    let (prev_count_map, prev_player_count) =
+        count_character_choice(&cached_mission_list[0..prev_count], character_id_to_game_id);
+    let (recent_count_map, recent_player_count) =
+        count_character_choice(&cached_mission_list[prev_count..], character_id_to_game_id);
+    let (total_count_map, total_player_count) =
+        count_character_choice(&cached_mission_list, character_id_to_game_id);
+
+    let rate = |count: i32, player_count: i32| match player_count {
+        0 => 0.0,
+        x => count as f64 / x as f64,
+    };
+
+    let mut character_choice_count = HashMap::with_capacity(total_count_map.len());
+    let mut character_choice_rate = HashMap::with_capacity(total_count_map.len());
+
+    for (&character_game_id, &total) in &total_count_map {
+        let prev = prev_count_map.get(character_game_id).copied().unwrap_or(0);
+        let recent = recent_count_map
+            .get(character_game_id)
+            .copied()
+            .unwrap_or(0);
+
+        character_choice_count.insert(
+            character_game_id.clone(),
+            DeltaData {
+                prev,
+                recent,
+                total,
+            },
+        );
+
+        let total_rate = rate(total, total_player_count);
+
+        character_choice_rate.insert(
+            character_game_id.clone(),
+            DeltaData {
+                prev: rate(prev, prev_player_count),
+                recent: match recent_player_count {
+                    0 => total_rate,
+                    _ => rate(recent, recent_player_count),
+                },
+                total: total_rate,
+            },
+        );
+    }
+
+    CharacterChoiceTrendInfo {
+        character_choice_count,
+        character_choice_rate,
+        character_mapping: character_game_id_to_name,
+    }
+}
+
+// choice_list: (mission_id, begin_timestamp, character_game_id)
+fn generate_choice_timeline(mut choice_list: Vec<(i32, i64, String)>) -> Vec<CharacterChoicePoint> {
+    choice_list.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut character_choice_count: HashMap<String, i32> = HashMap::new();
+
+    choice_list
+        .into_iter()
+        .map(|(mission_id, begin_timestamp, character_game_id)| {
+            *character_choice_count
+                .entry(character_game_id.clone())
+                .or_default() += 1;
+
+            CharacterChoicePoint {
+                mission_id,
+                begin_timestamp,
+                character_game_id,
+                character_choice_count: character_choice_count.clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choice_timeline_counts_in_timestamp_order() {
+        let choice_list = vec![
+            (3, 2000, "DRILLER".to_string()),
+            (1, 0, "SCOUT".to_string()),
+            (2, 1000, "SCOUT".to_string()),
+        ];
+
+        let timeline = generate_choice_timeline(choice_list);
+
+        assert_eq!(
+            timeline
+                .iter()
+                .map(|point| point.mission_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(timeline[1].character_choice_count["SCOUT"], 2);
+        assert_eq!(timeline[2].character_choice_count["SCOUT"], 2);
+        assert_eq!(timeline[2].character_choice_count["DRILLER"], 1);
+    }
+}
@@ -1,12 +1,15 @@
 use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::MissionType;
+use crate::db::schema::*;
 use actix_web::{
     get,
     web::{self, Data, Json},
 };
 use chrono::{DateTime, Timelike};
-use log::{debug, error};
+use diesel::prelude::*;
+use log::{debug, error, warn};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 const MISSION_TIME_RESOLUTION_SEC: u16 = 15;
@@ -23,12 +26,24 @@ pub struct GameTimeInfo {
     #[serde(rename = "gameTimeDistribution")]
     pub game_time_distribution: HashMap<i32, i32>,
 }
-use crate::{APIResponse, AppState, DbPool};
+
+#[derive(Serialize)]
+pub struct GameTimeByType {
+    #[serde(rename = "missionCount")]
+    pub mission_count: i32,
+    // 单位：秒
+    #[serde(rename = "totalMissionTime")]
+    pub total_mission_time: i64,
+    #[serde(rename = "averageMissionTime")]
+    pub average_mission_time: f64,
+}
+
+use crate::{APIResponse, AppState, ReadDbPool};
 
 #[get("/game_time")]
 async fn get_game_time(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<GameTimeInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -133,3 +148,177 @@ fn generate(cached_mission_list: &[MissionCachedInfo]) -> GameTimeInfo {
         game_time_distribution,
     }
 }
+
+#[get("/game_time_by_type")]
+async fn get_game_time_by_type(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<HashMap<String, GameTimeByType>>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let mission_type_list = match mission_type::table
+            .select(MissionType::as_select())
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get mission type list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let mission_type_id_to_game_id = mission_type_list
+            .into_iter()
+            .map(|item| (item.id, item.mission_type_game_id))
+            .collect::<HashMap<_, _>>();
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate_by_type(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &mission_type_id_to_game_id,
+        );
+
+        debug!("game time by type info generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+fn generate_by_type(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    mission_type_id_to_game_id: &HashMap<i16, String>,
+) -> HashMap<String, GameTimeByType> {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    // mission_type_game_id -> (mission_count, total_mission_time)
+    let mut mission_type_time: HashMap<&str, (i32, i64)> = HashMap::new();
+
+    for mission in cached_mission_list {
+        let mission_info = &mission.mission_info;
+
+        if invalid_mission_id_set.contains(&mission_info.id) {
+            continue;
+        }
+
+        let mission_type_game_id =
+            match mission_type_id_to_game_id.get(&mission_info.mission_type_id) {
+                Some(x) => x,
+                None => {
+                    warn!(
+                        "skipping mission {} with unknown mission type id {}",
+                        mission_info.id, mission_info.mission_type_id
+                    );
+                    continue;
+                }
+            };
+
+        let entry = mission_type_time
+            .entry(mission_type_game_id.as_str())
+            .or_default();
+        entry.0 += 1;
+        entry.1 += mission_info.mission_time as i64;
+    }
+
+    mission_type_time
+        .into_iter()
+        .map(
+            |(mission_type_game_id, (mission_count, total_mission_time))| {
+                (
+                    mission_type_game_id.to_string(),
+                    GameTimeByType {
+                        mission_count,
+                        total_mission_time,
+                        average_mission_time: total_mission_time as f64 / mission_count as f64,
+                    },
+                )
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::mission::tests::cached_mission;
+
+    #[test]
+    fn mission_with_unknown_type_is_skipped() {
+        let mut unknown_type_mission = cached_mission(2, &[]);
+        unknown_type_mission.mission_info.mission_type_id = 99;
+
+        let cached_mission_list = vec![cached_mission(1, &[]), unknown_type_mission];
+        let mission_type_id_to_game_id = HashMap::from([(0, "MissionType_Egg".to_string())]);
+
+        let result = generate_by_type(&cached_mission_list, &[], &mission_type_id_to_game_id);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result["MissionType_Egg"].mission_count, 1);
+        assert_eq!(result["MissionType_Egg"].total_mission_time, 600);
+    }
+}
@@ -1,31 +1,40 @@
-use super::{DeltaData, GeneralInfo};
+use super::{minerals_mined, DeltaData, Distribution, GeneralInfo, GeneralQuery};
 use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
 use crate::db::schema::*;
 use crate::hazard_id_to_real;
-use crate::{APIResponse, AppState, DbPool};
+use crate::RecentWindowInfo;
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
 };
 use diesel::prelude::*;
-use log::{debug, error};
+use log::{debug, error, warn};
 use std::collections::HashSet;
 use std::time::Instant;
 
 #[get("/")]
 async fn get_general(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
+    query: web::Query<GeneralQuery>,
 ) -> Json<APIResponse<GeneralInfo>> {
+    let GeneralQuery { exclude_player } = query.into_inner();
+
     let mapping = app_state.mapping.lock().unwrap();
 
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let non_mineral_resource_set = mapping.non_mineral_resource_set.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
+    let recent_window = app_state.recent_window;
+
     let result = web::block(move || {
         let begin = Instant::now();
 
@@ -45,7 +54,7 @@ async fn get_general(
             }
         };
 
-        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -70,36 +79,53 @@ async fn get_general(
             }
         };
 
-        let watchlist_player_id_list: Vec<i16> = match player::table
-            .select(player::id)
-            .filter(player::friend.eq(true))
-            .load(&mut db_conn)
-        {
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
             Ok(x) => x,
             Err(e) => {
-                error!("cannot get watchlist from db: {}", e);
+                error!("cannot get player list from db: {}", e);
                 return Err(());
             }
         };
 
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        // 排除该玩家的全部别名
+        let exclude_player_id = match exclude_player {
+            Some(player_name) => {
+                match merged_player_info.find_player_id(&player_mapping, player_name) {
+                    Some(x) => Some(x),
+                    None => return Ok(None),
+                }
+            }
+            None => None,
+        };
+
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
 
         let result = generate(
             &cached_mission_list,
             &invalid_mission_id_list,
-            &watchlist_player_id_list,
+            &merged_player_info.watchlist_player_id_list,
+            exclude_player_id,
+            &non_mineral_resource_set,
+            recent_window,
         );
 
         debug!("general info generated in {:?}", begin.elapsed());
 
-        Ok(result)
+        Ok(Some(result))
     })
     .await
     .unwrap();
 
     match result {
-        Ok(x) => Json(APIResponse::ok(x)),
+        Ok(Some(x)) => Json(APIResponse::ok(x)),
+        Ok(None) => Json(APIResponse::not_found()),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
@@ -108,7 +134,22 @@ fn generate(
     cached_mission_list: &[MissionCachedInfo],
     invalid_mission_id_list: &[i32],
     watchlist_player_id_list: &[i16],
+    exclude_player_id: Option<i16>,
+    non_mineral_resource_set: &HashSet<String>,
+    recent_window: RecentWindowInfo,
 ) -> GeneralInfo {
+    let excluded_mission_list;
+    let cached_mission_list = match exclude_player_id {
+        Some(player_id) => {
+            excluded_mission_list = cached_mission_list
+                .iter()
+                .map(|mission| exclude_player_from_mission(mission, player_id))
+                .collect::<Vec<_>>();
+            &excluded_mission_list[..]
+        }
+        None => cached_mission_list,
+    };
+
     let game_count = cached_mission_list.len() as i32;
 
     let invalid_mission_id_set = invalid_mission_id_list
@@ -119,6 +160,16 @@ fn generate(
     let cached_mission_list = cached_mission_list
         .iter()
         .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+        .filter(|item| {
+            if hazard_id_to_real(item.mission_info.hazard_id).is_none() {
+                warn!(
+                    "skipping mission {} with unknown hazard id {}",
+                    item.mission_info.id, item.mission_info.hazard_id
+                );
+                return false;
+            }
+            true
+        })
         .collect::<Vec<_>>();
 
     let valid_game_count = cached_mission_list.len();
@@ -129,16 +180,7 @@ fn generate(
         .iter()
         .map(|item| item.mission_info.mission_time as i64)
         .sum::<i64>();
-    let prev_count = match valid_game_count * 8 / 10 {
-        0..10 => 10,
-        x => x,
-    };
-
-    let prev_count = if prev_count >= valid_game_count {
-        valid_game_count
-    } else {
-        prev_count
-    };
+    let prev_count = recent_window.prev_count(valid_game_count);
 
     let prev_mission_list = &cached_mission_list[0..prev_count];
     let recent_mission_list = &cached_mission_list[prev_count..];
@@ -253,17 +295,17 @@ fn generate(
 
     let total_difficulty = cached_mission_list
         .iter()
-        .map(|item| hazard_id_to_real(item.mission_info.hazard_id))
+        .filter_map(|item| hazard_id_to_real(item.mission_info.hazard_id))
         .sum::<f64>();
 
     let prev_difficulty = prev_mission_list
         .iter()
-        .map(|item| hazard_id_to_real(item.mission_info.hazard_id))
+        .filter_map(|item| hazard_id_to_real(item.mission_info.hazard_id))
         .sum::<f64>();
 
     let recent_difficulty = recent_mission_list
         .iter()
-        .map(|item| hazard_id_to_real(item.mission_info.hazard_id))
+        .filter_map(|item| hazard_id_to_real(item.mission_info.hazard_id))
         .sum::<f64>();
 
     let average_difficulty = DeltaData {
@@ -433,7 +475,9 @@ fn generate(
         .map(|item| {
             item.resource_info
                 .values()
-                .map(|player_resource_info| player_resource_info.values().sum::<f64>())
+                .map(|player_resource_info| {
+                    minerals_mined(player_resource_info, non_mineral_resource_set)
+                })
                 .sum::<f64>()
         })
         .sum::<f64>();
@@ -443,7 +487,9 @@ fn generate(
         .map(|item| {
             item.resource_info
                 .values()
-                .map(|player_resource_info| player_resource_info.values().sum::<f64>())
+                .map(|player_resource_info| {
+                    minerals_mined(player_resource_info, non_mineral_resource_set)
+                })
                 .sum::<f64>()
         })
         .sum::<f64>();
@@ -453,7 +499,9 @@ fn generate(
         .map(|item| {
             item.resource_info
                 .values()
-                .map(|player_resource_info| player_resource_info.values().sum::<f64>())
+                .map(|player_resource_info| {
+                    minerals_mined(player_resource_info, non_mineral_resource_set)
+                })
                 .sum::<f64>()
         })
         .sum::<f64>();
@@ -533,6 +581,61 @@ fn generate(
         total: total_reward_credit / valid_game_count as f64,
     };
 
+    let mission_time_distribution = Distribution::from_values(
+        cached_mission_list
+            .iter()
+            .map(|item| item.mission_info.mission_time as f64)
+            .collect(),
+    );
+
+    let kill_num_distribution = Distribution::from_values(
+        cached_mission_list
+            .iter()
+            .map(|item| {
+                item.kill_info
+                    .values()
+                    .map(|player_data| {
+                        player_data
+                            .values()
+                            .map(|pack| pack.total_amount)
+                            .sum::<i32>()
+                    })
+                    .sum::<i32>() as f64
+            })
+            .collect(),
+    );
+
+    let damage_distribution = Distribution::from_values(
+        cached_mission_list
+            .iter()
+            .map(|item| {
+                item.damage_info
+                    .values()
+                    .map(|player_data| {
+                        player_data
+                            .values()
+                            .map(|pack| pack.total_amount)
+                            .sum::<f64>()
+                    })
+                    .sum::<f64>()
+            })
+            .collect(),
+    );
+
+    let minerals_mined_distribution = Distribution::from_values(
+        cached_mission_list
+            .iter()
+            .map(|item| {
+                item.resource_info
+                    .values()
+                    .map(|player_resource_info| {
+                        minerals_mined(player_resource_info, non_mineral_resource_set)
+                    })
+                    .sum::<f64>()
+            })
+            .collect(),
+    );
+
     GeneralInfo {
         game_count,
         valid_rate,
@@ -548,5 +651,28 @@ fn generate(
         average_minerals_mined,
         average_supply_count_per_player,
         average_reward_credit,
+        mission_time_distribution,
+        kill_num_distribution,
+        damage_distribution,
+        minerals_mined_distribution,
     }
 }
+
+// 去掉该玩家在本局中的所有记录，任务本身的数据（时间、结果、难度、奖励）保留
+fn exclude_player_from_mission(mission: &MissionCachedInfo, player_id: i16) -> MissionCachedInfo {
+    let mut mission = mission.clone();
+
+    mission
+        .player_info
+        .retain(|player_info| player_info.player_id != player_id);
+    mission.player_index.remove(&player_id);
+    mission.kill_info.remove(&player_id);
+    mission.damage_info.remove(&player_id);
+    mission.resource_info.remove(&player_id);
+    mission.revive_count.remove(&player_id);
+    mission.death_count.remove(&player_id);
+    mission.supply_info.remove(&player_id);
+    mission.player_weapon_set.remove(&player_id);
+
+    mission
+}
@@ -3,20 +3,20 @@ use crate::cache::mission::MissionCachedInfo;
 use crate::db::models::MissionType;
 use crate::db::schema::*;
 use crate::hazard_id_to_real;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
 };
 use diesel::prelude::*;
-use log::{debug, error};
+use log::{debug, error, warn};
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 #[get("/mission_type")]
 async fn get_mission_type(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<MissionTypeInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -132,6 +132,14 @@ fn generate(
     let mut result = HashMap::with_capacity(mission_list_by_type.len());
 
     for mission in cached_mission_list {
+        if hazard_id_to_real(mission.mission_info.hazard_id).is_none() {
+            warn!(
+                "skipping mission {} with unknown hazard id {}",
+                mission.mission_info.id, mission.mission_info.hazard_id
+            );
+            continue;
+        }
+
         let mission_type = mission.mission_info.mission_type_id;
         mission_list_by_type
             .entry(mission_type)
@@ -142,7 +150,7 @@ fn generate(
     for (mission_type_id, mission_list) in mission_list_by_type {
         let total_difficulty = mission_list
             .iter()
-            .map(|item| hazard_id_to_real(item.mission_info.hazard_id))
+            .filter_map(|item| hazard_id_to_real(item.mission_info.hazard_id))
             .sum::<f64>();
 
         let total_mission_time = mission_list
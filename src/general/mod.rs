@@ -2,11 +2,26 @@ pub mod character;
 pub mod game_time;
 pub mod general;
 pub mod mission_type;
+pub mod play_session;
 pub mod player;
-use std::collections::HashMap;
+pub mod resource;
+pub mod supply;
+use std::collections::{HashMap, HashSet};
 
 use actix_web::web;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+// 单个玩家的矿物采集量，不含non_mineral_resource_set中的资源
+pub fn minerals_mined(
+    resource_info: &HashMap<String, f64>,
+    non_mineral_resource_set: &HashSet<String>,
+) -> f64 {
+    resource_info
+        .iter()
+        .filter(|(resource_game_id, _)| !non_mineral_resource_set.contains(*resource_game_id))
+        .map(|(_, amount)| amount)
+        .sum()
+}
 
 #[derive(Serialize)]
 pub struct DeltaData<T: Serialize> {
@@ -15,6 +30,45 @@ pub struct DeltaData<T: Serialize> {
     total: T,
 }
 
+// 每局数据的分布，由单局值计算；无数据时各项均为0
+#[derive(Serialize)]
+pub struct Distribution {
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+    pub p90: f64,
+}
+
+impl Distribution {
+    pub fn from_values(mut value_list: Vec<f64>) -> Self {
+        value_list.sort_unstable_by(|a, b| a.total_cmp(b));
+
+        Distribution {
+            min: value_list.first().copied().unwrap_or(0.0),
+            max: value_list.last().copied().unwrap_or(0.0),
+            median: percentile(&value_list, 0.5),
+            p90: percentile(&value_list, 0.9),
+        }
+    }
+}
+
+// sorted_list需升序排列，p取值0~1；使用最近秩法，空列表返回0而非NaN
+pub fn percentile(sorted_list: &[f64], p: f64) -> f64 {
+    if sorted_list.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (p * sorted_list.len() as f64).ceil() as usize;
+
+    sorted_list[rank.clamp(1, sorted_list.len()) - 1]
+}
+
+#[derive(Deserialize)]
+pub struct GeneralQuery {
+    // 排除该玩家在每局中的记录后重新计算
+    pub exclude_player: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct GeneralInfo {
     #[serde(rename = "gameCount")]
@@ -45,6 +99,14 @@ pub struct GeneralInfo {
     pub average_supply_count_per_player: DeltaData<f64>,
     #[serde(rename = "averageRewardCredit")]
     pub average_reward_credit: DeltaData<f64>,
+    #[serde(rename = "missionTimeDistribution")]
+    pub mission_time_distribution: Distribution,
+    #[serde(rename = "killNumDistribution")]
+    pub kill_num_distribution: Distribution,
+    #[serde(rename = "damageDistribution")]
+    pub damage_distribution: Distribution,
+    #[serde(rename = "mineralsMinedDistribution")]
+    pub minerals_mined_distribution: Distribution,
 }
 
 #[derive(Serialize)]
@@ -102,6 +164,40 @@ pub struct PlayerInfo {
     pub prev_player_data: HashMap<String, PlayerData>,
 }
 
+#[derive(Serialize)]
+pub struct PlayerSupplyData {
+    #[serde(rename = "averageSupplyEfficiency")]
+    pub average_supply_efficiency: f64,
+    #[serde(rename = "totalSupplyCount")]
+    pub total_supply_count: i32,
+    #[serde(rename = "validMissionCount")]
+    pub valid_mission_count: i32,
+}
+
+#[derive(Serialize)]
+pub struct PlayerResourceRankData {
+    #[serde(rename = "playerName")]
+    pub player_name: String,
+    // resource_game_id -> total_amount
+    pub resource: HashMap<String, f64>,
+    #[serde(rename = "validMissionCount")]
+    pub valid_mission_count: i32,
+}
+
+#[derive(Serialize)]
+pub struct ResourceLeaderboardInfo {
+    pub player: Vec<PlayerResourceRankData>,
+    #[serde(rename = "resourceMapping")]
+    pub resource_mapping: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResourceLeaderboardQuery {
+    // resource_game_id
+    #[serde(default)]
+    pub sort_by: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct CharacterGeneralData {
     #[serde(rename = "playerIndex")]
@@ -134,11 +230,74 @@ pub struct CharacterChoiceInfo {
     pub character_mapping: HashMap<String, String>,
 }
 
+#[derive(Deserialize)]
+pub struct CharacterChoiceTimelineQuery {
+    pub player: String,
+}
+
+#[derive(Serialize)]
+pub struct CharacterChoicePoint {
+    #[serde(rename = "missionId")]
+    pub mission_id: i32,
+    #[serde(rename = "beginTimestamp")]
+    pub begin_timestamp: i64,
+    #[serde(rename = "characterGameId")]
+    pub character_game_id: String,
+    // 截至该任务（含）各角色的累计选择次数
+    #[serde(rename = "characterChoiceCount")]
+    pub character_choice_count: HashMap<String, i32>,
+}
+
+#[derive(Serialize)]
+pub struct CharacterChoiceTimeline {
+    pub timeline: Vec<CharacterChoicePoint>,
+    #[serde(rename = "characterMapping")]
+    pub character_mapping: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct CharacterChoiceTrendInfo {
+    // character_game_id -> 选择次数
+    #[serde(rename = "characterChoiceCount")]
+    pub character_choice_count: HashMap<String, DeltaData<i32>>,
+    // character_game_id -> 选择次数 / 该时间段内玩家总人次
+    #[serde(rename = "characterChoiceRate")]
+    pub character_choice_rate: HashMap<String, DeltaData<f64>>,
+    #[serde(rename = "characterMapping")]
+    pub character_mapping: HashMap<String, String>,
+}
+
 pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(general::get_general);
     cfg.service(mission_type::get_mission_type);
     cfg.service(player::get_player);
     cfg.service(character::get_character_general_info);
     cfg.service(character::get_character_choice_info);
+    cfg.service(character::get_character_choice_trend);
+    cfg.service(character::get_character_choice_timeline);
     cfg.service(game_time::get_game_time);
+    cfg.service(game_time::get_game_time_by_type);
+    cfg.service(supply::get_player_supply);
+    cfg.service(supply::get_supply_efficiency);
+    cfg.service(play_session::get_play_session);
+    cfg.service(resource::get_resource_leaderboard);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_uses_nearest_rank() {
+        let distribution = Distribution::from_values((1..=10).rev().map(|x| x as f64).collect());
+
+        assert_eq!(distribution.min, 1.0);
+        assert_eq!(distribution.max, 10.0);
+        assert_eq!(distribution.median, 5.0);
+        assert_eq!(distribution.p90, 9.0);
+
+        let empty = Distribution::from_values(Vec::new());
+        assert_eq!(empty.median, 0.0);
+        assert_eq!(empty.p90, 0.0);
+    }
 }
@@ -0,0 +1,201 @@
+use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
+use crate::db::schema::*;
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool, RE_SPOT_TIME_THRESHOLD};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+};
+use diesel::prelude::*;
+use log::{debug, error};
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+// 按开始时间排序后，玩家完全相同且与上一局开始时间间隔不超过RE_SPOT_TIME_THRESHOLD的相邻任务视为同一段游戏；
+// 仅返回包含至少两局的游戏段
+fn cluster_session(mut mission_list: Vec<(i32, i64, BTreeSet<i16>)>) -> Vec<Vec<i32>> {
+    mission_list
+        .sort_unstable_by_key(|&(mission_id, begin_timestamp, _)| (begin_timestamp, mission_id));
+
+    let mut result = Vec::new();
+    let mut current_session: Vec<i32> = Vec::new();
+    let mut last_mission: Option<(i64, BTreeSet<i16>)> = None;
+
+    for (mission_id, begin_timestamp, player_id_set) in mission_list {
+        let same_session = last_mission
+            .as_ref()
+            .is_some_and(|(last_timestamp, last_set)| {
+                begin_timestamp - last_timestamp <= RE_SPOT_TIME_THRESHOLD
+                    && *last_set == player_id_set
+            });
+
+        if !same_session {
+            let session = std::mem::take(&mut current_session);
+            if session.len() >= 2 {
+                result.push(session);
+            }
+        }
+
+        current_session.push(mission_id);
+        last_mission = Some((begin_timestamp, player_id_set));
+    }
+
+    if current_session.len() >= 2 {
+        result.push(current_session);
+    }
+
+    result
+}
+
+// 同一玩家的别名按同一玩家判断
+fn generate(
+    mut cached_mission_list: Vec<MissionCachedInfo>,
+    merged_player_info: &MergedPlayerInfo,
+) -> Vec<Vec<i32>> {
+    for cached_mission in &mut cached_mission_list {
+        cached_mission.merge_player_alias(merged_player_info);
+    }
+
+    cluster_session(
+        cached_mission_list
+            .iter()
+            .map(|mission| {
+                (
+                    mission.mission_info.id,
+                    mission.mission_info.begin_timestamp,
+                    mission
+                        .player_info
+                        .iter()
+                        .map(|player_info| player_info.player_id)
+                        .collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+#[get("/play_session")]
+async fn get_play_session(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<Vec<Vec<i32>>>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine, player_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get player list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate(cached_mission_list, &merged_player_info);
+
+        debug!("play session info generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::mission::tests::cached_mission;
+    use std::collections::HashMap;
+
+    #[test]
+    fn consecutive_missions_with_same_players_form_session() {
+        let team = BTreeSet::from([1, 2]);
+        let other_team = BTreeSet::from([1, 3]);
+
+        let mission_list = vec![
+            (4, 3000, team.clone()),
+            (1, 0, team.clone()),
+            (2, 1000, team.clone()),
+            // 玩家不同，结束上一段
+            (3, 2000, other_team.clone()),
+            (5, 3000 + RE_SPOT_TIME_THRESHOLD, team.clone()),
+            // 间隔超过RE_SPOT_TIME_THRESHOLD
+            (6, 4000 + 2 * RE_SPOT_TIME_THRESHOLD, team.clone()),
+        ];
+
+        assert_eq!(cluster_session(mission_list), vec![vec![1, 2], vec![4, 5]]);
+    }
+
+    #[test]
+    fn alias_counts_as_same_player() {
+        let player = |id, player_name: &str| Player {
+            id,
+            player_name: player_name.to_string(),
+            friend: false,
+        };
+        let player_list = vec![player(1, "a"), player(2, "b"), player(3, "b_alt")];
+        let player_mapping = HashMap::from([("b_alt".to_string(), "b".to_string())]);
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        let mut second_mission = cached_mission(2, &[(1, 0), (3, 1)]);
+        second_mission.mission_info.begin_timestamp = 1000;
+
+        let cached_mission_list = vec![cached_mission(1, &[(1, 0), (2, 1)]), second_mission];
+
+        assert_eq!(
+            generate(cached_mission_list, &merged_player_info),
+            vec![vec![1, 2]]
+        );
+    }
+}
@@ -2,7 +2,7 @@ use super::{PlayerData, PlayerInfo};
 use crate::cache::mission::MissionCachedInfo;
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool, RecentWindowInfo};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -15,7 +15,7 @@ use std::time::Instant;
 #[get("/player")]
 async fn get_player(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<PlayerInfo>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -23,10 +23,13 @@ async fn get_player(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
     let character_game_id_to_name = mapping.character_mapping.clone();
 
     drop(mapping);
 
+    let recent_window = app_state.recent_window;
+
     let result = web::block(move || {
         let begin = Instant::now();
 
@@ -46,7 +49,7 @@ async fn get_player(
             }
         };
 
-        let cached_mission_list = match MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -79,16 +82,17 @@ async fn get_player(
             }
         };
 
-        let watchlist_player_id_list: Vec<i16> = player_list
-            .iter()
-            .filter(|x| x.friend)
-            .map(|x| x.id)
-            .collect();
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|x| (x.id, x.player_name))
-            .collect();
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        let MergedPlayerInfo {
+            watchlist_player_id_list,
+            player_id_to_name,
+            ..
+        } = merged_player_info;
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -116,6 +120,7 @@ async fn get_player(
             &player_id_to_name,
             &character_id_to_game_id,
             character_game_id_to_name,
+            recent_window,
         );
 
         debug!("player info generated in {:?}", begin.elapsed());
@@ -138,6 +143,7 @@ fn generate(
     player_id_to_name: &HashMap<i16, String>,
     character_id_to_game_id: &HashMap<i16, String>,
     character_game_id_to_name: HashMap<String, String>,
+    recent_window: RecentWindowInfo,
 ) -> PlayerInfo {
     let invalid_mission_id_set = invalid_mission_id_list
         .iter()
@@ -172,16 +178,7 @@ fn generate(
     let mut prev_player_data_map = HashMap::with_capacity(mission_list_by_player.len());
 
     for (player_id, player_mission_list) in mission_list_by_player {
-        let prev_count = match player_mission_list.len() * 8 / 10 {
-            0..10 => 10,
-            x => x,
-        };
-
-        let prev_count = if prev_count > player_mission_list.len() {
-            player_mission_list.len()
-        } else {
-            prev_count
-        };
+        let prev_count = recent_window.prev_count(player_mission_list.len());
 
         let prev_mission_list = &player_mission_list[0..prev_count];
 
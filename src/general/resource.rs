@@ -0,0 +1,202 @@
+use super::{PlayerResourceRankData, ResourceLeaderboardInfo, ResourceLeaderboardQuery};
+use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
+use crate::db::schema::*;
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+};
+use diesel::prelude::*;
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+#[get("/resource_leaderboard")]
+async fn get_resource_leaderboard(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    query: web::Query<ResourceLeaderboardQuery>,
+) -> Json<APIResponse<ResourceLeaderboardInfo>> {
+    let (
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+        resource_game_id_to_name,
+        player_mapping,
+    ) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.resource_mapping.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let sort_by = query.into_inner().sort_by;
+
+    let result = web::block(move || {
+        let begin = Instant::now();
+
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+        ) {
+            Ok(x) => x,
+            Err(()) => {
+                error!("cannot get cached mission list");
+                return Err(());
+            }
+        };
+
+        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+            .select(mission_invalid::mission_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get invalid mission list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get player list from db: {}", e);
+                return Err(());
+            }
+        };
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        let MergedPlayerInfo {
+            watchlist_player_id_list,
+            player_id_to_name,
+            ..
+        } = merged_player_info;
+
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
+
+        let result = generate(
+            &cached_mission_list,
+            &invalid_mission_id_list,
+            &watchlist_player_id_list,
+            &player_id_to_name,
+            resource_game_id_to_name,
+            sort_by.as_deref(),
+        );
+
+        debug!("resource leaderboard generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+fn generate(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    watchlist_player_id_list: &[i16],
+    player_id_to_name: &HashMap<i16, String>,
+    resource_game_id_to_name: HashMap<String, String>,
+    sort_by: Option<&str>,
+) -> ResourceLeaderboardInfo {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let watchlist_player_id_set = watchlist_player_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    // player_id -> (valid_mission_count, resource_game_id -> total_amount)
+    let mut player_resource_map: HashMap<i16, (i32, HashMap<String, f64>)> = HashMap::new();
+
+    for mission in cached_mission_list
+        .iter()
+        .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+    {
+        for player_info in &mission.player_info {
+            if !watchlist_player_id_set.contains(&player_info.player_id) {
+                continue;
+            }
+
+            let entry = player_resource_map
+                .entry(player_info.player_id)
+                .or_default();
+
+            entry.0 += 1;
+
+            if let Some(resource_info) = mission.resource_info.get(&player_info.player_id) {
+                for (resource_game_id, &amount) in resource_info {
+                    *entry.1.entry(resource_game_id.clone()).or_default() += amount;
+                }
+            }
+        }
+    }
+
+    let mut player_list = player_resource_map
+        .into_iter()
+        .map(
+            |(player_id, (valid_mission_count, resource))| PlayerResourceRankData {
+                player_name: player_id_to_name.get(&player_id).unwrap().clone(),
+                resource,
+                valid_mission_count,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    // 未指定资源时按总采集量排序，相同时按玩家名排序
+    let sort_value = |item: &PlayerResourceRankData| match sort_by {
+        Some(resource_game_id) => item.resource.get(resource_game_id).copied().unwrap_or(0.0),
+        None => item.resource.values().sum::<f64>(),
+    };
+
+    player_list.sort_unstable_by(|a, b| {
+        sort_value(b)
+            .total_cmp(&sort_value(a))
+            .then_with(|| a.player_name.cmp(&b.player_name))
+    });
+
+    ResourceLeaderboardInfo {
+        player: player_list,
+        resource_mapping: resource_game_id_to_name,
+    }
+}
@@ -0,0 +1,293 @@
+use super::PlayerSupplyData;
+use crate::cache::mission::MissionCachedInfo;
+use crate::db::models::*;
+use crate::db::schema::*;
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+};
+use diesel::prelude::*;
+use log::{debug, error};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+fn load_player_supply(
+    db_pool: &ReadDbPool,
+    redis_client: &redis::Client,
+    entity_blacklist_set: &HashSet<String>,
+    entity_combine: &HashMap<String, String>,
+    weapon_combine: &HashMap<String, String>,
+    player_mapping: &HashMap<String, String>,
+) -> Result<HashMap<String, PlayerSupplyData>, ()> {
+    let begin = Instant::now();
+
+    let mut db_conn = match db_pool.get() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get db connection from pool: {}", e);
+            return Err(());
+        }
+    };
+
+    let mut redis_conn = match redis_client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get redis connection: {}", e);
+            return Err(());
+        }
+    };
+
+    let mut cached_mission_list = match MissionCachedInfo::get_cached_all(
+        &mut db_conn,
+        &mut redis_conn,
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot get cached mission list");
+            return Err(());
+        }
+    };
+
+    let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+        .select(mission_invalid::mission_id)
+        .load(&mut db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get invalid mission list from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get player list from db: {}", e);
+            return Err(());
+        }
+    };
+
+    let merged_player_info = MergedPlayerInfo::new(&player_list, player_mapping);
+
+    for cached_mission in &mut cached_mission_list {
+        cached_mission.merge_player_alias(&merged_player_info);
+    }
+
+    let MergedPlayerInfo {
+        watchlist_player_id_list,
+        player_id_to_name,
+        ..
+    } = merged_player_info;
+
+    debug!("data prepared in {:?}", begin.elapsed());
+    let begin = Instant::now();
+
+    let result = generate(
+        &cached_mission_list,
+        &invalid_mission_id_list,
+        &watchlist_player_id_list,
+        &player_id_to_name,
+    );
+
+    debug!("player supply info generated in {:?}", begin.elapsed());
+
+    Ok(result)
+}
+
+#[get("/player_supply")]
+async fn get_player_supply(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<HashMap<String, PlayerSupplyData>>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine, player_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        load_player_supply(
+            &db_pool,
+            &redis_client,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+            &player_mapping,
+        )
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// (player_name, average_supply_efficiency, total_supply_count)
+type SupplyEfficiencyLeaderboard = Vec<(String, f64, i32)>;
+
+#[get("/supply_efficiency")]
+async fn get_supply_efficiency(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<SupplyEfficiencyLeaderboard>> {
+    let (entity_blacklist_set, entity_combine, weapon_combine, player_mapping) = {
+        let mapping = app_state.mapping.lock().unwrap();
+
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        load_player_supply(
+            &db_pool,
+            &redis_client,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+            &player_mapping,
+        )
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(supply_efficiency_leaderboard(x))),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 按补给效率降序排列，不包含没有补给记录的玩家
+fn supply_efficiency_leaderboard(
+    player_supply: HashMap<String, PlayerSupplyData>,
+) -> SupplyEfficiencyLeaderboard {
+    let mut result = player_supply
+        .into_iter()
+        .filter(|(_, data)| data.total_supply_count > 0)
+        .map(|(player_name, data)| {
+            (
+                player_name,
+                data.average_supply_efficiency,
+                data.total_supply_count,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    result.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    result
+}
+
+fn generate(
+    cached_mission_list: &[MissionCachedInfo],
+    invalid_mission_id_list: &[i32],
+    watchlist_player_id_list: &[i16],
+    player_id_to_name: &HashMap<i16, String>,
+) -> HashMap<String, PlayerSupplyData> {
+    let invalid_mission_id_set = invalid_mission_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let watchlist_player_id_set = watchlist_player_id_list
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    // player_id -> (valid_mission_count, supply_efficiency_list)
+    let mut player_supply_map: HashMap<i16, (i32, Vec<f64>)> = HashMap::new();
+
+    for mission in cached_mission_list
+        .iter()
+        .filter(|item| !invalid_mission_id_set.contains(&item.mission_info.id))
+    {
+        for player_info in &mission.player_info {
+            if !watchlist_player_id_set.contains(&player_info.player_id) {
+                continue;
+            }
+
+            let entry = player_supply_map.entry(player_info.player_id).or_default();
+
+            entry.0 += 1;
+
+            // 每份补给最多回复50%弹药（不含特长），故定义效率为2 * 弹药比例变化量
+            entry.1.extend(
+                mission
+                    .supply_info
+                    .get(&player_info.player_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|pack| 2.0 * pack.ammo),
+            );
+        }
+    }
+
+    player_supply_map
+        .into_iter()
+        .map(
+            |(player_id, (valid_mission_count, supply_efficiency_list))| {
+                let average_supply_efficiency = match supply_efficiency_list.len() {
+                    0 => 0.0,
+                    x => supply_efficiency_list.iter().sum::<f64>() / x as f64,
+                };
+
+                (
+                    player_id_to_name.get(&player_id).unwrap().clone(),
+                    PlayerSupplyData {
+                        average_supply_efficiency,
+                        total_supply_count: supply_efficiency_list.len() as i32,
+                        valid_mission_count,
+                    },
+                )
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply_data(average_supply_efficiency: f64, total_supply_count: i32) -> PlayerSupplyData {
+        PlayerSupplyData {
+            average_supply_efficiency,
+            total_supply_count,
+            valid_mission_count: 1,
+        }
+    }
+
+    #[test]
+    fn leaderboard_is_sorted_and_skips_players_without_supply() {
+        let player_supply = HashMap::from([
+            ("a".to_string(), supply_data(0.5, 4)),
+            ("b".to_string(), supply_data(0.9, 2)),
+            ("c".to_string(), supply_data(0.0, 0)),
+            ("d".to_string(), supply_data(0.5, 1)),
+        ]);
+
+        assert_eq!(
+            supply_efficiency_leaderboard(player_supply),
+            vec![
+                ("b".to_string(), 0.9, 2),
+                ("a".to_string(), 0.5, 4),
+                ("d".to_string(), 0.5, 1),
+            ]
+        );
+    }
+}
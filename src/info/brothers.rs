@@ -4,7 +4,7 @@ use serde::Serialize;
 
 use crate::cache::mission::MissionCachedInfo;
 use crate::RE_SPOT_TIME_THRESHOLD;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, MergedPlayerInfo, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -136,7 +136,7 @@ fn generate(
 #[get("/brothers")]
 async fn get_brothers_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<APIBrothers>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -144,6 +144,7 @@ async fn get_brothers_info(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -173,24 +174,7 @@ async fn get_brothers_info(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
-
-        let watchlist_player_id_list: Vec<i16> = match player::table
-            .select(player::id)
-            .filter(player::friend.eq(true))
-            .load::<i16>(&mut db_conn)
-        {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get player id list: {}", e);
-                return Err(());
-            }
-        };
-
-        let cached_mission_list = MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -198,6 +182,18 @@ async fn get_brothers_info(
             &weapon_combine,
         )?;
 
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        let MergedPlayerInfo {
+            watchlist_player_id_list,
+            player_id_to_name,
+            ..
+        } = merged_player_info;
+
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
 
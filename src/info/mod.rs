@@ -1,8 +1,14 @@
 use actix_web::web;
 pub mod brothers;
+pub mod recent_window;
+pub mod version;
 pub mod weapon;
+pub mod weapon_meta;
 
 pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(brothers::get_brothers_info);
     cfg.service(weapon::get_weapon_preference);
+    cfg.service(recent_window::get_recent_window);
+    cfg.service(weapon_meta::get_weapon_meta);
+    cfg.service(version::get_version);
 }
@@ -0,0 +1,10 @@
+use crate::{APIResponse, AppState, RecentWindowInfo};
+use actix_web::{
+    get,
+    web::{Data, Json},
+};
+
+#[get("/recent_window")]
+async fn get_recent_window(app_state: Data<AppState>) -> Json<APIResponse<RecentWindowInfo>> {
+    Json(APIResponse::ok(app_state.recent_window))
+}
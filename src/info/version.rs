@@ -0,0 +1,31 @@
+use crate::{APIResponse, KPI_VERSION};
+use actix_web::{get, web::Json};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct VersionInfo {
+    #[serde(rename = "kpiVersion")]
+    pub kpi_version: String,
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    // 构建时的环境变量GIT_HASH，未设置时为null
+    #[serde(rename = "gitHash")]
+    pub git_hash: Option<String>,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        VersionInfo {
+            kpi_version: KPI_VERSION.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: option_env!("GIT_HASH")
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string()),
+        }
+    }
+}
+
+#[get("/version")]
+async fn get_version() -> Json<APIResponse<VersionInfo>> {
+    Json(APIResponse::ok(VersionInfo::current()))
+}
@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::cache::mission::MissionCachedInfo;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -132,7 +132,7 @@ fn generate(
 #[get("/weapon_preference")]
 async fn get_weapon_preference(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<WeaponPreferenceResponse>> {
     let mapping = app_state.mapping.lock().unwrap();
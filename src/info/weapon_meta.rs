@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+
+use crate::{APIResponse, AppState, ReadDbPool};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+};
+use serde::Serialize;
+
+use crate::db::schema::*;
+use crate::{is_ordered_weapon, WEAPON_ORDER, WEAPON_TYPE};
+use diesel::prelude::*;
+use log::{error, warn};
+
+// 未在WEAPON_TYPE/WEAPON_ORDER中定义的武器使用的值，排在所有已知武器之后
+pub const UNKNOWN_WEAPON_TYPE: i16 = -1;
+pub const UNKNOWN_WEAPON_ORDER: i16 = i16::MAX;
+
+// 每个未知武器只警告一次
+static WARNED_UNKNOWN_WEAPON: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct WeaponMeta {
+    #[serde(rename = "type")]
+    pub weapon_type: i16,
+    pub order: i16,
+}
+
+// weapon_game_id -> WeaponMeta
+type WeaponMetaResponse = HashMap<String, WeaponMeta>;
+
+fn generate(
+    weapon_game_id_list: &[String],
+    weapon_combine: &HashMap<String, String>,
+) -> WeaponMetaResponse {
+    // WEAPON_TYPE/WEAPON_ORDER为const，每次使用都会重新构造，先取出
+    let weapon_type_map = WEAPON_TYPE;
+    let weapon_order_map = WEAPON_ORDER;
+
+    let mut weapon_game_id_set = weapon_type_map
+        .keys()
+        .chain(weapon_order_map.keys())
+        .map(|&x| x.to_string())
+        .collect::<HashSet<_>>();
+
+    for weapon_game_id in weapon_game_id_list {
+        let weapon_game_id = weapon_combine.get(weapon_game_id).unwrap_or(weapon_game_id);
+        // 工具等不参与武器排序，与load_mission的未知武器检查一致
+        if is_ordered_weapon(weapon_game_id) {
+            weapon_game_id_set.insert(weapon_game_id.clone());
+        }
+    }
+
+    let mut result = HashMap::with_capacity(weapon_game_id_set.len());
+
+    for weapon_game_id in weapon_game_id_set {
+        let weapon_type = weapon_type_map.get(weapon_game_id.as_str()).copied();
+        let order = weapon_order_map.get(weapon_game_id.as_str()).copied();
+
+        if (weapon_type.is_none() || order.is_none())
+            && WARNED_UNKNOWN_WEAPON
+                .lock()
+                .unwrap()
+                .insert(weapon_game_id.clone())
+        {
+            warn!("weapon {} has no type or order defined", weapon_game_id);
+        }
+
+        result.insert(
+            weapon_game_id,
+            WeaponMeta {
+                weapon_type: weapon_type.unwrap_or(UNKNOWN_WEAPON_TYPE),
+                order: order.unwrap_or(UNKNOWN_WEAPON_ORDER),
+            },
+        );
+    }
+
+    result
+}
+
+#[get("/weapon_meta")]
+async fn get_weapon_meta(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+) -> Json<APIResponse<WeaponMetaResponse>> {
+    let weapon_combine = app_state.mapping.lock().unwrap().weapon_combine.clone();
+
+    let result = web::block(move || {
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let weapon_game_id_list: Vec<String> = match weapon::table
+            .select(weapon::weapon_game_id)
+            .load(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get weapon list: {}", e);
+                return Err(());
+            }
+        };
+
+        Ok(generate(&weapon_game_id_list, &weapon_combine))
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => Json(APIResponse::ok(x)),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_weapon_gets_sentinel_meta() {
+        let weapon_game_id_list = vec![
+            "WPN_Pistol_A".to_string(),
+            "WPN_NotExist".to_string(),
+            "WPN_Combined".to_string(),
+            "WPN_Pickaxe".to_string(),
+            "Grenade_Impact".to_string(),
+        ];
+        let weapon_combine =
+            HashMap::from([("WPN_Combined".to_string(), "WPN_Pistol_A".to_string())]);
+
+        let result = generate(&weapon_game_id_list, &weapon_combine);
+        let weapon_type_map = WEAPON_TYPE;
+        let weapon_order_map = WEAPON_ORDER;
+
+        assert_eq!(
+            result["WPN_Pistol_A"],
+            WeaponMeta {
+                weapon_type: weapon_type_map["WPN_Pistol_A"],
+                order: weapon_order_map["WPN_Pistol_A"],
+            }
+        );
+        assert_eq!(
+            result["WPN_NotExist"],
+            WeaponMeta {
+                weapon_type: UNKNOWN_WEAPON_TYPE,
+                order: UNKNOWN_WEAPON_ORDER,
+            }
+        );
+        assert!(!result.contains_key("WPN_Combined"));
+        // 工具与非WPN_前缀的伤害来源不包含在内
+        assert!(!result.contains_key("WPN_Pickaxe"));
+        assert!(!result.contains_key("Grenade_Impact"));
+    }
+}
@@ -1,10 +1,11 @@
-use super::player::generate_player_kpi;
+use super::player::{generate_player_kpi, PlayerKPIInfo};
 use crate::cache::kpi::CachedGlobalKPIState;
 use crate::cache::mission::{MissionCachedInfo, MissionKPICachedInfo};
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
-use crate::{KPIConfig, FLOAT_EPSILON};
+use crate::kpi::BotKPIInfoQuery;
+use crate::{canonical_player_id_to_name, hazard_id_to_real, APIResponse, AppState, ReadDbPool};
+use crate::{MergedPlayerInfo, RecentWindowInfo, FLOAT_EPSILON};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -12,7 +13,7 @@ use actix_web::{
 use diesel::prelude::*;
 use log::{debug, error};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 #[derive(Serialize)]
@@ -24,24 +25,9 @@ pub struct PlayerBotKPIInfo {
 }
 
 fn generate_bot_kpi_info(
-    cached_mission_list: &[MissionCachedInfo],
-    mission_kpi_cached_info_list: &[MissionKPICachedInfo],
-    invalid_mission_id_list: &[i32],
-    watchlist_player_id_list: &[i16],
-    player_id_to_name: &HashMap<i16, String>,
-    global_kpi_state: &CachedGlobalKPIState,
-    kpi_config: &KPIConfig,
+    player_kpi_info: HashMap<String, PlayerKPIInfo>,
+    recent_window: RecentWindowInfo,
 ) -> HashMap<String, PlayerBotKPIInfo> {
-    let player_kpi_info = generate_player_kpi(
-        cached_mission_list,
-        mission_kpi_cached_info_list,
-        invalid_mission_id_list,
-        watchlist_player_id_list,
-        player_id_to_name,
-        global_kpi_state,
-        kpi_config,
-    );
-
     let mut result = HashMap::with_capacity(player_kpi_info.len());
 
     for (player_game_id, player_info) in player_kpi_info {
@@ -52,18 +38,13 @@ fn generate_bot_kpi_info(
             .flatten()
             .collect::<Vec<_>>();
 
-        player_mission_info_list.sort_unstable_by(|a, b| a.begin_timestamp.cmp(&b.begin_timestamp));
-
-        let prev_mission_count = match player_mission_info_list.len() * 8 / 10 {
-            0..10 => 10,
-            x => x,
-        };
+        player_mission_info_list.sort_unstable_by(|a, b| {
+            a.begin_timestamp
+                .cmp(&b.begin_timestamp)
+                .then_with(|| a.mission_id.cmp(&b.mission_id))
+        });
 
-        let prev_mission_count = if prev_mission_count >= player_mission_info_list.len() {
-            player_mission_info_list.len()
-        } else {
-            prev_mission_count
-        };
+        let prev_mission_count = recent_window.prev_count(player_mission_info_list.len());
 
         let prev_list = &player_mission_info_list[0..prev_mission_count];
         let recent_list = &player_mission_info_list[prev_mission_count..];
@@ -115,17 +96,39 @@ fn generate_bot_kpi_info(
     result
 }
 
+fn filter_by_hazard(
+    cached_mission_list: &mut Vec<MissionCachedInfo>,
+    mission_kpi_cached_info_list: &mut Vec<MissionKPICachedInfo>,
+    hazard: f64,
+) {
+    cached_mission_list.retain(|mission| {
+        hazard_id_to_real(mission.mission_info.hazard_id)
+            .is_some_and(|real_hazard| (real_hazard - hazard).abs() < FLOAT_EPSILON)
+    });
+
+    let mission_id_set = cached_mission_list
+        .iter()
+        .map(|mission| mission.mission_info.id)
+        .collect::<HashSet<_>>();
+
+    mission_kpi_cached_info_list.retain(|info| mission_id_set.contains(&info.mission_id));
+}
+
 #[get("/bot_kpi_info")]
 async fn get_bot_kpi_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
+    query: web::Query<BotKPIInfoQuery>,
 ) -> Json<APIResponse<HashMap<String, PlayerBotKPIInfo>>> {
+    let hazard = query.hazard;
+
     let mapping = app_state.mapping.lock().unwrap();
 
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -142,6 +145,14 @@ async fn get_bot_kpi_info(
         .unwrap()
         .scout_special_player_set
         .clone();
+    let non_mineral_resource_set = app_state
+        .mapping
+        .lock()
+        .unwrap()
+        .non_mineral_resource_set
+        .clone();
+
+    let recent_window = app_state.recent_window;
 
     let result = web::block(move || {
         let begin = Instant::now();
@@ -170,16 +181,7 @@ async fn get_bot_kpi_info(
             }
         };
 
-        let watchlist_player_id_list = player_list
-            .iter()
-            .filter(|item| item.friend)
-            .map(|item| item.id)
-            .collect::<Vec<_>>();
-
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -208,7 +210,7 @@ async fn get_bot_kpi_info(
             }
         };
 
-        let cached_mission_list = MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -216,7 +218,7 @@ async fn get_bot_kpi_info(
             &weapon_combine,
         )?;
 
-        let mission_kpi_cached_info_list = MissionKPICachedInfo::get_cached_all(
+        let mut mission_kpi_cached_info_list = MissionKPICachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -225,6 +227,7 @@ async fn get_bot_kpi_info(
             &character_id_to_game_id,
             &player_id_to_name,
             &scout_special_player_set,
+            &non_mineral_resource_set,
             &kpi_config,
         )?;
 
@@ -239,22 +242,44 @@ async fn get_bot_kpi_info(
             &player_id_to_name,
             &character_id_to_game_id,
             &scout_special_player_set,
+            &non_mineral_resource_set,
         )?;
 
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        for mission_kpi_cached_info in &mut mission_kpi_cached_info_list {
+            mission_kpi_cached_info.merge_player_alias(&merged_player_info);
+        }
+
+        // 全局KPI基准仍按全部难度的任务计算
+        if let Some(hazard) = hazard {
+            filter_by_hazard(
+                &mut cached_mission_list,
+                &mut mission_kpi_cached_info_list,
+                hazard,
+            );
+        }
+
         debug!("data prepared in {:?}", begin.elapsed());
 
         let begin = Instant::now();
 
-        let result = generate_bot_kpi_info(
+        let player_kpi_info = generate_player_kpi(
             &cached_mission_list,
             &mission_kpi_cached_info_list,
             &invalid_mission_id_list,
-            &watchlist_player_id_list,
-            &player_id_to_name,
+            &merged_player_info.watchlist_player_id_list,
+            &merged_player_info.player_id_to_name,
             &global_kpi_state,
             &kpi_config,
         );
 
+        let result = generate_bot_kpi_info(player_kpi_info, recent_window);
+
         debug!("bot kpi info generated in {:?}", begin.elapsed());
         Ok(result)
     })
@@ -0,0 +1,143 @@
+use crate::cache::mission::MissionKPICachedInfo;
+use crate::db::models::*;
+use crate::db::schema::*;
+use crate::{canonical_player_id_to_name, APIResponse, AppState, ReadDbPool};
+use actix_web::{
+    get,
+    web::{self, Data, Json},
+    HttpRequest,
+};
+use diesel::prelude::*;
+use log::error;
+use std::collections::HashMap;
+
+// 返回单个任务缓存的KPI中间数据，用于排查KPI计算问题
+// 仅在设置DEBUG_ENDPOINTS时可用，否则返回404
+#[get("/debug/{mission_id}")]
+async fn get_mission_kpi_debug(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    redis_client: Data<redis::Client>,
+    path: web::Path<i32>,
+) -> Json<APIResponse<MissionKPICachedInfo>> {
+    if !app_state.debug_endpoints {
+        return Json(APIResponse::not_found());
+    }
+
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let mission_id = path.into_inner();
+
+    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
+        Some(x) => x,
+        None => {
+            return Json(APIResponse::config_required("kpi_config"));
+        }
+    };
+
+    let (
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+        scout_special_player_set,
+        non_mineral_resource_set,
+        player_mapping,
+    ) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.scout_special_player_set.clone(),
+            mapping.non_mineral_resource_set.clone(),
+            mapping.player_mapping.clone(),
+        )
+    };
+
+    let result = web::block(move || {
+        let mut db_conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        let mut redis_conn = match redis_client.get_connection() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get redis connection: {}", e);
+                return Err(());
+            }
+        };
+
+        let mission_count: i64 = match mission::table
+            .filter(mission::id.eq(mission_id))
+            .count()
+            .get_result(&mut db_conn)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get mission {}: {}", mission_id, e);
+                return Err(());
+            }
+        };
+
+        if mission_count == 0 {
+            return Ok(None);
+        }
+
+        let player_id_to_name = match player::table.select(Player::as_select()).load(&mut db_conn) {
+            Ok(x) => canonical_player_id_to_name(&x, &player_mapping),
+            Err(e) => {
+                error!("cannot get player list: {}", e);
+                return Err(());
+            }
+        };
+
+        let character_id_to_game_id = match character::table
+            .select(Character::as_select())
+            .load(&mut db_conn)
+        {
+            Ok(x) => x
+                .into_iter()
+                .map(|character| (character.id, character.character_game_id))
+                .collect::<HashMap<_, _>>(),
+            Err(e) => {
+                error!("cannot get character list: {}", e);
+                return Err(());
+            }
+        };
+
+        match MissionKPICachedInfo::get_cached(
+            &mut db_conn,
+            &mut redis_conn,
+            &entity_blacklist_set,
+            &entity_combine,
+            &weapon_combine,
+            &character_id_to_game_id,
+            &player_id_to_name,
+            &scout_special_player_set,
+            &non_mineral_resource_set,
+            &kpi_config,
+            mission_id,
+        ) {
+            Ok(x) => Ok(Some(x)),
+            Err(()) => {
+                error!("cannot get mission kpi cached info");
+                Err(())
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(Some(x)) => Json(APIResponse::ok(x)),
+        Ok(None) => Json(APIResponse::not_found()),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
@@ -4,7 +4,7 @@ use crate::db::models::*;
 use crate::db::schema::*;
 use crate::kpi::CharacterKPIType;
 use crate::kpi::IndexTransformRange;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{canonical_player_id_to_name, APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -25,7 +25,7 @@ pub struct GammaInnerInfo {
 #[get("/gamma")]
 async fn get_gamma_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<HashMap<String, HashMap<String, GammaInnerInfo>>>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -33,6 +33,7 @@ async fn get_gamma_info(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -49,6 +50,12 @@ async fn get_gamma_info(
         .unwrap()
         .scout_special_player_set
         .clone();
+    let non_mineral_resource_set = app_state
+        .mapping
+        .lock()
+        .unwrap()
+        .non_mineral_resource_set
+        .clone();
 
     let result = web::block(move || {
         let mut db_conn = match db_pool.get() {
@@ -75,10 +82,7 @@ async fn get_gamma_info(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -118,6 +122,7 @@ async fn get_gamma_info(
             &player_id_to_name,
             &character_id_to_game_id,
             &scout_special_player_set,
+            &non_mineral_resource_set,
         )?;
 
         Ok(result)
@@ -151,7 +156,7 @@ async fn get_gamma_info(
 #[get("/transform_range_info")]
 async fn get_transform_range_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<HashMap<String, HashMap<String, Vec<IndexTransformRange>>>>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -159,6 +164,7 @@ async fn get_transform_range_info(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -175,6 +181,12 @@ async fn get_transform_range_info(
         .unwrap()
         .scout_special_player_set
         .clone();
+    let non_mineral_resource_set = app_state
+        .mapping
+        .lock()
+        .unwrap()
+        .non_mineral_resource_set
+        .clone();
 
     let result = web::block(move || {
         let mut db_conn = match db_pool.get() {
@@ -201,10 +213,7 @@ async fn get_transform_range_info(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -244,6 +253,7 @@ async fn get_transform_range_info(
             &player_id_to_name,
             &character_id_to_game_id,
             &scout_special_player_set,
+            &non_mineral_resource_set,
         )?;
 
         Ok(result)
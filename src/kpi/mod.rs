@@ -1,4 +1,5 @@
 pub mod bot_kpi_info;
+pub mod debug;
 pub mod info;
 pub mod player;
 pub mod version;
@@ -10,6 +11,7 @@ use std::fmt::Formatter;
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
+    hash::Hash,
 };
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -156,6 +158,25 @@ impl KPIComponent {
             KPIComponent::Minerals => 1.0,
         }
     }
+
+    // Death、Supply为负向指标，FriendlyFire在友伤率过高时为-1000
+    pub fn min_value(&self) -> f64 {
+        match self {
+            KPIComponent::Kill => 0.0,
+            KPIComponent::Damage => 0.0,
+            KPIComponent::Priority => 0.0,
+            KPIComponent::Revive => 0.0,
+            KPIComponent::Death => -1.0,
+            KPIComponent::FriendlyFire => -1000.0,
+            KPIComponent::Nitra => 0.0,
+            KPIComponent::Supply => -1.0,
+            KPIComponent::Minerals => 0.0,
+        }
+    }
+
+    pub fn clamp_index(&self, index: f64) -> f64 {
+        index.clamp(self.min_value(), self.max_value())
+    }
 }
 
 impl TryFrom<usize> for KPIComponent {
@@ -183,6 +204,272 @@ pub struct KPIConfig {
     pub resource_weight_table: HashMap<String, f64>,
     pub character_component_weight: HashMap<CharacterKPIType, HashMap<KPIComponent, f64>>,
     pub transform_range: Vec<IndexTransformRangeConfig>,
+    // 不计入全局基准（修正因子、转换区间）的玩家，其KPI仍按该基准计算
+    #[serde(default)]
+    pub baseline_excluded_player: Vec<String>,
+    // 友伤指数曲线，未设置时使用FriendlyFireConfig::default()
+    #[serde(default)]
+    pub friendly_fire: Option<FriendlyFireConfig>,
+}
+
+// 友伤率不低于cutoff时友伤指数为floor，否则为coefficient / (ff_rate - 1) + coefficient + 1，
+// 即无友伤时为1
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct FriendlyFireConfig {
+    pub cutoff: f64,
+    pub coefficient: f64,
+    pub floor: f64,
+}
+
+impl Default for FriendlyFireConfig {
+    fn default() -> Self {
+        FriendlyFireConfig {
+            cutoff: 0.91,
+            coefficient: 99.0,
+            floor: -1000.0,
+        }
+    }
+}
+
+impl KPIConfig {
+    // 上传时检查配置，避免缓存生成时才因缺少权重等问题失败
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problem_list = Vec::new();
+
+        for character_kpi_type in (0..5i16).map(|x| CharacterKPIType::try_from(x).unwrap()) {
+            let component_weight = match self.character_component_weight.get(&character_kpi_type) {
+                Some(x) => x,
+                None => {
+                    problem_list.push(format!(
+                        "character_component_weight: missing character {}",
+                        character_kpi_type
+                    ));
+                    continue;
+                }
+            };
+
+            for kpi_component in (0..9usize).map(|x| KPIComponent::try_from(x).unwrap()) {
+                if !component_weight.contains_key(&kpi_component) {
+                    problem_list.push(format!(
+                        "character_component_weight: missing component {} for {}",
+                        kpi_component, character_kpi_type
+                    ));
+                }
+            }
+        }
+
+        let in_unit_range = |range: (f64, f64)| {
+            (0.0..=1.0).contains(&range.0) && (0.0..=1.0).contains(&range.1) && range.0 <= range.1
+        };
+
+        for (i, range_config) in self.transform_range.iter().enumerate() {
+            if !in_unit_range(range_config.rank_range) {
+                problem_list.push(format!(
+                    "transform_range[{}]: invalid rank range {:?}",
+                    i, range_config.rank_range
+                ));
+            }
+
+            if !in_unit_range(range_config.transform_range) {
+                problem_list.push(format!(
+                    "transform_range[{}]: invalid transform range {:?}",
+                    i, range_config.transform_range
+                ));
+            }
+
+            if i > 0 {
+                let prev = &self.transform_range[i - 1];
+
+                if range_config.rank_range.0 < prev.rank_range.1 {
+                    problem_list.push(format!(
+                        "transform_range[{}]: rank range {:?} is not sorted after {:?}",
+                        i, range_config.rank_range, prev.rank_range
+                    ));
+                }
+
+                if range_config.transform_range.0 < prev.transform_range.1 {
+                    problem_list.push(format!(
+                        "transform_range[{}]: transform range {:?} is not sorted after {:?}",
+                        i, range_config.transform_range, prev.transform_range
+                    ));
+                }
+            }
+        }
+
+        if let Some(friendly_fire) = &self.friendly_fire {
+            // cutoff大于1时友伤率为1会除以0
+            if !(friendly_fire.cutoff > 0.0 && friendly_fire.cutoff <= 1.0) {
+                problem_list.push(format!(
+                    "friendly_fire: cutoff {} is not in (0, 1]",
+                    friendly_fire.cutoff
+                ));
+            }
+
+            if friendly_fire.coefficient.is_nan() || friendly_fire.coefficient <= 0.0 {
+                problem_list.push(format!(
+                    "friendly_fire: coefficient {} is not positive",
+                    friendly_fire.coefficient
+                ));
+            }
+
+            // 修正后的指数会被限制在该范围内
+            let component = KPIComponent::FriendlyFire;
+            if !(component.min_value()..=component.max_value()).contains(&friendly_fire.floor) {
+                problem_list.push(format!(
+                    "friendly_fire: floor {} is not in [{}, {}]",
+                    friendly_fire.floor,
+                    component.min_value(),
+                    component.max_value()
+                ));
+            }
+        }
+
+        match problem_list.is_empty() {
+            true => Ok(()),
+            false => Err(problem_list),
+        }
+    }
+
+    // 以self为当前生效的配置，比较staged中变化的项
+    pub fn diff(&self, staged: &KPIConfig) -> KPIConfigDiff {
+        let transform_range_changed = self.transform_range.len() != staged.transform_range.len()
+            || self
+                .transform_range
+                .iter()
+                .zip(&staged.transform_range)
+                .any(|(a, b)| {
+                    a.rank_range != b.rank_range || a.transform_range != b.transform_range
+                });
+
+        KPIConfigDiff {
+            priority_table: diff_weight_table(&self.priority_table, &staged.priority_table),
+            resource_weight_table: diff_weight_table(
+                &self.resource_weight_table,
+                &staged.resource_weight_table,
+            ),
+            character_weight_table: diff_character_table(
+                &self.character_weight_table,
+                &staged.character_weight_table,
+            ),
+            character_component_weight: diff_character_table(
+                &self.character_component_weight,
+                &staged.character_component_weight,
+            ),
+            transform_range: match transform_range_changed {
+                true => Some(KPITransformRangeChange {
+                    active: self.transform_range.clone(),
+                    staged: staged.transform_range.clone(),
+                }),
+                false => None,
+            },
+            friendly_fire: match self.friendly_fire == staged.friendly_fire {
+                true => None,
+                false => Some(KPIFriendlyFireChange {
+                    active: self.friendly_fire,
+                    staged: staged.friendly_fire,
+                }),
+            },
+        }
+    }
+}
+
+// 某一权重项的变化，不存在的项为None
+#[derive(Serialize, Deserialize)]
+pub struct KPIValueChange<K> {
+    pub key: K,
+    pub active: Option<f64>,
+    pub staged: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KPITransformRangeChange {
+    pub active: Vec<IndexTransformRangeConfig>,
+    pub staged: Vec<IndexTransformRangeConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KPIFriendlyFireChange {
+    pub active: Option<FriendlyFireConfig>,
+    pub staged: Option<FriendlyFireConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KPIConfigDiff {
+    #[serde(rename = "priorityTable")]
+    pub priority_table: Vec<KPIValueChange<String>>,
+    #[serde(rename = "resourceWeightTable")]
+    pub resource_weight_table: Vec<KPIValueChange<String>>,
+    #[serde(rename = "characterWeightTable")]
+    pub character_weight_table: Vec<KPIValueChange<(CharacterKPIType, String)>>,
+    #[serde(rename = "characterComponentWeight")]
+    pub character_component_weight: Vec<KPIValueChange<(CharacterKPIType, KPIComponent)>>,
+    // 转换区间整体比较，未变化时为None
+    #[serde(rename = "transformRange")]
+    pub transform_range: Option<KPITransformRangeChange>,
+    #[serde(rename = "friendlyFire")]
+    pub friendly_fire: Option<KPIFriendlyFireChange>,
+}
+
+impl KPIConfigDiff {
+    pub fn is_empty(&self) -> bool {
+        self.priority_table.is_empty()
+            && self.resource_weight_table.is_empty()
+            && self.character_weight_table.is_empty()
+            && self.character_component_weight.is_empty()
+            && self.transform_range.is_none()
+            && self.friendly_fire.is_none()
+    }
+}
+
+fn diff_weight_table<K: Clone + Eq + Hash + Display>(
+    active: &HashMap<K, f64>,
+    staged: &HashMap<K, f64>,
+) -> Vec<KPIValueChange<K>> {
+    let key_set = active.keys().chain(staged.keys()).collect::<HashSet<_>>();
+
+    let mut result = key_set
+        .into_iter()
+        .filter_map(|key| {
+            let active_value = active.get(key).copied();
+            let staged_value = staged.get(key).copied();
+
+            match active_value == staged_value {
+                true => None,
+                false => Some(KPIValueChange {
+                    key: key.clone(),
+                    active: active_value,
+                    staged: staged_value,
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    result.sort_unstable_by_key(|x| x.key.to_string());
+
+    result
+}
+
+fn diff_character_table<K: Clone + Eq + Hash + Display>(
+    active: &HashMap<CharacterKPIType, HashMap<K, f64>>,
+    staged: &HashMap<CharacterKPIType, HashMap<K, f64>>,
+) -> Vec<KPIValueChange<(CharacterKPIType, K)>> {
+    let empty = HashMap::new();
+
+    (0..5i16)
+        .map(|x| CharacterKPIType::try_from(x).unwrap())
+        .flat_map(|character_kpi_type| {
+            diff_weight_table(
+                active.get(&character_kpi_type).unwrap_or(&empty),
+                staged.get(&character_kpi_type).unwrap_or(&empty),
+            )
+            .into_iter()
+            .map(move |change| KPIValueChange {
+                key: (character_kpi_type, change.key),
+                active: change.active,
+                staged: change.staged,
+            })
+        })
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
@@ -206,6 +493,18 @@ pub struct IndexTransformRange {
     pub player_count: i32,
 }
 
+#[derive(Deserialize)]
+pub struct PlayerKPIQuery {
+    // 仅计算该玩家的KPI，玩家不在关注列表中时返回not_found
+    pub player: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BotKPIInfoQuery {
+    // 仅统计该难度（hazard_id_to_real后的实际难度）的任务，未设置时统计全部难度
+    pub hazard: Option<f64>,
+}
+
 #[derive(Serialize)]
 pub struct APIWeightTableData {
     #[serde(rename = "entityGameId")]
@@ -234,11 +533,11 @@ pub fn apply_weight_table(
     result.into_iter().map(|(k, v)| (k.clone(), v)).collect()
 }
 
-pub fn friendly_fire_index(ff_rate: f64) -> f64 {
-    if ff_rate >= 0.91 {
-        return -1000.0;
+pub fn friendly_fire_index(ff_rate: f64, config: &FriendlyFireConfig) -> f64 {
+    if ff_rate >= config.cutoff {
+        return config.floor;
     } else {
-        return 99.0 / (ff_rate - 1.0) + 100.0;
+        return config.coefficient / (ff_rate - 1.0) + (config.coefficient + 1.0);
     }
 }
 
@@ -252,4 +551,159 @@ pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(player::get_player_kpi);
 
     cfg.service(bot_kpi_info::get_bot_kpi_info);
+
+    cfg.service(debug::get_mission_kpi_debug);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const ALL_KPI_COMPONENT: [KPIComponent; 9] = [
+        KPIComponent::Kill,
+        KPIComponent::Damage,
+        KPIComponent::Priority,
+        KPIComponent::Revive,
+        KPIComponent::Death,
+        KPIComponent::FriendlyFire,
+        KPIComponent::Nitra,
+        KPIComponent::Supply,
+        KPIComponent::Minerals,
+    ];
+
+    #[test]
+    fn kpi_component_i16_round_trip() {
+        for component in ALL_KPI_COMPONENT {
+            let id: i16 = component.into();
+            assert_eq!(KPIComponent::try_from(id as usize), Ok(component));
+        }
+    }
+
+    #[test]
+    fn out_of_range_kpi_component_is_rejected() {
+        for id in [9usize, i16::MAX as usize, usize::MAX] {
+            assert!(KPIComponent::try_from(id).is_err());
+        }
+    }
+
+    pub(crate) fn valid_kpi_config() -> KPIConfig {
+        let component_weight = ALL_KPI_COMPONENT
+            .iter()
+            .map(|&x| (x, 0.1))
+            .collect::<HashMap<_, _>>();
+
+        KPIConfig {
+            character_weight_table: HashMap::new(),
+            priority_table: HashMap::new(),
+            resource_weight_table: HashMap::new(),
+            character_component_weight: (0..5i16)
+                .map(|x| {
+                    (
+                        CharacterKPIType::try_from(x).unwrap(),
+                        component_weight.clone(),
+                    )
+                })
+                .collect(),
+            transform_range: vec![
+                IndexTransformRangeConfig {
+                    rank_range: (0.0, 0.3),
+                    transform_range: (0.0, 0.35),
+                },
+                IndexTransformRangeConfig {
+                    rank_range: (0.3, 1.0),
+                    transform_range: (0.35, 1.0),
+                },
+            ],
+            baseline_excluded_player: Vec::new(),
+            friendly_fire: None,
+        }
+    }
+
+    #[test]
+    fn valid_kpi_config_passes_validation() {
+        assert!(valid_kpi_config().validate().is_ok());
+    }
+
+    #[test]
+    fn kpi_config_validation_lists_all_problems() {
+        let mut kpi_config = valid_kpi_config();
+        kpi_config
+            .character_component_weight
+            .remove(&CharacterKPIType::Gunner);
+        kpi_config
+            .character_component_weight
+            .get_mut(&CharacterKPIType::Driller)
+            .unwrap()
+            .remove(&KPIComponent::Nitra);
+        kpi_config.transform_range.swap(0, 1);
+        kpi_config.transform_range[1].transform_range = (0.0, 1.5);
+
+        let problem_list = kpi_config.validate().unwrap_err();
+
+        assert_eq!(problem_list.len(), 5);
+        assert!(problem_list
+            .iter()
+            .any(|x| x.contains("missing character gunner")));
+        assert!(problem_list
+            .iter()
+            .any(|x| x.contains("missing component nitra for driller")));
+    }
+
+    #[test]
+    fn kpi_config_diff_reports_changed_entries() {
+        let active = valid_kpi_config();
+        let mut staged = valid_kpi_config();
+
+        assert!(active.diff(&staged).is_empty());
+
+        staged
+            .priority_table
+            .insert("ED_Spider_Boss".to_string(), 2.0);
+        staged
+            .character_component_weight
+            .get_mut(&CharacterKPIType::Scout)
+            .unwrap()
+            .insert(KPIComponent::Revive, 0.3);
+        staged.transform_range[0].transform_range = (0.0, 0.3);
+
+        let diff = active.diff(&staged);
+
+        assert_eq!(diff.priority_table.len(), 1);
+        assert_eq!(diff.priority_table[0].active, None);
+        assert_eq!(diff.priority_table[0].staged, Some(2.0));
+        assert_eq!(diff.character_component_weight.len(), 1);
+        assert_eq!(
+            diff.character_component_weight[0].key,
+            (CharacterKPIType::Scout, KPIComponent::Revive)
+        );
+        assert!(diff.character_weight_table.is_empty());
+        assert!(diff.transform_range.is_some());
+    }
+
+    #[test]
+    fn default_friendly_fire_index_matches_fixed_curve() {
+        let config = FriendlyFireConfig::default();
+
+        for i in 0..=100 {
+            let ff_rate = i as f64 / 100.0;
+            let expected = match ff_rate >= 0.91 {
+                true => -1000.0,
+                false => 99.0 / (ff_rate - 1.0) + 100.0,
+            };
+
+            assert_eq!(
+                friendly_fire_index(ff_rate, &config).to_bits(),
+                f64::to_bits(expected)
+            );
+        }
+
+        let lenient = FriendlyFireConfig {
+            cutoff: 0.95,
+            coefficient: 49.0,
+            floor: -500.0,
+        };
+        assert_eq!(friendly_fire_index(0.0, &lenient), 1.0);
+        assert_eq!(friendly_fire_index(0.96, &lenient), -500.0);
+        assert!(friendly_fire_index(0.5, &lenient) > friendly_fire_index(0.5, &config));
+    }
 }
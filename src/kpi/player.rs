@@ -2,10 +2,11 @@ use crate::cache::kpi::*;
 use crate::cache::mission::*;
 use crate::db::models::*;
 use crate::db::schema::*;
-use crate::kpi::KPIConfig;
+use crate::kpi::{KPIConfig, PlayerKPIQuery};
 use crate::mission::mission::generate_mission_kpi;
 use crate::mission::MissionKPIInfo;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{canonical_player_id_to_name, canonical_player_name, MergedPlayerInfo};
+use crate::{APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
@@ -74,9 +75,15 @@ pub fn generate_player_kpi(
         .copied()
         .collect::<HashSet<_>>();
 
+    // 不含关注列表玩家的任务不影响结果，无需计算其KPI
     let mission_kpi_cached_info_list = mission_kpi_cached_info_list
         .into_iter()
         .filter(|item| !invalid_mission_id_set.contains(&item.mission_id))
+        .filter(|item| {
+            watchlist_player_id_list
+                .iter()
+                .any(|player_id| item.raw_kpi_data.contains_key(player_id))
+        })
         .collect::<Vec<_>>();
 
     let mission_id_to_cached_info = cached_mission_list
@@ -159,6 +166,12 @@ pub fn generate_player_kpi(
                 player_kpi_weighted_sum += player_index * mission_kpi_info.mission_kpi;
             }
 
+            result_mission_list.sort_unstable_by(|a, b| {
+                a.begin_timestamp
+                    .cmp(&b.begin_timestamp)
+                    .then_with(|| a.mission_id.cmp(&b.mission_id))
+            });
+
             let player_character_kpi_info = PlayerCharacterKPIInfo {
                 player_index: total_character_player_index,
                 character_kpi: mission_kpi_weighted_sum / total_character_player_index,
@@ -184,14 +197,18 @@ pub fn generate_player_kpi(
 #[get("/player_kpi")]
 async fn get_player_kpi(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
+    query: web::Query<PlayerKPIQuery>,
 ) -> Json<APIResponse<HashMap<String, PlayerKPIInfo>>> {
+    let PlayerKPIQuery { player } = query.into_inner();
+
     let mapping = app_state.mapping.lock().unwrap();
 
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -208,6 +225,12 @@ async fn get_player_kpi(
         .unwrap()
         .scout_special_player_set
         .clone();
+    let non_mineral_resource_set = app_state
+        .mapping
+        .lock()
+        .unwrap()
+        .non_mineral_resource_set
+        .clone();
 
     let result = web::block(move || {
         let begin = Instant::now();
@@ -236,16 +259,28 @@ async fn get_player_kpi(
             }
         };
 
-        let watchlist_player_id_list = player_list
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        // 使用别名查询时同样按其主名称匹配
+        let player = player.map(|player_name| canonical_player_name(&player_mapping, player_name));
+
+        let watchlist_player_id_list = merged_player_info
+            .watchlist_player_id_list
             .iter()
-            .filter(|item| item.friend)
-            .map(|item| item.id)
+            .copied()
+            .filter(|player_id| match &player {
+                Some(player_name) => {
+                    &merged_player_info.player_id_to_name[player_id] == player_name
+                }
+                None => true,
+            })
             .collect::<Vec<_>>();
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        if player.is_some() && watchlist_player_id_list.is_empty() {
+            return Ok(None);
+        }
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -274,7 +309,7 @@ async fn get_player_kpi(
             }
         };
 
-        let cached_mission_list = MissionCachedInfo::get_cached_all(
+        let mut cached_mission_list = MissionCachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -282,7 +317,7 @@ async fn get_player_kpi(
             &weapon_combine,
         )?;
 
-        let mission_kpi_cached_info_list = MissionKPICachedInfo::get_cached_all(
+        let mut mission_kpi_cached_info_list = MissionKPICachedInfo::get_cached_all(
             &mut db_conn,
             &mut redis_conn,
             &entity_blacklist_set,
@@ -291,6 +326,7 @@ async fn get_player_kpi(
             &character_id_to_game_id,
             &player_id_to_name,
             &scout_special_player_set,
+            &non_mineral_resource_set,
             &kpi_config,
         )?;
 
@@ -305,8 +341,17 @@ async fn get_player_kpi(
             &player_id_to_name,
             &character_id_to_game_id,
             &scout_special_player_set,
+            &non_mineral_resource_set,
         )?;
 
+        for cached_mission in &mut cached_mission_list {
+            cached_mission.merge_player_alias(&merged_player_info);
+        }
+
+        for mission_kpi_cached_info in &mut mission_kpi_cached_info_list {
+            mission_kpi_cached_info.merge_player_alias(&merged_player_info);
+        }
+
         debug!("data prepared in {:?}", begin.elapsed());
 
         let begin = Instant::now();
@@ -316,19 +361,20 @@ async fn get_player_kpi(
             &mission_kpi_cached_info_list,
             &invalid_mission_id_list,
             &watchlist_player_id_list,
-            &player_id_to_name,
+            &merged_player_info.player_id_to_name,
             &global_kpi_state,
             &kpi_config,
         );
 
         debug!("player kpi generated in {:?}", begin.elapsed());
-        Ok(result)
+        Ok(Some(result))
     })
     .await
     .unwrap();
 
     match result {
-        Ok(x) => Json(APIResponse::ok(x)),
+        Ok(Some(x)) => Json(APIResponse::ok(x)),
+        Ok(None) => Json(APIResponse::not_found()),
         Err(()) => Json(APIResponse::internal_error()),
     }
 }
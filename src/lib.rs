@@ -9,19 +9,51 @@ pub mod kpi;
 pub mod mission;
 use actix_web::{
     get,
-    web::{Data, Json},
+    web::{self, Data, Json},
+    HttpRequest, HttpResponse,
 };
+use cache::{CacheTypeUpdateState, CacheUpdateState};
 use diesel::pg::PgConnection;
 use diesel::r2d2::{ConnectionManager, Pool};
 use kpi::{KPIComponent, KPIConfig};
+use log::error;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::error::Category;
 use std::cell::LazyCell;
 use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const SESSION_COOKIE_NAME: &str = "session_id";
+// 未设置SESSION_TTL时session的有效期
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(24 * 3600);
+// 同一IP在LOGIN_ATTEMPT_WINDOW内失败LOGIN_MAX_FAILED_ATTEMPT次后，窗口结束前直接拒绝登录
+pub const LOGIN_MAX_FAILED_ATTEMPT: u32 = 5;
+pub const LOGIN_ATTEMPT_WINDOW: Duration = Duration::from_secs(15 * 60);
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 
+// 只读接口使用的连接池，配置了只读副本时指向副本，否则与DbPool相同
+pub struct ReadDbPool(pub DbPool);
+
+impl Deref for ReadDbPool {
+    type Target = DbPool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// 请求体的最大长度，对load_mission为压缩后的长度
+pub const MAX_BODY_LENGTH: usize = 64 * 1024 * 1024;
+// load_mission解压后数据的默认最大长度，防止高压缩比的数据耗尽内存
+pub const DEFAULT_MAX_DECOMPRESSED_LENGTH: usize = 1024 * 1024 * 1024;
+
 pub const NITRA_GAME_ID: &str = "RES_VEIN_Nitra";
 pub const FLOAT_EPSILON: f64 = 1e-3;
 pub const KPI_CALCULATION_PLAYER_INDEX: f64 = 0.5;
@@ -32,6 +64,12 @@ pub const RE_SPOT_TIME_THRESHOLD: i64 = 60 * 60 * 24;
 
 pub const INVALID_MISSION_TIME_THRESHOLD: i16 = 60 * 5;
 
+// 所有prev/recent（DeltaData）统计共用的“近期”窗口的默认定义，可通过RECENT_WINDOW_PREV_PERCENT、RECENT_WINDOW_PREV_MIN_COUNT覆盖，客户端通过/info/recent_window获取实际使用的值
+pub const RECENT_WINDOW: RecentWindowInfo = RecentWindowInfo {
+    prev_percent: 80,
+    prev_min_count: 10,
+};
+
 pub const CORRECTION_ITEMS: &[KPIComponent] = &[
     KPIComponent::Damage,
     KPIComponent::Priority,
@@ -106,6 +144,19 @@ pub const WEAPON_ORDER: LazyCell<HashMap<&str, i16>> = LazyCell::new(|| {
     ])
 });
 
+// 不属于主/副武器的工具，不参与武器排序
+pub const NON_ORDERED_WEAPON: [&str; 4] = [
+    "WPN_DoubleDrills",
+    "WPN_FlareGun",
+    "WPN_Pickaxe",
+    "WPN_PlagueSoaper",
+];
+
+// 是否为应在WEAPON_TYPE/WEAPON_ORDER中定义的主/副武器；手雷、炮台等非WPN_前缀的伤害来源及工具不是
+pub fn is_ordered_weapon(weapon_game_id: &str) -> bool {
+    weapon_game_id.starts_with("WPN_") && !NON_ORDERED_WEAPON.contains(&weapon_game_id)
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Mapping {
     #[serde(default)]
@@ -128,6 +179,12 @@ pub struct Mapping {
     pub weapon_character: HashMap<String, String>,
     #[serde(default)]
     pub scout_special_player_set: HashSet<String>,
+    // 不计入采集量的非矿物资源（如信用点），为空时计入全部资源
+    #[serde(default)]
+    pub non_mineral_resource_set: HashSet<String>,
+    // 玩家别名 -> 主名称，玩家改名后将其各个名称视为同一玩家
+    #[serde(default)]
+    pub player_mapping: HashMap<String, String>,
 }
 
 impl Default for Mapping {
@@ -143,25 +200,136 @@ impl Default for Mapping {
             weapon_combine: HashMap::new(),
             weapon_character: HashMap::new(),
             scout_special_player_set: HashSet::new(),
+            non_mineral_resource_set: HashSet::new(),
+            player_mapping: HashMap::new(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginAttempt {
+    Accepted,
+    Rejected,
+    // 失败次数过多，窗口结束前不再比较access_token
+    Locked,
+}
+
 pub struct AppState {
     pub access_token: Option<String>,
     pub instance_path: PathBuf,
     pub mapping: Mutex<Mapping>,
     pub kpi_config: Mutex<Option<KPIConfig>>,
+    pub cache_update: CacheUpdateState,
+    // /cache/update_*按类型合并并发的更新请求
+    pub cache_type_update: CacheTypeUpdateState,
+    // 全部缓存是否已生成，供/ready使用；启动时预热缓存的情况下，首次全部生成成功前为false
+    pub cache_ready: AtomicBool,
+    // 通过/admin/login获得的session及其创建时间
+    pub valid_session: Mutex<HashMap<String, Instant>>,
+    pub session_ttl: Duration,
+    // 各IP登录失败的次数及当前窗口的开始时间
+    pub login_attempt: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+    pub recent_window: RecentWindowInfo,
+    // 是否启用/kpi/debug等调试接口
+    pub debug_endpoints: bool,
+    // load_mission解压后数据的最大长度（字节）
+    pub max_decompressed_length: usize,
+}
+
+impl AppState {
+    // 创建新的session，同时清理已过期的session
+    pub fn new_session(&self) -> String {
+        let session_id = uuid::Uuid::new_v4().to_string();
+
+        let mut valid_session = self.valid_session.lock().unwrap();
+        valid_session.retain(|_, created| created.elapsed() < self.session_ttl);
+        valid_session.insert(session_id.clone(), Instant::now());
+
+        session_id
+    }
+
+    // 未设置access_token时不进行验证
+    pub fn check_session(&self, requests: &HttpRequest) -> bool {
+        if self.access_token.is_none() {
+            return true;
+        }
+
+        let session_id = match requests.cookie(SESSION_COOKIE_NAME) {
+            Some(x) => x,
+            None => return false,
+        };
+
+        let mut valid_session = self.valid_session.lock().unwrap();
+
+        match valid_session.get(session_id.value()) {
+            Some(created) if created.elapsed() < self.session_ttl => true,
+            Some(_) => {
+                valid_session.remove(session_id.value());
+                false
+            }
+            None => false,
+        }
+    }
+
+    // 在同一次加锁中检查是否已锁定并记录本次失败，同时清理已结束的窗口
+    // 注意：位于反向代理之后时peer_addr为代理的地址，所有客户端将共用同一计数
+    pub fn check_login_attempt(&self, ip: IpAddr, token_valid: bool) -> LoginAttempt {
+        let mut login_attempt = self.login_attempt.lock().unwrap();
+        login_attempt.retain(|_, (_, window_start)| window_start.elapsed() < LOGIN_ATTEMPT_WINDOW);
+
+        let entry = login_attempt.entry(ip).or_insert((0, Instant::now()));
+        if entry.0 >= LOGIN_MAX_FAILED_ATTEMPT {
+            return LoginAttempt::Locked;
+        }
+
+        if token_valid {
+            login_attempt.remove(&ip);
+            LoginAttempt::Accepted
+        } else {
+            entry.0 += 1;
+            LoginAttempt::Rejected
+        }
+    }
+
+    pub fn remove_session(&self, requests: &HttpRequest) {
+        if let Some(session_id) = requests.cookie(SESSION_COOKIE_NAME) {
+            self.valid_session
+                .lock()
+                .unwrap()
+                .remove(session_id.value());
+        }
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    pub(crate) fn for_test(access_token: Option<String>) -> Self {
+        AppState {
+            access_token,
+            instance_path: PathBuf::new(),
+            mapping: Mutex::new(Mapping::default()),
+            kpi_config: Mutex::new(None),
+            cache_update: CacheUpdateState::default(),
+            cache_type_update: CacheTypeUpdateState::default(),
+            cache_ready: AtomicBool::new(true),
+            valid_session: Mutex::new(HashMap::new()),
+            session_ttl: DEFAULT_SESSION_TTL,
+            login_attempt: Mutex::new(HashMap::new()),
+            recent_window: RECENT_WINDOW,
+            debug_endpoints: false,
+            max_decompressed_length: DEFAULT_MAX_DECOMPRESSED_LENGTH,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
-pub struct APIResponse<T: Serialize> {
+pub struct APIResponse<T> {
     pub code: i32,
     pub message: String,
     pub data: Option<T>,
 }
 
-impl<'a, T: Serialize> APIResponse<T> {
+impl<'a, T> APIResponse<T> {
     pub fn new(code: i32, message: String, data: Option<T>) -> Self {
         APIResponse {
             code,
@@ -211,6 +379,14 @@ impl<'a, T: Serialize> APIResponse<T> {
         }
     }
 
+    pub fn not_ready() -> Self {
+        APIResponse {
+            code: 503,
+            message: "Waiting for the drop pod: cache is being generated".to_string(),
+            data: None,
+        }
+    }
+
     pub fn config_required(for_what: &str) -> Self {
         APIResponse {
             code: 1001,
@@ -234,6 +410,97 @@ pub struct ClientConfig {
     pub watchlist_path: Option<String>,
     #[serde(default)]
     pub kpi_config_path: Option<String>,
+    // 连接失败时的最大重试次数，未设置时使用client::DEFAULT_MAX_RETRIES
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    // load_mission每批上传的任务数，未设置时使用client::DEFAULT_UPLOAD_BATCH_SIZE
+    #[serde(default)]
+    pub upload_batch_size: Option<usize>,
+    // begin_timestamp早于此时间的任务视为时钟异常，未设置时使用client::raw_log::DEFAULT_MIN_BEGIN_TIMESTAMP
+    #[serde(default)]
+    pub min_begin_timestamp: Option<i64>,
+    // 识别深潜时是否跳过时钟异常的任务，默认跳过
+    #[serde(default)]
+    pub exclude_skewed_from_deep_dive: Option<bool>,
+    // load_mission记录上次上传进度的文件，未设置时使用client::DEFAULT_LAST_UPLOAD_STATE_PATH
+    #[serde(default)]
+    pub last_upload_state_path: Option<String>,
+    // 请求的整体超时（秒），须大于0，未设置时不限制；可由命令行参数--timeout覆盖。连接超时固定为client::CONNECT_TIMEOUT
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+// 解析请求体中的JSON，错误信息中说明失败的阶段：读取、JSON语法或数据结构不符
+pub fn api_parse_json_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(body).map_err(|e| match e.classify() {
+        Category::Io => format!("cannot read payload body: {}", e),
+        Category::Syntax | Category::Eof => format!("payload body is not valid json: {}", e),
+        Category::Data => format!("payload json does not match expected schema: {}", e),
+    })
+}
+
+impl ClientConfig {
+    pub fn normalize(mut self) -> Result<Self, String> {
+        self.endpoint_url = normalize_endpoint_url(&self.endpoint_url)?;
+
+        if self.timeout == Some(0) {
+            return Err("timeout must be greater than 0".to_string());
+        }
+
+        Ok(self)
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(client::DEFAULT_MAX_RETRIES)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
+    }
+
+    pub fn timestamp_check(&self) -> client::raw_log::TimestampCheck {
+        client::raw_log::TimestampCheck::new(
+            self.min_begin_timestamp
+                .unwrap_or(client::raw_log::DEFAULT_MIN_BEGIN_TIMESTAMP),
+            self.exclude_skewed_from_deep_dive.unwrap_or(true),
+        )
+    }
+
+    pub fn last_upload_state_path(&self) -> PathBuf {
+        PathBuf::from(
+            self.last_upload_state_path
+                .as_deref()
+                .unwrap_or(client::DEFAULT_LAST_UPLOAD_STATE_PATH),
+        )
+    }
+
+    pub fn upload_batch_size(&self) -> usize {
+        match self.upload_batch_size {
+            Some(0) | None => client::DEFAULT_UPLOAD_BATCH_SIZE,
+            Some(x) => x,
+        }
+    }
+}
+
+// 去除末尾的'/'，要求使用http/https，避免拼接出"//mission"之类的地址
+pub fn normalize_endpoint_url(endpoint_url: &str) -> Result<String, String> {
+    let endpoint_url = endpoint_url.trim().trim_end_matches('/');
+
+    if !(endpoint_url.starts_with("http://") || endpoint_url.starts_with("https://")) {
+        return Err(format!(
+            "invalid endpoint_url {}: must start with http:// or https://",
+            endpoint_url
+        ));
+    }
+
+    match reqwest::Url::parse(endpoint_url) {
+        Ok(url) if url.host_str().is_some() => Ok(endpoint_url.to_string()),
+        Ok(_) => Err(format!(
+            "invalid endpoint_url {}: missing host",
+            endpoint_url
+        )),
+        Err(e) => Err(format!("invalid endpoint_url {}: {}", endpoint_url, e)),
+    }
 }
 
 #[derive(Serialize)]
@@ -252,18 +519,49 @@ pub struct APIMapping {
     pub weapon_combine: HashMap<String, String>,
     #[serde(rename = "weaponHero")]
     pub weapon_character: HashMap<String, String>,
+    pub player: HashMap<String, String>,
+}
+
+// 比较耗时与内容无关，避免通过响应时间猜测access_token
+// 长度不同时同样比较完整个较长的输入，不提前返回
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+
+    let diff = (0..len).fold((a.len() != b.len()) as u8, |acc, i| {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        acc | (x ^ y)
+    });
+
+    diff == 0
 }
 
-pub fn hazard_id_to_real(hazard_id: i16) -> f64 {
+// 未知的hazard_id返回None，由调用方跳过对应任务
+pub fn hazard_id_to_real(hazard_id: i16) -> Option<f64> {
     match hazard_id {
-        1..6 => hazard_id as f64,
-        100 => 3.0,
-        101 => 3.5,
-        102 => 3.5,
-        103 => 4.5,
-        104 => 5.0,
-        105 => 5.5,
-        _ => unreachable!("invalid hazard id"),
+        1..6 => Some(hazard_id as f64),
+        100 => Some(3.0),
+        101 => Some(3.5),
+        102 => Some(3.5),
+        103 => Some(4.5),
+        104 => Some(5.0),
+        105 => Some(5.5),
+        // 层数未知的普通深潜、精英深潜，取各层的中间难度
+        106 => Some(3.5),
+        107 => Some(5.0),
+        _ => None,
+    }
+}
+
+// 深潜的伪难度id对应的显示名称，供前端展示（如"Deep Dive L2"而非3.5）
+pub fn real_hazard_to_display_label(hazard_id: i16) -> Option<String> {
+    match hazard_id {
+        1..6 => Some(format!("Hazard {}", hazard_id)),
+        100..103 => Some(format!("Deep Dive L{}", hazard_id - 99)),
+        103..106 => Some(format!("Elite Deep Dive L{}", hazard_id - 102)),
+        106 => Some("Deep Dive".to_string()),
+        107 => Some("Elite Deep Dive".to_string()),
+        _ => None,
     }
 }
 
@@ -278,6 +576,100 @@ pub fn generate_mapping(mapping: Mapping) -> APIMapping {
         weapon: mapping.weapon_mapping,
         weapon_combine: mapping.weapon_combine,
         weapon_character: mapping.weapon_character,
+        player: mapping.player_mapping,
+    }
+}
+
+// 按player_mapping将玩家别名替换为主名称，未设置别名的玩家名称不变
+pub fn canonical_player_name(
+    player_mapping: &HashMap<String, String>,
+    player_name: String,
+) -> String {
+    match player_mapping.get(&player_name) {
+        Some(x) => x.clone(),
+        None => player_name,
+    }
+}
+
+// player_id -> 玩家名称，别名替换为主名称
+pub fn canonical_player_id_to_name(
+    player_list: &[db::models::Player],
+    player_mapping: &HashMap<String, String>,
+) -> HashMap<i16, String> {
+    player_list
+        .iter()
+        .map(|player| {
+            (
+                player.id,
+                canonical_player_name(player_mapping, player.player_name.clone()),
+            )
+        })
+        .collect()
+}
+
+// 按player_mapping合并别名后的玩家信息，主名称相同的玩家合并至其中最小的player_id
+// 用于跨任务按玩家汇总的统计，需先对缓存调用merge_player_alias
+pub struct MergedPlayerInfo {
+    // player_id -> 合并后的player_id
+    pub player_id_to_merged_id: HashMap<i16, i16>,
+    // 合并后的player_id -> 主名称
+    pub player_id_to_name: HashMap<i16, String>,
+    // 合并后的关注列表，任一别名在关注列表中即视为关注
+    pub watchlist_player_id_list: Vec<i16>,
+}
+
+impl MergedPlayerInfo {
+    pub fn new(
+        player_list: &[db::models::Player],
+        player_mapping: &HashMap<String, String>,
+    ) -> Self {
+        let mut name_to_merged_id: HashMap<String, i16> = HashMap::new();
+
+        for player in player_list {
+            let name = canonical_player_name(player_mapping, player.player_name.clone());
+            let entry = name_to_merged_id.entry(name).or_insert(player.id);
+            *entry = (*entry).min(player.id);
+        }
+
+        let player_id_to_merged_id = player_list
+            .iter()
+            .map(|player| {
+                let name = canonical_player_name(player_mapping, player.player_name.clone());
+                (player.id, name_to_merged_id[&name])
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut watchlist_player_id_list = player_list
+            .iter()
+            .filter(|player| player.friend)
+            .map(|player| player_id_to_merged_id[&player.id])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        watchlist_player_id_list.sort_unstable();
+
+        MergedPlayerInfo {
+            player_id_to_merged_id,
+            player_id_to_name: name_to_merged_id
+                .into_iter()
+                .map(|(name, player_id)| (player_id, name))
+                .collect(),
+            watchlist_player_id_list,
+        }
+    }
+
+    // 按名称（可为别名）查找合并后的player_id
+    pub fn find_player_id(
+        &self,
+        player_mapping: &HashMap<String, String>,
+        player_name: String,
+    ) -> Option<i16> {
+        let player_name = canonical_player_name(player_mapping, player_name);
+
+        self.player_id_to_name
+            .iter()
+            .find(|(_, name)| **name == player_name)
+            .map(|(player_id, _)| *player_id)
     }
 }
 
@@ -287,7 +679,314 @@ pub async fn get_mapping(app_state: Data<AppState>) -> Json<APIResponse<APIMappi
     Json(APIResponse::ok(generate_mapping(mapping.clone())))
 }
 
+// 按时间排序后，前prev_percent%（至少prev_min_count局，不超过总数）为prev，其余为recent
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct RecentWindowInfo {
+    #[serde(rename = "prevPercent")]
+    pub prev_percent: usize,
+    #[serde(rename = "prevMinCount")]
+    pub prev_min_count: usize,
+}
+
+impl RecentWindowInfo {
+    pub fn prev_count(&self, total_count: usize) -> usize {
+        (total_count * self.prev_percent / 100)
+            .max(self.prev_min_count)
+            .min(total_count)
+    }
+
+    pub fn recent_count(&self, total_count: usize) -> usize {
+        total_count - self.prev_count(total_count)
+    }
+}
+
 #[get("/heartbeat")]
 pub async fn echo_heartbeat() -> Json<APIResponse<()>> {
     Json(APIResponse::ok(()))
 }
+
+#[get("/ready")]
+pub async fn echo_ready(app_state: Data<AppState>) -> HttpResponse {
+    if app_state.cache_ready.load(Ordering::SeqCst) {
+        HttpResponse::Ok().json(APIResponse::ok(()))
+    } else {
+        HttpResponse::ServiceUnavailable().json(APIResponse::<()>::not_ready())
+    }
+}
+
+#[derive(Serialize)]
+pub struct HealthInfo {
+    pub database: bool,
+    pub redis: bool,
+}
+
+impl HealthInfo {
+    pub fn failed_dependency(&self) -> Vec<&'static str> {
+        let mut failed = Vec::new();
+        if !self.database {
+            failed.push("database");
+        }
+        if !self.redis {
+            failed.push("redis");
+        }
+        failed
+    }
+}
+
+// 检查数据库与Redis是否可连接，任一失败时返回HTTP 500，可用作负载均衡的存活检查
+#[get("/health")]
+pub async fn echo_health(db_pool: Data<DbPool>, redis_client: Data<redis::Client>) -> HttpResponse {
+    let result = web::block(move || {
+        let database = match db_pool.get() {
+            Ok(_) => true,
+            Err(e) => {
+                error!("health check: cannot get db connection from pool: {}", e);
+                false
+            }
+        };
+
+        let redis = match redis_client.get_connection() {
+            Ok(mut conn) => match redis::cmd("PING").query::<String>(&mut conn) {
+                Ok(_) => true,
+                Err(e) => {
+                    error!("health check: redis PING failed: {}", e);
+                    false
+                }
+            },
+            Err(e) => {
+                error!("health check: cannot get redis connection: {}", e);
+                false
+            }
+        };
+
+        HealthInfo { database, redis }
+    })
+    .await;
+
+    match result {
+        Ok(info) => {
+            let failed = info.failed_dependency();
+            if failed.is_empty() {
+                HttpResponse::Ok().json(APIResponse::ok(info))
+            } else {
+                HttpResponse::InternalServerError().json(APIResponse::new(
+                    500,
+                    format!(
+                        "Multiplayer Session Ended: cannot reach {}",
+                        failed.join(", ")
+                    ),
+                    Some(info),
+                ))
+            }
+        }
+        Err(e) => {
+            error!("health check: blocking error: {}", e);
+            HttpResponse::InternalServerError().json(APIResponse::<()>::internal_error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_url_trailing_slash_is_normalized() {
+        let with_slash = normalize_endpoint_url("http://host/api/").unwrap();
+        let without_slash = normalize_endpoint_url("http://host/api").unwrap();
+
+        assert_eq!(
+            format!("{}/mission/load_mission", with_slash),
+            format!("{}/mission/load_mission", without_slash)
+        );
+        assert_eq!(without_slash, "http://host/api");
+    }
+
+    #[test]
+    fn health_reports_failed_dependency() {
+        let info = HealthInfo {
+            database: true,
+            redis: false,
+        };
+        assert_eq!(info.failed_dependency(), vec!["redis"]);
+
+        let info = HealthInfo {
+            database: true,
+            redis: true,
+        };
+        assert!(info.failed_dependency().is_empty());
+    }
+
+    #[test]
+    fn only_issued_session_is_accepted() {
+        let app_state = AppState::for_test(Some("Rock and stone!".to_string()));
+
+        let session_id = app_state.new_session();
+
+        let request = actix_web::test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(
+                SESSION_COOKIE_NAME,
+                session_id,
+            ))
+            .to_http_request();
+        assert!(app_state.check_session(&request));
+
+        let request = actix_web::test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(
+                SESSION_COOKIE_NAME,
+                "Rock and stone!",
+            ))
+            .to_http_request();
+        assert!(!app_state.check_session(&request));
+
+        let request = actix_web::test::TestRequest::default().to_http_request();
+        assert!(!app_state.check_session(&request));
+    }
+
+    #[test]
+    fn expired_session_is_evicted() {
+        let mut app_state = AppState::for_test(Some("Rock and stone!".to_string()));
+        app_state.session_ttl = Duration::ZERO;
+
+        let session_id = app_state.new_session();
+
+        let request = actix_web::test::TestRequest::default()
+            .cookie(actix_web::cookie::Cookie::new(
+                SESSION_COOKIE_NAME,
+                session_id,
+            ))
+            .to_http_request();
+        assert!(!app_state.check_session(&request));
+        assert!(app_state.valid_session.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn login_is_locked_after_repeated_failure() {
+        let app_state = AppState::for_test(Some("Rock and stone!".to_string()));
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let other_ip: IpAddr = "192.0.2.2".parse().unwrap();
+
+        for _ in 0..LOGIN_MAX_FAILED_ATTEMPT - 1 {
+            assert_eq!(
+                app_state.check_login_attempt(ip, false),
+                LoginAttempt::Rejected
+            );
+        }
+        assert_eq!(
+            app_state.check_login_attempt(ip, true),
+            LoginAttempt::Accepted
+        );
+
+        for _ in 0..LOGIN_MAX_FAILED_ATTEMPT {
+            assert_eq!(
+                app_state.check_login_attempt(ip, false),
+                LoginAttempt::Rejected
+            );
+        }
+        assert_eq!(
+            app_state.check_login_attempt(ip, true),
+            LoginAttempt::Locked
+        );
+        assert_eq!(
+            app_state.check_login_attempt(other_ip, true),
+            LoginAttempt::Accepted
+        );
+
+        // 窗口结束后解除锁定，并清理过期的记录
+        app_state
+            .login_attempt
+            .lock()
+            .unwrap()
+            .get_mut(&ip)
+            .unwrap()
+            .1 = Instant::now() - LOGIN_ATTEMPT_WINDOW;
+        app_state.check_login_attempt(other_ip, false);
+        assert!(!app_state.login_attempt.lock().unwrap().contains_key(&ip));
+        assert_eq!(
+            app_state.check_login_attempt(ip, true),
+            LoginAttempt::Accepted
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_compares_content() {
+        assert!(constant_time_eq(b"Rock and stone!", b"Rock and stone!"));
+        assert!(!constant_time_eq(b"Rock and stone!", b"Rock and stone?"));
+        assert!(!constant_time_eq(b"Rock and stone!", b"Rock and stone"));
+        assert!(!constant_time_eq(b"Rock", b"Rock and stone!"));
+        assert!(!constant_time_eq(b"", b"Rock and stone!"));
+        assert!(!constant_time_eq(b"Rock and stone!", b""));
+        assert!(!constant_time_eq(b"Rock\0", b"Rock"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn unknown_hazard_id_is_rejected() {
+        assert_eq!(hazard_id_to_real(101), Some(3.5));
+        assert_eq!(hazard_id_to_real(0), None);
+        assert_eq!(hazard_id_to_real(108), None);
+
+        assert_eq!(
+            real_hazard_to_display_label(101).as_deref(),
+            Some("Deep Dive L2")
+        );
+        assert_eq!(
+            real_hazard_to_display_label(105).as_deref(),
+            Some("Elite Deep Dive L3")
+        );
+        assert_eq!(real_hazard_to_display_label(108), None);
+    }
+
+    #[test]
+    fn endpoint_url_without_scheme_is_rejected() {
+        assert!(normalize_endpoint_url("host/api").is_err());
+        assert!(normalize_endpoint_url("ftp://host/api").is_err());
+    }
+
+    #[test]
+    fn recent_window_keeps_minimum_prev_count() {
+        assert_eq!(RECENT_WINDOW.prev_count(5), 5);
+        assert_eq!(RECENT_WINDOW.prev_count(12), 10);
+        assert_eq!(RECENT_WINDOW.prev_count(25), 20);
+        assert_eq!(RECENT_WINDOW.recent_count(25), 5);
+        assert_eq!(RECENT_WINDOW.recent_count(0), 0);
+    }
+
+    #[test]
+    fn json_body_error_names_failure_stage() {
+        let syntax_error = api_parse_json_body::<Vec<i32>>(b"[1, 2").unwrap_err();
+        assert!(syntax_error.contains("not valid json"));
+
+        let schema_error = api_parse_json_body::<Vec<i32>>(b"[\"a\"]").unwrap_err();
+        assert!(schema_error.contains("does not match expected schema"));
+
+        assert_eq!(
+            api_parse_json_body::<Vec<i32>>(b"[1, 2]").unwrap(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn player_alias_merged_into_smallest_id() {
+        let player = |id, player_name: &str, friend| db::models::Player {
+            id,
+            player_name: player_name.to_string(),
+            friend,
+        };
+        let player_list = vec![
+            player(1, "new_name", false),
+            player(2, "other", false),
+            player(3, "old_name", true),
+        ];
+        let player_mapping = HashMap::from([("old_name".to_string(), "new_name".to_string())]);
+
+        let merged_player_info = MergedPlayerInfo::new(&player_list, &player_mapping);
+
+        assert_eq!(merged_player_info.player_id_to_merged_id[&3], 1);
+        assert_eq!(merged_player_info.player_id_to_merged_id[&2], 2);
+        assert_eq!(merged_player_info.player_id_to_name.len(), 2);
+        assert_eq!(merged_player_info.player_id_to_name[&1], "new_name");
+        // 别名在关注列表中时，合并后的玩家同样视为关注
+        assert_eq!(merged_player_info.watchlist_player_id_list, vec![1]);
+    }
+}
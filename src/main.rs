@@ -1,9 +1,10 @@
-use actix_web::middleware::Logger;
+use actix_web::middleware::{Compress, Condition, Logger};
 use actix_web::{web, App, HttpServer};
 use diesel::{Connection, PgConnection};
 use env_logger::Env;
 use log::{error, info, warn};
 use mission_backend_rs::cache;
+use mission_backend_rs::cache::{CacheTypeUpdateState, CacheUpdateState};
 use mission_backend_rs::damage;
 use mission_backend_rs::general;
 use mission_backend_rs::get_mapping;
@@ -14,12 +15,20 @@ use mission_backend_rs::mission;
 use mission_backend_rs::AppState;
 use mission_backend_rs::DbPool;
 use mission_backend_rs::Mapping;
-use mission_backend_rs::{admin, echo_heartbeat};
+use mission_backend_rs::ReadDbPool;
+use mission_backend_rs::DEFAULT_MAX_DECOMPRESSED_LENGTH;
+use mission_backend_rs::DEFAULT_SESSION_TTL;
+use mission_backend_rs::MAX_BODY_LENGTH;
+use mission_backend_rs::{admin, echo_health, echo_heartbeat, echo_ready};
+use mission_backend_rs::{RecentWindowInfo, RECENT_WINDOW};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
@@ -27,8 +36,6 @@ use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
-const MAX_BODY_LENGTH: usize = 64 * 1024 * 1024;
-
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     env_logger::init_from_env(Env::default().default_filter_or("info"));
@@ -36,12 +43,70 @@ async fn main() -> std::io::Result<()> {
     let database_url = read_file_env("DATABASE_URL").expect("cannot get database url");
     let redis_url = read_file_env("REDIS_URL").expect("cannot get redis url");
 
+    // 可选的只读副本，供只读接口使用；未设置时只读接口与写入共用同一连接池
+    let database_read_url = read_file_env("DATABASE_READ_URL");
+
     let access_token = read_file_env("ACCESS_TOKEN");
 
     if access_token.is_none() {
         warn!("cannot get access token, any token would be accepted, check ACCESS_TOKEN_FILE or ACCESS_TOKEN enviroment variable");
     }
 
+    // 按客户端Accept-Encoding压缩响应，资源受限时可通过DISABLE_COMPRESSION关闭
+    let enable_compression = !matches!(
+        read_file_env("DISABLE_COMPRESSION").as_deref(),
+        Some("1") | Some("true")
+    );
+
+    if !enable_compression {
+        info!("response compression disabled");
+    }
+
+    // 启动时在后台重新生成全部缓存，完成前/api/ready返回503
+    let warm_cache_on_start = matches!(
+        read_file_env("WARM_CACHE_ON_START").as_deref(),
+        Some("1") | Some("true")
+    );
+
+    // session的有效期（秒）
+    let session_ttl = match read_file_env("SESSION_TTL") {
+        Some(x) => match x.parse::<u64>() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(e) => {
+                warn!("cannot parse SESSION_TTL {}: {}, using default", x, e);
+                DEFAULT_SESSION_TTL
+            }
+        },
+        None => DEFAULT_SESSION_TTL,
+    };
+
+    // prev/recent统计的窗口，未设置时使用RECENT_WINDOW
+    let recent_window = RecentWindowInfo {
+        prev_percent: match read_usize_env("RECENT_WINDOW_PREV_PERCENT") {
+            Some(x) if x > 100 => {
+                warn!(
+                    "RECENT_WINDOW_PREV_PERCENT {} is greater than 100, using default",
+                    x
+                );
+                RECENT_WINDOW.prev_percent
+            }
+            Some(x) => x,
+            None => RECENT_WINDOW.prev_percent,
+        },
+        prev_min_count: read_usize_env("RECENT_WINDOW_PREV_MIN_COUNT")
+            .unwrap_or(RECENT_WINDOW.prev_min_count),
+    };
+
+    // 调试接口会暴露KPI计算的中间数据，默认关闭
+    let debug_endpoints = matches!(
+        read_file_env("DEBUG_ENDPOINTS").as_deref(),
+        Some("1") | Some("true")
+    );
+
+    // load_mission解压后数据的最大长度（字节）
+    let max_decompressed_length =
+        read_usize_env("MAX_DECOMPRESSED_LENGTH").unwrap_or(DEFAULT_MAX_DECOMPRESSED_LENGTH);
+
     let instance_dir = read_file_env("INSTANCE_DIR");
 
     let instance_dir = match instance_dir {
@@ -90,6 +155,20 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    let read_db_pool = match database_read_url {
+        Some(database_read_url) => {
+            info!("using read replica for read-only endpoints");
+            let manager = ConnectionManager::<PgConnection>::new(database_read_url);
+            match Pool::new(manager) {
+                Ok(x) => ReadDbPool(x),
+                Err(e) => {
+                    panic!("cannot build read replica database pool: {}", e);
+                }
+            }
+        }
+        None => ReadDbPool(db_pool.clone()),
+    };
+
     let redis_client = match redis::Client::open(redis_url) {
         Ok(x) => x,
         Err(e) => {
@@ -105,21 +184,40 @@ async fn main() -> std::io::Result<()> {
         instance_path: instance_dir.clone(),
         mapping: inner_mapping,
         kpi_config: inner_kpi_config,
+        cache_update: CacheUpdateState::default(),
+        cache_type_update: CacheTypeUpdateState::default(),
+        cache_ready: AtomicBool::new(!warm_cache_on_start),
+        valid_session: Mutex::new(HashMap::new()),
+        session_ttl,
+        login_attempt: Mutex::new(HashMap::new()),
+        recent_window,
+        debug_endpoints,
+        max_decompressed_length,
     });
     let db_pool = web::Data::new(db_pool);
+    let read_db_pool = web::Data::new(read_db_pool);
     let redis_client = web::Data::new(redis_client);
 
+    if warm_cache_on_start {
+        info!("warming cache on start");
+        cache::schedule_cache_update(app_state.clone(), db_pool.clone(), redis_client.clone());
+    }
+
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .wrap(Logger::new("%a %{User-Agent}i"))
+            .wrap(Condition::new(enable_compression, Compress::default()))
             .app_data(app_state.clone())
             .app_data(db_pool.clone())
+            .app_data(read_db_pool.clone())
             .app_data(redis_client.clone())
             .app_data(web::PayloadConfig::default().limit(MAX_BODY_LENGTH))
             .service(
                 web::scope("/api")
                     .service(echo_heartbeat)
+                    .service(echo_ready)
+                    .service(echo_health)
                     .service(get_mapping)
                     .service(web::scope("/mission").configure(mission::scoped_config))
                     .service(web::scope("/admin").configure(admin::scoped_config))
@@ -156,6 +254,20 @@ fn read_file_env(target_env: &str) -> Option<String> {
     return result;
 }
 
+fn read_usize_env(target_env: &str) -> Option<usize> {
+    let value = read_file_env(target_env)?;
+    match value.parse::<usize>() {
+        Ok(x) => Some(x),
+        Err(e) => {
+            warn!(
+                "cannot parse {} {}: {}, using default",
+                target_env, value, e
+            );
+            None
+        }
+    }
+}
+
 fn load_mapping(mapping_path: &Path) -> Mapping {
     info!("loading mapping from: {}", mapping_path.to_string_lossy());
     let file_content = match fs::read(mapping_path) {
@@ -0,0 +1,43 @@
+use crate::admin::delete_mission;
+use crate::{APIResponse, AppState, DbPool};
+use actix_web::{
+    post,
+    web::{self, Data, Json},
+    HttpRequest,
+};
+use log::error;
+
+#[post("/{mission_id}/delete")]
+pub async fn api_delete_mission(
+    requests: HttpRequest,
+    app_state: Data<AppState>,
+    db_pool: Data<DbPool>,
+    path: web::Path<i32>,
+) -> Json<APIResponse<()>> {
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
+    }
+
+    let mission_id = path.into_inner();
+
+    let result = web::block(move || {
+        let mut conn = match db_pool.get() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("cannot get db connection from pool: {}", e);
+                return Err(());
+            }
+        };
+
+        // 与批量删除不同，任务不存在时返回404
+        delete_mission::delete_mission(&mut conn, mission_id)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(true) => Json(APIResponse::ok(())),
+        Ok(false) => Json(APIResponse::not_found()),
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
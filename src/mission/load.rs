@@ -1,99 +1,308 @@
 use actix_web::{
+    http::header::CONTENT_ENCODING,
     post,
     web::{self, Buf, Bytes, Data, Json},
     HttpRequest,
 };
 
+use crate::cache::write_mission_raw_cache_for;
 use crate::db::{mission_log::*, models::*, schema::*};
-use crate::INVALID_MISSION_TIME_THRESHOLD;
 use crate::{db, DbPool};
-use crate::{APIResponse, AppState};
+use crate::{is_ordered_weapon, INVALID_MISSION_TIME_THRESHOLD, MAX_BODY_LENGTH, WEAPON_ORDER};
+use crate::{APIResponse, AppState, Mapping};
 use diesel::prelude::*;
 use log::{error, info, warn};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
 use std::time::{Duration, Instant};
-use std::{collections::HashMap, io::Read};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct LoadResult {
     pub load_count: i32,
+    // 已存在（begin_timestamp相同）而跳过的任务数，重复上传同一批次时不会重复写入
+    pub skip_count: i32,
     pub decode_time: String,
     pub load_time: String,
+    // 不影响加载结果的警告，如上传数据中存在未在WEAPON_ORDER中定义的武器
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+// 上传数据的压缩方式，由请求头Content-Encoding指定
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PayloadEncoding {
+    Zstd,
+    Gzip,
+    Identity,
+}
+
+impl PayloadEncoding {
+    // 未设置Content-Encoding时按zstd处理，兼容不设置该请求头的旧版客户端
+    fn from_header(content_encoding: Option<&str>) -> Result<Self, String> {
+        match content_encoding
+            .map(|x| x.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("zstd") => Ok(PayloadEncoding::Zstd),
+            Some("gzip") | Some("x-gzip") => Ok(PayloadEncoding::Gzip),
+            Some("identity") | Some("") => Ok(PayloadEncoding::Identity),
+            Some(x) => Err(format!(
+                "unsupported Content-Encoding: {}, expected zstd, gzip or identity",
+                x
+            )),
+        }
+    }
 }
 
+impl fmt::Display for PayloadEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadEncoding::Zstd => write!(f, "zstd"),
+            PayloadEncoding::Gzip => write!(f, "gzip"),
+            PayloadEncoding::Identity => write!(f, "identity"),
+        }
+    }
+}
+
+// 使用原始的Payload而不是Bytes：Bytes会按Content-Encoding自动解压，这里需要自行解压并统计解压耗时
 #[post("/load_mission")]
 pub async fn load_mission(
     requests: HttpRequest,
-    raw_body: Bytes,
+    payload: web::Payload,
     app_state: Data<AppState>,
     db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<LoadResult>> {
-    if let Some(access_token) = app_state.access_token.clone() {
-        if let Some(provieded_access_token) = requests.cookie("access_token") {
-            if provieded_access_token.value() != access_token {
-                return Json(APIResponse::unauthorized());
-            }
-        } else {
-            return Json(APIResponse::unauthorized());
-        }
+    if !app_state.check_session(&requests) {
+        return Json(APIResponse::unauthorized());
     }
 
-    let decode_result = web::block(|| decompress_zstd_payload(raw_body))
-        .await
-        .unwrap();
+    let content_encoding = match requests.headers().get(CONTENT_ENCODING) {
+        Some(x) => match x.to_str() {
+            Ok(x) => Some(x),
+            Err(_) => {
+                return Json(APIResponse::bad_request("invalid Content-Encoding header"));
+            }
+        },
+        None => None,
+    };
 
-    let (decode_time, decompressed) = match decode_result {
+    let encoding = match PayloadEncoding::from_header(content_encoding) {
         Ok(x) => x,
+        Err(message) => {
+            warn!("{}", message);
+            return Json(APIResponse::bad_request(&message));
+        }
+    };
+
+    let raw_body = match payload.to_bytes_limited(MAX_BODY_LENGTH).await {
+        Ok(Ok(x)) => x,
+        Ok(Err(e)) => {
+            warn!("failed to read the payload: {}", e);
+            return Json(APIResponse::bad_request(&format!(
+                "failed to read the payload: {}",
+                e
+            )));
+        }
         Err(e) => {
+            return Json(APIResponse::bad_request(&format!(
+                "payload too large: {}",
+                e
+            )));
+        }
+    };
+
+    let max_decompressed_length = app_state.max_decompressed_length;
+
+    let decode_result = web::block(move || {
+        decode_payload::<Vec<LogContent>>(encoding, raw_body, max_decompressed_length)
+    })
+    .await
+    .unwrap();
+
+    let (decode_time, mission_list) = match decode_result {
+        Ok(x) => x,
+        Err(PayloadDecodeError::TooLarge) => {
+            warn!(
+                "decompressed payload from {} exceeds {} bytes, rejected",
+                requests
+                    .peer_addr()
+                    .map(|x| x.to_string())
+                    .unwrap_or("unknown".to_string()),
+                max_decompressed_length
+            );
+            return Json(APIResponse::bad_request(&format!(
+                "decompressed payload exceeds {} bytes",
+                max_decompressed_length
+            )));
+        }
+        Err(PayloadDecodeError::Decompress(e)) => {
             warn!("failed to decompress the payload: {}", e);
-            return Json(APIResponse::bad_request("failed to decompress the payload"));
+            return Json(APIResponse::bad_request(&format!(
+                "failed to decompress the payload ({}): {}",
+                encoding, e
+            )));
+        }
+        Err(PayloadDecodeError::Msgpack(e)) => {
+            let message = describe_msgpack_error(&e);
+            warn!("failed to decode the payload: {}", message);
+            return Json(APIResponse::bad_request(&message));
         }
     };
 
-    match rmp_serde::from_read::<_, Vec<LogContent>>(&decompressed[..]) {
-        Ok(mission_list) => {
-            match web::block(|| load_mission_db(db_pool, mission_list))
-                .await
-                .unwrap()
-            {
-                Ok((load_time, load_count)) => {
-                    let response_data = LoadResult {
-                        load_count,
-                        load_time: format!("{:?}", load_time),
-                        decode_time: format!("{:?}", decode_time),
-                    };
-
-                    return Json(APIResponse::ok(response_data));
-                }
-                Err(()) => {
-                    return Json(APIResponse::internal_error());
-                }
-            }
+    let mapping = app_state.mapping.lock().unwrap().clone();
+
+    match web::block(move || load_mission_db(db_pool, redis_client, &mapping, mission_list))
+        .await
+        .unwrap()
+    {
+        Ok((load_time, load_count, skip_count, warnings)) => {
+            let response_data = LoadResult {
+                load_count,
+                skip_count,
+                load_time: format!("{:?}", load_time),
+                decode_time: format!("{:?}", decode_time),
+                warnings,
+            };
+
+            Json(APIResponse::ok(response_data))
         }
-        Err(e) => {
-            warn!("failed to decode the payload: {}", e);
-            return Json(APIResponse::bad_request("failed to decode the payload"));
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 区分payload本身不是合法的msgpack与msgpack数据结构不符
+fn describe_msgpack_error(e: &rmp_serde::decode::Error) -> String {
+    use rmp_serde::decode::Error;
+    match e {
+        Error::InvalidMarkerRead(_)
+        | Error::InvalidDataRead(_)
+        | Error::Utf8Error(_)
+        | Error::DepthLimitExceeded => {
+            format!("decompressed payload is not valid msgpack: {}", e)
         }
+        _ => format!("payload msgpack does not match expected schema: {}", e),
     }
 }
 
-fn decompress_zstd_payload(data: Bytes) -> Result<(Duration, Vec<u8>), std::io::Error> {
+enum PayloadDecodeError {
+    // 解压后的数据超过max_decompressed_length
+    TooLarge,
+    Decompress(std::io::Error),
+    Msgpack(rmp_serde::decode::Error),
+}
+
+// 限制解压后读取的总字节数，并记录解压过程中的错误，以便与msgpack格式错误区分
+struct LimitedReader<R: Read> {
+    inner: R,
+    remaining: usize,
+    error: Option<PayloadDecodeError>,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // 已达到上限时再尝试读取一个字节，仍有数据则说明超出上限
+        let max_read = match self.remaining {
+            0 => 1,
+            x => x.min(buf.len()),
+        };
+
+        let read_count = match self.inner.read(&mut buf[..max_read]) {
+            Ok(x) => x,
+            Err(e) => {
+                let message = e.to_string();
+                self.error = Some(PayloadDecodeError::Decompress(e));
+                return Err(std::io::Error::other(message));
+            }
+        };
+
+        if self.remaining == 0 && read_count > 0 {
+            self.error = Some(PayloadDecodeError::TooLarge);
+            return Err(std::io::Error::other("decompressed payload too large"));
+        }
+
+        self.remaining -= read_count;
+        Ok(read_count)
+    }
+}
+
+// 边解压边反序列化，不保留完整的解压数据，解压后超过max_decompressed_length字节时停止
+fn decode_payload<T: DeserializeOwned>(
+    encoding: PayloadEncoding,
+    data: Bytes,
+    max_decompressed_length: usize,
+) -> Result<(Duration, T), PayloadDecodeError> {
     let begin = Instant::now();
-    let mut decoder = zstd::Decoder::new(data.reader()).unwrap();
-    let mut decompressed = Vec::new();
 
-    let decode_result = decoder.read_to_end(&mut decompressed);
+    let decoder: Box<dyn Read> = match encoding {
+        PayloadEncoding::Zstd => {
+            Box::new(zstd::Decoder::new(data.reader()).map_err(PayloadDecodeError::Decompress)?)
+        }
+        PayloadEncoding::Gzip => Box::new(flate2::read::GzDecoder::new(data.reader())),
+        PayloadEncoding::Identity => Box::new(data.reader()),
+    };
+
+    let mut reader = LimitedReader {
+        inner: decoder,
+        remaining: max_decompressed_length,
+        error: None,
+    };
+
+    match rmp_serde::from_read::<_, T>(&mut reader) {
+        Ok(x) => Ok((begin.elapsed(), x)),
+        Err(e) => Err(reader
+            .error
+            .take()
+            .unwrap_or(PayloadDecodeError::Msgpack(e))),
+    }
+}
+
+// 玩家使用的、未在WEAPON_ORDER中定义的武器，按weapon_game_id排序
+fn find_unknown_weapon<'a>(
+    damage_info_iter: impl Iterator<Item = &'a LogDamageInfo>,
+    weapon_combine: &HashMap<String, String>,
+) -> Vec<String> {
+    let weapon_order = WEAPON_ORDER;
+
+    let mut unknown_weapon_set = HashSet::new();
+
+    for damage_info in damage_info_iter {
+        // 仅检查玩家造成的伤害，其余来源（敌人、环境等）不是武器
+        if damage_info.causer_type != 1 {
+            continue;
+        }
+
+        let weapon_game_id = weapon_combine
+            .get(&damage_info.weapon)
+            .unwrap_or(&damage_info.weapon);
 
-    match decode_result {
-        Ok(_) => Ok((begin.elapsed(), decompressed)),
-        Err(e) => Err(e),
+        if !is_ordered_weapon(weapon_game_id) || weapon_order.contains_key(weapon_game_id.as_str())
+        {
+            continue;
+        }
+
+        unknown_weapon_set.insert(weapon_game_id.clone());
     }
+
+    let mut result = unknown_weapon_set.into_iter().collect::<Vec<_>>();
+    result.sort_unstable();
+    result
 }
 
 fn load_mission_db(
     db_pool: Data<DbPool>,
+    redis_client: Data<redis::Client>,
+    mapping: &Mapping,
     log_list: Vec<LogContent>,
-) -> Result<(Duration, i32), ()> {
+) -> Result<(Duration, i32, i32, Vec<String>), ()> {
     let begin = Instant::now();
     let mut conn = match db_pool.get() {
         Ok(conn) => conn,
@@ -103,23 +312,93 @@ fn load_mission_db(
         }
     };
 
-    let load_count = log_list.len() as i32;
+    let upload_timestamp_list = log_list
+        .iter()
+        .map(|log| log.mission_info.begin_timestamp)
+        .collect::<Vec<_>>();
+
+    let mut loaded_timestamp_set = match mission::table
+        .select(mission::begin_timestamp)
+        .filter(mission::begin_timestamp.eq_any(&upload_timestamp_list))
+        .load::<i64>(&mut conn)
+    {
+        Ok(x) => x.into_iter().collect::<HashSet<_>>(),
+        Err(e) => {
+            error!("cannot get loaded mission timestamp: {}", e);
+            return Err(());
+        }
+    };
+
+    let unknown_weapon_list = find_unknown_weapon(
+        log_list.iter().flat_map(|log| log.damage_info.iter()),
+        &mapping.weapon_combine,
+    );
+
+    let mut loaded_mission_id_list = Vec::new();
+    let mut skip_count = 0;
 
     for log in log_list {
         let current_mission_timestamp = log.mission_info.begin_timestamp;
+        // 以begin_timestamp判断任务是否已加载，使分批上传中断后可安全重传
+        if !loaded_timestamp_set.insert(current_mission_timestamp) {
+            info!("skipping loaded mission: {}", current_mission_timestamp);
+            skip_count += 1;
+            continue;
+        }
         info!("loading mission: {}", current_mission_timestamp);
-        if let Err(e) = db::mission::load_mission(log, &mut conn) {
-            error!(
-                "db error while loading mission {}: {}",
-                current_mission_timestamp, e
-            );
-            return Err(());
+        match db::mission::load_mission(log, &mut conn) {
+            Ok(mission_id) => loaded_mission_id_list.push(mission_id),
+            Err(e) => {
+                error!(
+                    "db error while loading mission {}: {}",
+                    current_mission_timestamp, e
+                );
+                return Err(());
+            }
         }
     }
 
     mark_invalid_mission(db_pool)?;
 
-    Ok((begin.elapsed(), load_count))
+    let mut warnings = Vec::new();
+
+    if !unknown_weapon_list.is_empty() {
+        warn!(
+            "weapons not in WEAPON_ORDER: {}",
+            unknown_weapon_list.join(", ")
+        );
+        warnings.extend(
+            unknown_weapon_list
+                .into_iter()
+                .map(|x| format!("weapon {} is not in WEAPON_ORDER/WEAPON_TYPE", x)),
+        );
+    }
+
+    // 仅为新加载的任务生成mission_raw缓存；失败时不影响加载结果，读取时会按需重新生成
+    match redis_client.get_connection() {
+        Ok(mut redis_conn) => {
+            if write_mission_raw_cache_for(
+                &mut conn,
+                &mut redis_conn,
+                mapping,
+                &loaded_mission_id_list,
+            )
+            .is_err()
+            {
+                warn!("cannot update mission raw cache for loaded missions");
+            }
+        }
+        Err(e) => {
+            warn!("cannot get redis connection: {}", e);
+        }
+    }
+
+    Ok((
+        begin.elapsed(),
+        loaded_mission_id_list.len() as i32,
+        skip_count,
+        warnings,
+    ))
 }
 
 fn mark_invalid_mission(db_pool: Data<DbPool>) -> Result<(), ()> {
@@ -171,21 +450,104 @@ fn mark_invalid_mission(db_pool: Data<DbPool>) -> Result<(), ()> {
         }
     }
 
-    for (mission_id, reason) in inavlid_mission_id_to_reason {
-        if let Err(e) = diesel::insert_into(mission_invalid::table)
-            .values((
-                mission_invalid::mission_id.eq(mission_id),
-                mission_invalid::reason.eq(reason),
-            ))
-            .on_conflict(mission_invalid::mission_id)
-            .do_update()
-            .set(mission_invalid::reason.eq(reason))
-            .execute(&mut conn)
-        {
-            error!("cannot insert into invalid mission: {}", e);
-            return Err(());
+    // 全部写入或全部不写入，避免部分任务被标记
+    let result = conn.transaction(|conn| {
+        for (mission_id, reason) in inavlid_mission_id_to_reason {
+            diesel::insert_into(mission_invalid::table)
+                .values((
+                    mission_invalid::mission_id.eq(mission_id),
+                    mission_invalid::reason.eq(reason),
+                ))
+                .on_conflict(mission_invalid::mission_id)
+                .do_update()
+                .set(mission_invalid::reason.eq(reason))
+                .execute(conn)?;
         }
+
+        QueryResult::Ok(())
+    });
+
+    if let Err(e) = result {
+        error!("cannot insert into invalid mission: {}", e);
+        return Err(());
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damage_info(weapon: &str, causer_type: i16) -> LogDamageInfo {
+        LogDamageInfo {
+            mission_time: 0,
+            damage: 1.0,
+            taker: "ED_Spider_Grunt".to_string(),
+            causer: "player".to_string(),
+            weapon: weapon.to_string(),
+            causer_type,
+            taker_type: 2,
+        }
+    }
+
+    #[test]
+    fn unknown_player_weapon_is_reported_once() {
+        let damage_info_list = vec![
+            damage_info("WPN_Pistol_A", 1),
+            damage_info("WPN_NewWeapon", 1),
+            damage_info("WPN_NewWeapon", 1),
+            damage_info("WPN_DoubleDrills", 1),
+            damage_info("Grenade_Axe", 1),
+            damage_info("WPN_EnemyWeapon", 2),
+            damage_info("WPN_Combined", 1),
+        ];
+        let weapon_combine =
+            HashMap::from([("WPN_Combined".to_string(), "WPN_Pistol_A".to_string())]);
+
+        assert_eq!(
+            find_unknown_weapon(damage_info_list.iter(), &weapon_combine),
+            vec!["WPN_NewWeapon".to_string()]
+        );
+    }
+
+    #[test]
+    fn payload_is_decompressed_by_content_encoding() {
+        use std::io::Write;
+
+        let data = rmp_serde::to_vec(&"Rock and stone!").unwrap();
+
+        let zstd_data = zstd::encode_all(&data[..], 3).unwrap();
+        let mut gzip_encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip_encoder.write_all(&data).unwrap();
+        let gzip_data = gzip_encoder.finish().unwrap();
+
+        for (content_encoding, payload) in [
+            (None, zstd_data.clone()),
+            (Some("zstd"), zstd_data),
+            (Some("GZIP"), gzip_data),
+            (Some("identity"), data.clone()),
+        ] {
+            let encoding = PayloadEncoding::from_header(content_encoding).unwrap();
+            let (_, decoded) = decode_payload::<String>(encoding, Bytes::from(payload), data.len())
+                .ok()
+                .unwrap();
+            assert_eq!(decoded, "Rock and stone!");
+        }
+
+        assert!(PayloadEncoding::from_header(Some("br")).is_err());
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected() {
+        let data = rmp_serde::to_vec(&vec![0u8; 4096]).unwrap();
+        let zstd_data = zstd::encode_all(&data[..], 3).unwrap();
+
+        let result = decode_payload::<Vec<u8>>(PayloadEncoding::Zstd, Bytes::from(zstd_data), 1024);
+        assert!(matches!(result, Err(PayloadDecodeError::TooLarge)));
+
+        let result = decode_payload::<Vec<u8>>(PayloadEncoding::Zstd, Bytes::from(data), 1024);
+        assert!(matches!(result, Err(PayloadDecodeError::Decompress(_))));
+    }
+}
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::{
-    MissionDamageInfo, MissionGeneralData, MissionGeneralInfo, MissionGeneralPlayerInfo,
-    MissionKPIComponent, MissionKPIInfo, MissionResourceInfo, MissionWeaponDamageInfo,
-    PlayerDamageInfo, PlayerFriendlyFireInfo, PlayerResourceData,
+    DPSBase, MissionCharacterWeaponDamageInfo, MissionDamageInfo, MissionGeneralData,
+    MissionGeneralInfo, MissionGeneralPlayerInfo, MissionKPIComponent, MissionKPIInfo,
+    MissionResourceInfo, MissionWeaponDamageInfo, MissionWeaponDamageQuery, PlayerDamageInfo,
+    PlayerFriendlyFireInfo, PlayerResourceData,
 };
 use crate::cache::kpi::CachedGlobalKPIState;
 use crate::cache::mission::{MissionCachedInfo, MissionKPICachedInfo};
@@ -12,13 +13,15 @@ use crate::kpi::{KPIComponent, KPIConfig};
 use crate::{CORRECTION_ITEMS, NITRA_GAME_ID};
 
 use crate::db::schema::*;
-use crate::{APIResponse, AppState, DbPool};
+use crate::{canonical_player_id_to_name, APIResponse, AppState, ReadDbPool};
 use actix_web::{
     get,
     web::{self, Data, Json},
+    HttpResponse,
 };
 use diesel::prelude::*;
-use log::{debug, error};
+use log::{debug, error, warn};
+use serde::Serialize;
 use std::time::Instant;
 
 fn generate_mission_general_info(
@@ -80,6 +83,7 @@ fn generate_mission_general(
     player_id_to_name: &HashMap<i16, String>,
     character_id_to_game_id: &HashMap<i16, String>,
     mission_type_id_to_game_id: &HashMap<i16, String>,
+    weapon_game_id_to_character_game_id: &HashMap<String, String>,
     mission_id: i32,
 ) -> Option<MissionGeneralData> {
     let target_mission = cached_mission_list
@@ -108,6 +112,37 @@ fn generate_mission_general(
         );
     }
 
+    let mut class_mismatch = HashMap::new();
+
+    for player_info in &target_mission.player_info {
+        let player_weapon_set = match target_mission.player_weapon_set.get(&player_info.player_id) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let character_game_id = character_id_to_game_id
+            .get(&player_info.character_id)
+            .unwrap();
+
+        // 未在mapping中登记所属角色的武器无法判断，跳过
+        let mut mismatch_weapon_list = player_weapon_set
+            .iter()
+            .filter(|weapon_game_id| {
+                match weapon_game_id_to_character_game_id.get(*weapon_game_id) {
+                    Some(weapon_character) => weapon_character != character_game_id,
+                    None => false,
+                }
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !mismatch_weapon_list.is_empty() {
+            mismatch_weapon_list.sort_unstable();
+            let player_name = player_id_to_name.get(&player_info.player_id).unwrap();
+            class_mismatch.insert(player_name.clone(), mismatch_weapon_list);
+        }
+    }
+
     let mission_type_game_id = mission_type_id_to_game_id
         .get(&target_mission.mission_info.mission_type_id)
         .unwrap();
@@ -117,7 +152,7 @@ fn generate_mission_general(
         .values()
         .map(|player_damage_data| player_damage_data.values())
         .flatten()
-        .filter(|pack| pack.taker_type != 1)
+        .filter(|pack| !pack.is_friendly_fire())
         .map(|pack| pack.total_amount)
         .sum::<f64>();
 
@@ -163,6 +198,7 @@ fn generate_mission_general(
         total_minerals,
         total_nitra,
         total_supply_count,
+        class_mismatch,
     })
 }
 
@@ -189,7 +225,7 @@ fn generate_mission_damage(
         let causer_player_name = player_id_to_name.get(causer_player_id).unwrap();
 
         for (taker_game_id, pack) in player_damage_map {
-            if pack.taker_type != 1 {
+            if !pack.is_friendly_fire() {
                 continue;
             }
 
@@ -197,13 +233,17 @@ fn generate_mission_damage(
                 continue;
             }
 
+            let taker_player_name = player_id_to_name
+                .get(&pack.taker_id)
+                .unwrap_or(taker_game_id);
+
             ff_causer_taker_map
                 .entry(causer_player_name)
                 .or_insert_with(HashMap::new)
-                .insert(taker_game_id, pack.total_amount);
+                .insert(taker_player_name, pack.total_amount);
 
             ff_taker_causer_map
-                .entry(taker_game_id)
+                .entry(taker_player_name)
                 .or_insert_with(HashMap::new)
                 .insert(causer_player_name, pack.total_amount);
         }
@@ -219,7 +259,7 @@ fn generate_mission_damage(
             .iter()
             .map(|x| x.iter())
             .flatten()
-            .filter(|(_, pack)| pack.taker_type != 1)
+            .filter(|(_, pack)| !pack.is_friendly_fire())
             .map(|(k, v)| (k.clone(), v.total_amount))
             .collect::<HashMap<_, _>>();
 
@@ -281,6 +321,8 @@ fn generate_mission_weapon_damage(
     weapon_game_id_to_character_game_id: &HashMap<String, String>,
     weapon_game_id_to_name: &HashMap<String, String>,
     mission_id: i32,
+    dps_base: DPSBase,
+    min_damage: f64,
 ) -> Option<HashMap<String, MissionWeaponDamageInfo>> {
     let target_mission = cached_mission_list
         .iter()
@@ -292,17 +334,21 @@ fn generate_mission_weapon_damage(
         let damage = weapon_pack
             .detail
             .values()
-            .filter(|pack| pack.taker_type != 1)
+            .filter(|pack| !pack.is_friendly_fire())
             .map(|pack| pack.total_amount)
             .sum::<f64>();
 
         let friendly_fire = weapon_pack
             .detail
             .values()
-            .filter(|pack| pack.taker_type == 1)
+            .filter(|pack| pack.is_friendly_fire())
             .map(|pack| pack.total_amount)
             .sum::<f64>();
 
+        if damage + friendly_fire < min_damage {
+            continue;
+        }
+
         let character_game_id = weapon_game_id_to_character_game_id
             .get(weapon_game_id)
             .map(|inner| inner.clone())
@@ -313,6 +359,17 @@ fn generate_mission_weapon_damage(
             .unwrap_or(weapon_game_id)
             .clone();
 
+        // 武器只造成过一次伤害时活跃时间为0，此时退回使用任务时间
+        let dps_time = match dps_base {
+            DPSBase::Span if weapon_pack.active_span > 0 => weapon_pack.active_span,
+            _ => target_mission.mission_info.mission_time,
+        };
+
+        let dps = match dps_time {
+            x if x > 0 => damage / x as f64,
+            _ => 0.0,
+        };
+
         result.insert(
             weapon_game_id.clone(),
             MissionWeaponDamageInfo {
@@ -320,6 +377,7 @@ fn generate_mission_weapon_damage(
                 friendly_fire,
                 character_game_id,
                 mapped_name,
+                dps,
             },
         );
     }
@@ -327,6 +385,32 @@ fn generate_mission_weapon_damage(
     Some(result)
 }
 
+// 按武器所属角色汇总任务武器伤害
+fn generate_mission_character_weapon_damage(
+    weapon_damage: HashMap<String, MissionWeaponDamageInfo>,
+    character_game_id_to_name: &HashMap<String, String>,
+) -> HashMap<String, MissionCharacterWeaponDamageInfo> {
+    let mut result: HashMap<String, MissionCharacterWeaponDamageInfo> = HashMap::new();
+
+    for weapon_info in weapon_damage.into_values() {
+        let entry = result
+            .entry(weapon_info.character_game_id)
+            .or_insert_with_key(|character_game_id| MissionCharacterWeaponDamageInfo {
+                damage: 0.0,
+                friendly_fire: 0.0,
+                mapped_name: character_game_id_to_name
+                    .get(character_game_id)
+                    .unwrap_or(character_game_id)
+                    .clone(),
+            });
+
+        entry.damage += weapon_info.damage;
+        entry.friendly_fire += weapon_info.friendly_fire;
+    }
+
+    result
+}
+
 fn generate_mission_resource(
     cached_mission_list: &[MissionCachedInfo],
     player_id_to_name: &HashMap<i16, String>,
@@ -380,30 +464,59 @@ pub fn generate_mission_kpi(
     let mut mission_correction_factor_sum = HashMap::new();
     let mut mission_correction_factor = HashMap::new();
 
+    // 全局状态中可能缺少本任务中出现的角色类型（如该角色类型的任务均被标记为无效）
+    let mut uncorrected_character_type_set = HashSet::new();
+
+    // 按player_id顺序累加，避免HashMap迭代顺序导致浮点求和结果不稳定
+    let mut player_character_type_list = mission_kpi_cached_info
+        .player_id_to_kpi_character
+        .iter()
+        .collect::<Vec<_>>();
+    player_character_type_list.sort_unstable_by_key(|(player_id, _)| **player_id);
+
+    // 缺少任一玩家的修正因子时，该项的修正因子之和不完整，整局该项均不修正，避免改变其他玩家的修正结果
+    let mut incomplete_component_set = HashSet::new();
+
     for &kpi_component in CORRECTION_ITEMS {
-        for character_type in mission_kpi_cached_info.player_id_to_kpi_character.values() {
-            let correction_factor = global_kpi_state
+        for (_, character_type) in &player_character_type_list {
+            match global_kpi_state
                 .character_correction_factor
                 .get(character_type)
-                .unwrap()
-                .get(&kpi_component)
-                .map(|x| x.correction_factor)
-                .unwrap();
-
-            *mission_correction_factor_sum
-                .entry(kpi_component)
-                .or_insert(0.0) += correction_factor;
+                .and_then(|x| x.get(&kpi_component))
+            {
+                Some(x) => {
+                    *mission_correction_factor_sum
+                        .entry(kpi_component)
+                        .or_insert(0.0) += x.correction_factor;
+                }
+                None => {
+                    uncorrected_character_type_set.insert(*character_type);
+                    incomplete_component_set.insert(kpi_component);
+                }
+            }
         }
     }
 
-    for &kpi_component in CORRECTION_ITEMS {
-        mission_correction_factor.insert(
-            kpi_component,
-            mission_correction_factor_sum[&kpi_component]
-                / global_kpi_state.standard_correction_sum[&kpi_component],
+    for character_type in &uncorrected_character_type_set {
+        warn!(
+            "mission {}: character type {} is missing in global kpi state, using uncorrected index for all players of this mission",
+            mission_kpi_cached_info.mission_id, character_type
         );
     }
 
+    for &kpi_component in CORRECTION_ITEMS {
+        if incomplete_component_set.contains(&kpi_component) {
+            continue;
+        }
+
+        if let (Some(factor_sum), Some(standard_sum)) = (
+            mission_correction_factor_sum.get(&kpi_component),
+            global_kpi_state.standard_correction_sum.get(&kpi_component),
+        ) {
+            mission_correction_factor.insert(kpi_component, factor_sum / standard_sum);
+        }
+    }
+
     for (player_id, raw_kpi_data) in &mission_kpi_cached_info.raw_kpi_data {
         let player_name = player_id_to_name.get(&player_id).unwrap().clone();
 
@@ -454,25 +567,24 @@ pub fn generate_mission_kpi(
         let mut player_mission_kpi_weighted_sum = 0.0;
         let mut player_mission_kpi_max_sum = 0.0;
 
-        let mut component_name_to_component = HashMap::new();
+        // 按KPIComponent id顺序生成，同时保证输出顺序与加权求和顺序固定
+        let mut raw_kpi_data_list = raw_kpi_data.iter().collect::<Vec<_>>();
+        raw_kpi_data_list.sort_unstable_by_key(|(&kpi_component, _)| i16::from(kpi_component));
 
-        for (kpi_component, kpi_data) in raw_kpi_data {
+        for (kpi_component, kpi_data) in raw_kpi_data_list {
             let component_name = kpi_component.to_string_zh();
 
-            component_name_to_component.insert(component_name.clone(), kpi_component);
-
             let corrected_index = match mission_correction_factor.get(&kpi_component) {
-                Some(factor) => (kpi_data.raw_index * factor).min(1.0),
+                Some(factor) => kpi_component.clamp_index(kpi_data.raw_index * factor),
                 None => kpi_data.raw_index,
             };
 
             let transformed_index = match global_kpi_state
                 .transform_range
                 .get(kpi_character_type)
-                .unwrap()
-                .get(&kpi_component)
+                .and_then(|x| x.get(&kpi_component))
             {
-                Some(range_info) => {
+                Some(range_info) if !range_info.is_empty() => {
                     let mut range_index = 0;
 
                     for i in 0..range_info.len() {
@@ -488,7 +600,7 @@ pub fn generate_mission_kpi(
                     corrected_index * transform_range.transform_cofficient.0
                         + transform_range.transform_cofficient.1
                 }
-                None => corrected_index,
+                _ => corrected_index,
             };
 
             let current_weight =
@@ -509,13 +621,6 @@ pub fn generate_mission_kpi(
             player_mission_kpi_max_sum += kpi_component.max_value() * current_weight;
         }
 
-        player_kpi_component_list.sort_unstable_by(|a, b| {
-            let a_index: i16 = (**component_name_to_component.get(&a.name).unwrap()).into();
-            let b_index: i16 = (**component_name_to_component.get(&b.name).unwrap()).into();
-
-            a_index.cmp(&b_index)
-        });
-
         result.push(MissionKPIInfo {
             player_name,
             kpi_character_type: kpi_character_type.to_string(),
@@ -533,13 +638,24 @@ pub fn generate_mission_kpi(
         });
     }
 
+    sort_mission_kpi_info(&mut result);
+
     result
 }
 
+// 按mission_kpi降序排列，相同时按player_name升序，保证输出顺序稳定
+fn sort_mission_kpi_info(mission_kpi_info_list: &mut [MissionKPIInfo]) {
+    mission_kpi_info_list.sort_unstable_by(|a, b| {
+        b.mission_kpi
+            .total_cmp(&a.mission_kpi)
+            .then_with(|| a.player_name.cmp(&b.player_name))
+    });
+}
+
 #[get("/{mission_id}/info")]
 async fn get_general_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<MissionGeneralInfo>> {
@@ -621,7 +737,7 @@ async fn get_general_info(
 #[get("/{mission_id}/basic")]
 async fn get_player_character(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<HashMap<String, String>>> {
@@ -631,6 +747,7 @@ async fn get_player_character(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
 
     drop(mapping);
 
@@ -675,10 +792,7 @@ async fn get_player_character(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -728,7 +842,7 @@ async fn get_player_character(
 #[get("/{mission_id}/general")]
 async fn get_mission_general(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<MissionGeneralData>> {
@@ -738,6 +852,8 @@ async fn get_mission_general(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
+    let weapon_character = mapping.weapon_character.clone();
 
     drop(mapping);
 
@@ -782,10 +898,7 @@ async fn get_mission_general(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         let character_list = match character::table
             .select(Character::as_select())
@@ -827,6 +940,7 @@ async fn get_mission_general(
             &player_id_to_name,
             &character_id_to_game_id,
             &mission_type_id_to_game_id,
+            &weapon_character,
             mission_id,
         );
 
@@ -849,7 +963,7 @@ async fn get_mission_general(
 #[get("/{mission_id}/damage")]
 async fn get_mission_damage(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<MissionDamageInfo>> {
@@ -859,6 +973,7 @@ async fn get_mission_damage(
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
     let entity_game_id_to_name = mapping.entity_mapping.clone();
 
     drop(mapping);
@@ -904,10 +1019,7 @@ async fn get_mission_damage(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
@@ -938,11 +1050,14 @@ async fn get_mission_damage(
 #[get("/{mission_id}/weapon")]
 async fn get_mission_weapon_damage(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
+    query: web::Query<MissionWeaponDamageQuery>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<HashMap<String, MissionWeaponDamageInfo>>> {
     let mission_id = path.into_inner();
+    let dps_base = query.dps_base;
+    let min_damage = query.min_damage;
     let mapping = app_state.mapping.lock().unwrap();
 
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
@@ -994,6 +1109,8 @@ async fn get_mission_weapon_damage(
             &weapon_game_id_to_character_game_id,
             &weapon_game_id_to_name,
             mission_id,
+            dps_base,
+            min_damage,
         );
 
         debug!("mission weapon damage generated in {:?}", begin.elapsed());
@@ -1012,22 +1129,33 @@ async fn get_mission_weapon_damage(
     }
 }
 
-#[get("/{mission_id}/resource")]
-async fn get_mission_resource_info(
+#[get("/{mission_id}/weapon_by_character")]
+async fn get_mission_weapon_damage_by_character(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
-) -> Json<APIResponse<MissionResourceInfo>> {
+) -> Json<APIResponse<HashMap<String, MissionCharacterWeaponDamageInfo>>> {
     let mission_id = path.into_inner();
-    let mapping = app_state.mapping.lock().unwrap();
-
-    let entity_blacklist_set = mapping.entity_blacklist_set.clone();
-    let entity_combine = mapping.entity_combine.clone();
-    let weapon_combine = mapping.weapon_combine.clone();
-    let resource_game_id_to_name = mapping.resource_mapping.clone();
 
-    drop(mapping);
+    let (
+        entity_blacklist_set,
+        entity_combine,
+        weapon_combine,
+        weapon_game_id_to_name,
+        weapon_game_id_to_character_game_id,
+        character_game_id_to_name,
+    ) = {
+        let mapping = app_state.mapping.lock().unwrap();
+        (
+            mapping.entity_blacklist_set.clone(),
+            mapping.entity_combine.clone(),
+            mapping.weapon_combine.clone(),
+            mapping.weapon_mapping.clone(),
+            mapping.weapon_character.clone(),
+            mapping.character_mapping.clone(),
+        )
+    };
 
     let result = web::block(move || {
         let begin = Instant::now();
@@ -1062,30 +1190,25 @@ async fn get_mission_resource_info(
             }
         };
 
-        let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get player list: {}", e);
-                return Err(());
-            }
-        };
-
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
-
         debug!("data prepared in {:?}", begin.elapsed());
         let begin = Instant::now();
 
-        let result = generate_mission_resource(
+        let result = generate_mission_weapon_damage(
             &cached_mission_list,
-            &player_id_to_name,
-            &resource_game_id_to_name,
+            &weapon_game_id_to_character_game_id,
+            &weapon_game_id_to_name,
             mission_id,
-        );
+            DPSBase::default(),
+            0.0,
+        )
+        .map(|weapon_damage| {
+            generate_mission_character_weapon_damage(weapon_damage, &character_game_id_to_name)
+        });
 
-        debug!("mission resource info generated in {:?}", begin.elapsed());
+        debug!(
+            "mission weapon damage by character generated in {:?}",
+            begin.elapsed()
+        );
 
         Ok(result)
     })
@@ -1101,30 +1224,24 @@ async fn get_mission_resource_info(
     }
 }
 
-#[get("/{mission_id}/kpi")]
-async fn get_mission_kpi(
+#[get("/{mission_id}/resource")]
+async fn get_mission_resource_info(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     path: web::Path<i32>,
     redis_client: Data<redis::Client>,
-) -> Json<APIResponse<Vec<MissionKPIInfo>>> {
+) -> Json<APIResponse<MissionResourceInfo>> {
     let mission_id = path.into_inner();
     let mapping = app_state.mapping.lock().unwrap();
 
     let entity_blacklist_set = mapping.entity_blacklist_set.clone();
     let entity_combine = mapping.entity_combine.clone();
     let weapon_combine = mapping.weapon_combine.clone();
-    let scout_special_player_set = mapping.scout_special_player_set.clone();
+    let player_mapping = mapping.player_mapping.clone();
+    let resource_game_id_to_name = mapping.resource_mapping.clone();
 
     drop(mapping);
 
-    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
-        Some(x) => x,
-        None => {
-            return Json(APIResponse::config_required("kpi_config"));
-        }
-    };
-
     let result = web::block(move || {
         let begin = Instant::now();
 
@@ -1158,19 +1275,6 @@ async fn get_mission_kpi(
             }
         };
 
-        let mut found = false;
-
-        for mission in &cached_mission_list {
-            if mission.mission_info.id == mission_id {
-                found = true;
-                break;
-            }
-        }
-
-        if !found {
-            return Ok(None);
-        }
-
         let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
             Ok(x) => x,
             Err(e) => {
@@ -1179,78 +1283,485 @@ async fn get_mission_kpi(
             }
         };
 
-        let player_id_to_name = player_list
-            .into_iter()
-            .map(|player| (player.id, player.player_name))
-            .collect::<HashMap<_, _>>();
+        let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
 
-        let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
-            .select(mission_invalid::mission_id)
-            .load(&mut db_conn)
-        {
-            Ok(x) => x,
-            Err(e) => {
-                error!("cannot get invalid mission list: {}", e);
-                return Err(());
-            }
-        };
+        debug!("data prepared in {:?}", begin.elapsed());
+        let begin = Instant::now();
 
-        let character_list = match character::table
-            .select(Character::as_select())
-            .load(&mut db_conn)
-        {
-            Ok(x) => x,
+        let result = generate_mission_resource(
+            &cached_mission_list,
+            &player_id_to_name,
+            &resource_game_id_to_name,
+            mission_id,
+        );
+
+        debug!("mission resource info generated in {:?}", begin.elapsed());
+
+        Ok(result)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => match x {
+            Some(info) => Json(APIResponse::ok(info)),
+            None => Json(APIResponse::not_found()),
+        },
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+// 获取单个任务的玩家KPI信息，任务不存在时返回None
+fn load_mission_kpi(
+    app_state: &AppState,
+    db_pool: &ReadDbPool,
+    redis_client: &redis::Client,
+    kpi_config: &KPIConfig,
+    mission_id: i32,
+) -> Result<Option<Vec<MissionKPIInfo>>, ()> {
+    let mapping = app_state.mapping.lock().unwrap();
+
+    let entity_blacklist_set = mapping.entity_blacklist_set.clone();
+    let entity_combine = mapping.entity_combine.clone();
+    let weapon_combine = mapping.weapon_combine.clone();
+    let player_mapping = mapping.player_mapping.clone();
+    let scout_special_player_set = mapping.scout_special_player_set.clone();
+    let non_mineral_resource_set = mapping.non_mineral_resource_set.clone();
+
+    drop(mapping);
+
+    let begin = Instant::now();
+
+    let mut db_conn = match db_pool.get() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get db connection from pool: {}", e);
+            return Err(());
+        }
+    };
+
+    let mut redis_conn = match redis_client.get_connection() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get redis connection: {}", e);
+            return Err(());
+        }
+    };
+
+    let cached_mission_list = match MissionCachedInfo::get_cached_all(
+        &mut db_conn,
+        &mut redis_conn,
+        &entity_blacklist_set,
+        &entity_combine,
+        &weapon_combine,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot get cached mission list");
+            return Err(());
+        }
+    };
+
+    let mut found = false;
+
+    for mission in &cached_mission_list {
+        if mission.mission_info.id == mission_id {
+            found = true;
+            break;
+        }
+    }
+
+    if !found {
+        return Ok(None);
+    }
+
+    let player_list = match player::table.select(Player::as_select()).load(&mut db_conn) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get player list: {}", e);
+            return Err(());
+        }
+    };
+
+    let player_id_to_name = canonical_player_id_to_name(&player_list, &player_mapping);
+
+    let invalid_mission_id_list: Vec<i32> = match mission_invalid::table
+        .select(mission_invalid::mission_id)
+        .load(&mut db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get invalid mission list: {}", e);
+            return Err(());
+        }
+    };
+
+    let character_list = match character::table
+        .select(Character::as_select())
+        .load(&mut db_conn)
+    {
+        Ok(x) => x,
+        Err(e) => {
+            error!("cannot get character list: {}", e);
+            return Err(());
+        }
+    };
+
+    let character_id_to_game_id = character_list
+        .into_iter()
+        .map(|character| (character.id, character.character_game_id))
+        .collect::<HashMap<_, _>>();
+
+    let global_kpi_state = match CachedGlobalKPIState::get_cached(
+        &mut db_conn,
+        &mut redis_conn,
+        &entity_blacklist_set,
+        &entity_combine,
+        &weapon_combine,
+        &invalid_mission_id_list,
+        kpi_config,
+        &player_id_to_name,
+        &character_id_to_game_id,
+        &scout_special_player_set,
+        &non_mineral_resource_set,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot get global kpi state");
+            return Err(());
+        }
+    };
+
+    let mission_kpi_cached_info = match MissionKPICachedInfo::get_cached(
+        &mut db_conn,
+        &mut redis_conn,
+        &entity_blacklist_set,
+        &entity_combine,
+        &weapon_combine,
+        &character_id_to_game_id,
+        &player_id_to_name,
+        &scout_special_player_set,
+        &non_mineral_resource_set,
+        kpi_config,
+        mission_id,
+    ) {
+        Ok(x) => x,
+        Err(()) => {
+            error!("cannot get mission kpi cached info");
+            return Err(());
+        }
+    };
+
+    debug!("data prepared in {:?}", begin.elapsed());
+    let begin = Instant::now();
+
+    let result = generate_mission_kpi(
+        &mission_kpi_cached_info,
+        &player_id_to_name,
+        &global_kpi_state,
+        kpi_config,
+    );
+
+    debug!("mission kpi generated in {:?}", begin.elapsed());
+
+    Ok(Some(result))
+}
+
+#[get("/{mission_id}/kpi")]
+async fn get_mission_kpi(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    path: web::Path<i32>,
+    redis_client: Data<redis::Client>,
+) -> Json<APIResponse<Vec<MissionKPIInfo>>> {
+    let mission_id = path.into_inner();
+
+    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
+        Some(x) => x,
+        None => {
+            return Json(APIResponse::config_required("kpi_config"));
+        }
+    };
+
+    let result = web::block(move || {
+        load_mission_kpi(&app_state, &db_pool, &redis_client, &kpi_config, mission_id)
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(x) => match x {
+            Some(info) => Json(APIResponse::ok(info)),
+            None => Json(APIResponse::not_found()),
+        },
+        Err(()) => Json(APIResponse::internal_error()),
+    }
+}
+
+#[derive(Serialize)]
+struct MissionKPICsvRow<'a> {
+    player_name: &'a str,
+    component: &'a str,
+    source_value: f64,
+    weighted_value: f64,
+    raw_index: f64,
+    corrected_index: f64,
+    transformed_index: f64,
+    weight: f64,
+}
+
+fn mission_kpi_to_csv(mission_kpi_info: &[MissionKPIInfo]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for player_info in mission_kpi_info {
+        for component in &player_info.component {
+            writer.serialize(MissionKPICsvRow {
+                player_name: &player_info.player_name,
+                component: &component.name,
+                source_value: component.source_value,
+                weighted_value: component.weighted_value,
+                raw_index: component.raw_index,
+                corrected_index: component.corrected_index,
+                transformed_index: component.transformed_index,
+                weight: component.weight,
+            })?;
+        }
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+// 以CSV格式返回任务中每名玩家的各KPI组成部分，失败时返回JSON格式的APIResponse
+#[get("/{mission_id}/kpi.csv")]
+async fn get_mission_kpi_csv(
+    app_state: Data<AppState>,
+    db_pool: Data<ReadDbPool>,
+    path: web::Path<i32>,
+    redis_client: Data<redis::Client>,
+) -> HttpResponse {
+    let mission_id = path.into_inner();
+
+    let kpi_config = match app_state.kpi_config.lock().unwrap().clone() {
+        Some(x) => x,
+        None => {
+            return HttpResponse::Ok().json(APIResponse::<()>::config_required("kpi_config"));
+        }
+    };
+
+    let result = web::block(move || {
+        let mission_kpi_info =
+            match load_mission_kpi(&app_state, &db_pool, &redis_client, &kpi_config, mission_id)? {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+
+        match mission_kpi_to_csv(&mission_kpi_info) {
+            Ok(x) => Ok(Some(x)),
             Err(e) => {
-                error!("cannot get character list: {}", e);
-                return Err(());
+                error!("cannot serialize mission kpi csv: {}", e);
+                Err(())
             }
-        };
+        }
+    })
+    .await
+    .unwrap();
 
-        let character_id_to_game_id = character_list
-            .into_iter()
-            .map(|character| (character.id, character.character_game_id))
+    match result {
+        Ok(x) => match x {
+            Some(csv) => HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .body(csv),
+            None => HttpResponse::Ok().json(APIResponse::<()>::not_found()),
+        },
+        Err(()) => HttpResponse::Ok().json(APIResponse::<()>::internal_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::kpi::CorrectionFactorInfo;
+    use crate::cache::mission::PlayerRawKPIData;
+    use crate::kpi::tests::valid_kpi_config;
+    use crate::kpi::CharacterKPIType;
+
+    fn mission_kpi_info(player_name: &str, mission_kpi: f64) -> MissionKPIInfo {
+        MissionKPIInfo {
+            player_name: player_name.to_string(),
+            kpi_character_type: "driller".to_string(),
+            weighted_kill: 0.0,
+            weighted_damage: 0.0,
+            priority_damage: 0.0,
+            revive_num: 0.0,
+            death_num: 0.0,
+            friendly_fire: 0.0,
+            nitra: 0.0,
+            supply_count: 0.0,
+            weighted_resource: 0.0,
+            component: Vec::new(),
+            mission_kpi,
+        }
+    }
+
+    #[test]
+    fn mission_kpi_without_correction_factor_for_character() {
+        let raw_kpi_data = (0..9usize)
+            .map(|i| {
+                (
+                    KPIComponent::try_from(i).unwrap(),
+                    PlayerRawKPIData {
+                        source_value: 1.0,
+                        weighted_value: 1.0,
+                        mission_total_weighted_value: 2.0,
+                        raw_index: 0.5,
+                    },
+                )
+            })
             .collect::<HashMap<_, _>>();
 
-        let global_kpi_state = match CachedGlobalKPIState::get_cached(
-            &mut db_conn,
-            &mut redis_conn,
-            &entity_blacklist_set,
-            &entity_combine,
-            &weapon_combine,
-            &invalid_mission_id_list,
-            &kpi_config,
-            &player_id_to_name,
-            &character_id_to_game_id,
-            &scout_special_player_set,
-        ) {
-            Ok(x) => x,
-            Err(()) => {
-                error!("cannot get global kpi state");
-                return Err(());
-            }
+        let mission_kpi_cached_info = MissionKPICachedInfo {
+            mission_id: 1,
+            damage_map: HashMap::new(),
+            kill_map: HashMap::new(),
+            resource_map: HashMap::new(),
+            total_damage_map: HashMap::new(),
+            total_kill_map: HashMap::new(),
+            total_resource_map: HashMap::new(),
+            player_id_to_kpi_character: HashMap::from([
+                (1, CharacterKPIType::ScoutSpecial),
+                (2, CharacterKPIType::Driller),
+            ]),
+            raw_kpi_data: HashMap::from([(1, raw_kpi_data.clone()), (2, raw_kpi_data)]),
         };
 
-        let mission_kpi_cached_info = match MissionKPICachedInfo::get_cached(
-            &mut db_conn,
-            &mut redis_conn,
-            &entity_blacklist_set,
-            &entity_combine,
-            &weapon_combine,
-            &character_id_to_game_id,
+        // 全局状态中仅包含Driller
+        let global_kpi_state = CachedGlobalKPIState {
+            character_correction_factor: HashMap::from([(
+                CharacterKPIType::Driller,
+                CORRECTION_ITEMS
+                    .iter()
+                    .map(|&kpi_component| {
+                        (
+                            kpi_component,
+                            CorrectionFactorInfo {
+                                player_index: 1.0,
+                                value: 1.0,
+                                correction_factor: 2.0,
+                            },
+                        )
+                    })
+                    .collect(),
+            )]),
+            standard_correction_sum: CORRECTION_ITEMS.iter().map(|&x| (x, 1.0)).collect(),
+            transform_range: HashMap::new(),
+        };
+
+        let kpi_config = KPIConfig {
+            character_component_weight: [CharacterKPIType::ScoutSpecial, CharacterKPIType::Driller]
+                .into_iter()
+                .map(|character_type| {
+                    (
+                        character_type,
+                        (0..9usize)
+                            .map(|i| (KPIComponent::try_from(i).unwrap(), 1.0))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            transform_range: Vec::new(),
+            ..valid_kpi_config()
+        };
+
+        let player_id_to_name =
+            HashMap::from([(1, "player".to_string()), (2, "driller".to_string())]);
+
+        let result = generate_mission_kpi(
+            &mission_kpi_cached_info,
             &player_id_to_name,
-            &scout_special_player_set,
+            &global_kpi_state,
             &kpi_config,
-            mission_id,
-        ) {
-            Ok(x) => x,
-            Err(()) => {
-                error!("cannot get mission kpi cached info");
-                return Err(());
+        );
+
+        // 修正因子之和缺少ScoutSpecial，Driller玩家同样不修正
+        assert_eq!(result.len(), 2);
+        for mission_kpi_info in &result {
+            for component in &mission_kpi_info.component {
+                assert_eq!(component.corrected_index, component.raw_index);
             }
+        }
+    }
+
+    #[test]
+    fn corrected_index_is_clamped_per_component() {
+        let raw_kpi_data = (0..9usize)
+            .map(|i| {
+                let kpi_component = KPIComponent::try_from(i).unwrap();
+                (
+                    kpi_component,
+                    PlayerRawKPIData {
+                        source_value: 1.0,
+                        weighted_value: 1.0,
+                        mission_total_weighted_value: 2.0,
+                        raw_index: match kpi_component {
+                            KPIComponent::Death | KPIComponent::Supply => -0.5,
+                            // 超出[0, 1]的原始值
+                            KPIComponent::Nitra => -0.3,
+                            KPIComponent::Minerals => 1.2,
+                            _ => 0.8,
+                        },
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mission_kpi_cached_info = MissionKPICachedInfo {
+            mission_id: 1,
+            damage_map: HashMap::new(),
+            kill_map: HashMap::new(),
+            resource_map: HashMap::new(),
+            total_damage_map: HashMap::new(),
+            total_kill_map: HashMap::new(),
+            total_resource_map: HashMap::new(),
+            player_id_to_kpi_character: HashMap::from([(1, CharacterKPIType::Driller)]),
+            raw_kpi_data: HashMap::from([(1, raw_kpi_data)]),
         };
 
-        debug!("data prepared in {:?}", begin.elapsed());
-        let begin = Instant::now();
+        // 修正因子为2，修正后的正向指标超出1.0
+        let global_kpi_state = CachedGlobalKPIState {
+            character_correction_factor: HashMap::from([(
+                CharacterKPIType::Driller,
+                CORRECTION_ITEMS
+                    .iter()
+                    .map(|&kpi_component| {
+                        (
+                            kpi_component,
+                            CorrectionFactorInfo {
+                                player_index: 1.0,
+                                value: 1.0,
+                                correction_factor: 2.0,
+                            },
+                        )
+                    })
+                    .collect(),
+            )]),
+            standard_correction_sum: CORRECTION_ITEMS.iter().map(|&x| (x, 1.0)).collect(),
+            transform_range: HashMap::new(),
+        };
+
+        let kpi_config = KPIConfig {
+            character_component_weight: HashMap::from([(
+                CharacterKPIType::Driller,
+                (0..9usize)
+                    .map(|i| (KPIComponent::try_from(i).unwrap(), 1.0))
+                    .collect(),
+            )]),
+            transform_range: Vec::new(),
+            ..valid_kpi_config()
+        };
+
+        let player_id_to_name = HashMap::from([(1, "player".to_string())]);
 
         let result = generate_mission_kpi(
             &mission_kpi_cached_info,
@@ -1259,18 +1770,246 @@ async fn get_mission_kpi(
             &kpi_config,
         );
 
-        debug!("mission kpi generated in {:?}", begin.elapsed());
+        let corrected_index = |kpi_component: KPIComponent| {
+            result[0]
+                .component
+                .iter()
+                .find(|x| x.name == kpi_component.to_string_zh())
+                .unwrap()
+                .corrected_index
+        };
 
-        Ok(Some(result))
-    })
-    .await
-    .unwrap();
+        assert_eq!(corrected_index(KPIComponent::Damage), 1.0);
+        assert_eq!(corrected_index(KPIComponent::Kill), 1.0);
+        // 修正后按各指标的范围限制，而非保留超出范围的原始值
+        assert_eq!(corrected_index(KPIComponent::Nitra), 0.0);
+        assert_eq!(corrected_index(KPIComponent::Minerals), 1.0);
+        // 不在CORRECTION_ITEMS中的指标不修正
+        assert_eq!(corrected_index(KPIComponent::Death), -0.5);
+
+        // 负向指标不会被限制到[0, 1]
+        assert_eq!(KPIComponent::Death.clamp_index(-0.75), -0.75);
+        assert_eq!(KPIComponent::Death.clamp_index(-1.5), -1.0);
+        assert_eq!(KPIComponent::Damage.clamp_index(1.6), 1.0);
+        assert_eq!(KPIComponent::Damage.clamp_index(-0.1), 0.0);
+    }
 
-    match result {
-        Ok(x) => match x {
-            Some(info) => Json(APIResponse::ok(info)),
-            None => Json(APIResponse::not_found()),
-        },
-        Err(()) => Json(APIResponse::internal_error()),
+    #[test]
+    fn mission_kpi_order_is_stable_under_ties() {
+        let mut list = vec![
+            mission_kpi_info("c", 0.5),
+            mission_kpi_info("b", 0.8),
+            mission_kpi_info("a", 0.5),
+            mission_kpi_info("d", 0.8),
+        ];
+
+        sort_mission_kpi_info(&mut list);
+
+        let order = list
+            .iter()
+            .map(|item| item.player_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(order, vec!["b", "d", "a", "c"]);
+    }
+
+    #[test]
+    fn mission_kpi_csv_has_one_row_per_component() {
+        let mut player_info = mission_kpi_info("player, 1", 1.0);
+        for name in ["击杀", "伤害"] {
+            player_info.component.push(MissionKPIComponent {
+                name: name.to_string(),
+                source_value: 1.0,
+                weighted_value: 2.0,
+                mission_total_weighted_value: 4.0,
+                raw_index: 0.5,
+                corrected_index: 0.5,
+                transformed_index: 0.25,
+                weight: 1.0,
+            });
+        }
+
+        let csv = String::from_utf8(mission_kpi_to_csv(&[player_info]).unwrap()).unwrap();
+        let line_list = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            line_list[0],
+            "player_name,component,source_value,weighted_value,raw_index,corrected_index,transformed_index,weight"
+        );
+        assert_eq!(line_list.len(), 3);
+        assert_eq!(line_list[1], "\"player, 1\",击杀,1.0,2.0,0.5,0.5,0.25,1.0");
+    }
+
+    #[test]
+    fn mission_kpi_is_identical_across_runs() {
+        let character_type_list = [
+            CharacterKPIType::Driller,
+            CharacterKPIType::Engineer,
+            CharacterKPIType::Gunner,
+            CharacterKPIType::Scout,
+        ];
+
+        // 每次重新构造HashMap，使其迭代顺序不同
+        let run = || {
+            let raw_kpi_data = (1..=4i16)
+                .map(|player_id| {
+                    let component_data = (0..9usize)
+                        .map(|i| {
+                            (
+                                KPIComponent::try_from(i).unwrap(),
+                                PlayerRawKPIData {
+                                    source_value: 1.0,
+                                    weighted_value: 1.0,
+                                    mission_total_weighted_value: 3.0,
+                                    raw_index: 0.1 * player_id as f64 + 0.07 * i as f64,
+                                },
+                            )
+                        })
+                        .collect::<HashMap<_, _>>();
+                    (player_id, component_data)
+                })
+                .collect::<HashMap<_, _>>();
+
+            let mission_kpi_cached_info = MissionKPICachedInfo {
+                mission_id: 1,
+                damage_map: HashMap::new(),
+                kill_map: HashMap::new(),
+                resource_map: HashMap::new(),
+                total_damage_map: HashMap::new(),
+                total_kill_map: HashMap::new(),
+                total_resource_map: HashMap::new(),
+                player_id_to_kpi_character: (1..=4i16)
+                    .map(|player_id| (player_id, character_type_list[player_id as usize - 1]))
+                    .collect(),
+                raw_kpi_data,
+            };
+
+            let global_kpi_state = CachedGlobalKPIState {
+                character_correction_factor: character_type_list
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &character_type)| {
+                        (
+                            character_type,
+                            CORRECTION_ITEMS
+                                .iter()
+                                .map(|&kpi_component| {
+                                    (
+                                        kpi_component,
+                                        CorrectionFactorInfo {
+                                            player_index: 1.0,
+                                            value: 1.0,
+                                            correction_factor: 0.1 + 0.2 * i as f64,
+                                        },
+                                    )
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+                standard_correction_sum: CORRECTION_ITEMS.iter().map(|&x| (x, 1.3)).collect(),
+                transform_range: HashMap::new(),
+            };
+
+            let kpi_config = KPIConfig {
+                character_component_weight: character_type_list
+                    .iter()
+                    .map(|&character_type| {
+                        (
+                            character_type,
+                            (0..9usize)
+                                .map(|i| {
+                                    (KPIComponent::try_from(i).unwrap(), 0.1 + 0.01 * i as f64)
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+                transform_range: Vec::new(),
+                ..valid_kpi_config()
+            };
+
+            let player_id_to_name = (1..=4i16)
+                .map(|player_id| (player_id, format!("player{}", player_id)))
+                .collect::<HashMap<_, _>>();
+
+            let result = generate_mission_kpi(
+                &mission_kpi_cached_info,
+                &player_id_to_name,
+                &global_kpi_state,
+                &kpi_config,
+            );
+
+            serde_json::to_string(&result).unwrap()
+        };
+
+        let expected = run();
+        for _ in 0..32 {
+            assert_eq!(run(), expected);
+        }
+    }
+
+    #[test]
+    fn mission_kpi_component_is_ordered_by_id() {
+        let raw_kpi_data = (0..9usize)
+            .map(|i| {
+                (
+                    KPIComponent::try_from(i).unwrap(),
+                    PlayerRawKPIData {
+                        source_value: 1.0,
+                        weighted_value: 1.0,
+                        mission_total_weighted_value: 2.0,
+                        raw_index: 0.5,
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mission_kpi_cached_info = MissionKPICachedInfo {
+            mission_id: 1,
+            damage_map: HashMap::new(),
+            kill_map: HashMap::new(),
+            resource_map: HashMap::new(),
+            total_damage_map: HashMap::new(),
+            total_kill_map: HashMap::new(),
+            total_resource_map: HashMap::new(),
+            player_id_to_kpi_character: HashMap::from([(1, CharacterKPIType::Driller)]),
+            raw_kpi_data: HashMap::from([(1, raw_kpi_data)]),
+        };
+
+        let global_kpi_state = CachedGlobalKPIState {
+            character_correction_factor: HashMap::new(),
+            standard_correction_sum: HashMap::new(),
+            transform_range: HashMap::new(),
+        };
+
+        let kpi_config = KPIConfig {
+            character_component_weight: HashMap::from([(
+                CharacterKPIType::Driller,
+                (0..9usize)
+                    .map(|i| (KPIComponent::try_from(i).unwrap(), 1.0))
+                    .collect(),
+            )]),
+            transform_range: Vec::new(),
+            ..valid_kpi_config()
+        };
+
+        let result = generate_mission_kpi(
+            &mission_kpi_cached_info,
+            &HashMap::from([(1, "player".to_string())]),
+            &global_kpi_state,
+            &kpi_config,
+        );
+
+        let name_list = result[0]
+            .component
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+        let expected_name_list = (0..9usize)
+            .map(|i| KPIComponent::try_from(i).unwrap().to_string_zh())
+            .collect::<Vec<_>>();
+
+        assert_eq!(name_list, expected_name_list);
     }
 }
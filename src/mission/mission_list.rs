@@ -3,7 +3,7 @@ use crate::cache::mission::MissionCachedInfo;
 use crate::{
     db::models::{Mission, MissionInvalid, MissionType},
     db::schema::*,
-    APIResponse, AppState, DbPool,
+    real_hazard_to_display_label, APIResponse, AppState, ReadDbPool,
 };
 use actix_web::{
     get,
@@ -17,7 +17,7 @@ use std::sync::Arc;
 use std::time::Instant;
 
 #[get("/api_mission_list")]
-async fn get_api_mission_list(db_pool: Data<DbPool>) -> Json<APIResponse<Vec<APIMission>>> {
+async fn get_api_mission_list(db_pool: Data<ReadDbPool>) -> Json<APIResponse<Vec<APIMission>>> {
     let inner_pool = (*db_pool).clone();
 
     let mission_type_map = match web::block(|| load_mission_type_map(inner_pool))
@@ -46,7 +46,7 @@ async fn get_api_mission_list(db_pool: Data<DbPool>) -> Json<APIResponse<Vec<API
     Json(APIResponse::ok(result))
 }
 
-fn load_mission_list(db_pool: Arc<DbPool>) -> Result<Vec<Mission>, ()> {
+fn load_mission_list(db_pool: Arc<ReadDbPool>) -> Result<Vec<Mission>, ()> {
     use crate::db::schema::*;
     let mut conn = match db_pool.get() {
         Ok(conn) => conn,
@@ -65,7 +65,7 @@ fn load_mission_list(db_pool: Arc<DbPool>) -> Result<Vec<Mission>, ()> {
     }
 }
 
-fn load_mission_type_map(db_pool: Arc<DbPool>) -> Result<HashMap<i16, String>, ()> {
+fn load_mission_type_map(db_pool: Arc<ReadDbPool>) -> Result<HashMap<i16, String>, ()> {
     use crate::db::schema::*;
     let mut conn = match db_pool.get() {
         Ok(conn) => conn,
@@ -95,7 +95,7 @@ fn load_mission_type_map(db_pool: Arc<DbPool>) -> Result<HashMap<i16, String>, (
 #[get("/mission_list")]
 async fn get_mission_list(
     app_state: Data<AppState>,
-    db_pool: Data<DbPool>,
+    db_pool: Data<ReadDbPool>,
     redis_client: Data<redis::Client>,
 ) -> Json<APIResponse<MissionList>> {
     let mapping = app_state.mapping.lock().unwrap();
@@ -225,6 +225,7 @@ pub fn generate(
             mission_time: current_mission_info.mission_time,
             mission_type_id: mission_type_id.clone(),
             hazard_id: current_mission_info.hazard_id,
+            hazard_label: real_hazard_to_display_label(current_mission_info.hazard_id),
             mission_result: current_mission_info.result,
             reward_credit: current_mission_info.reward_credit,
             mission_invalid,
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::{damage::SupplyPack, db::models::Mission};
+pub mod delete;
 pub mod load;
 pub mod mission;
 pub mod mission_list;
@@ -39,7 +40,7 @@ impl APIMission {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionInfo {
     #[serde(rename = "missionId")]
     pub mission_id: i32,
@@ -51,6 +52,8 @@ pub struct MissionInfo {
     pub mission_type_id: String,
     #[serde(rename = "hazardId")]
     pub hazard_id: i16,
+    #[serde(rename = "hazardLabel")]
+    pub hazard_label: Option<String>,
     #[serde(rename = "missionResult")]
     pub mission_result: i16,
     #[serde(rename = "rewardCredit")]
@@ -61,7 +64,7 @@ pub struct MissionInfo {
     pub mission_invalid_reason: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionList {
     #[serde(rename = "missionInfo")]
     pub mission_info: Vec<MissionInfo>,
@@ -81,7 +84,7 @@ pub struct MissionGeneralInfo {
     pub mission_invalid_reason: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionGeneralPlayerInfo {
     #[serde(rename = "characterGameId")]
     pub character_game_id: String,
@@ -101,7 +104,7 @@ pub struct MissionGeneralPlayerInfo {
     pub player_escaped: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionGeneralData {
     #[serde(rename = "beginTimeStamp")]
     pub begin_timestamp: i64,
@@ -127,15 +130,18 @@ pub struct MissionGeneralData {
     pub total_nitra: f64,
     #[serde(rename = "totalSupplyCount")]
     pub total_supply_count: i16,
+    // player_name -> 不属于该玩家记录角色的武器weapon_game_id
+    #[serde(rename = "classMismatch")]
+    pub class_mismatch: HashMap<String, Vec<String>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct PlayerFriendlyFireInfo {
-    cause: HashMap<String, f64>,
-    take: HashMap<String, f64>,
+    pub cause: HashMap<String, f64>,
+    pub take: HashMap<String, f64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct PlayerDamageInfo {
     pub damage: HashMap<String, f64>,
     pub kill: HashMap<String, i32>,
@@ -144,7 +150,7 @@ pub struct PlayerDamageInfo {
     pub supply_count: i16,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionDamageInfo {
     pub info: HashMap<String, PlayerDamageInfo>,
     #[serde(rename = "entityMapping")]
@@ -160,6 +166,37 @@ pub struct MissionWeaponDamageInfo {
     pub character_game_id: String,
     #[serde(rename = "mappedName")]
     pub mapped_name: String,
+    // 不含友伤，分母由DPSBase决定
+    pub dps: f64,
+}
+
+#[derive(Serialize)]
+pub struct MissionCharacterWeaponDamageInfo {
+    // 该角色所有武器伤害之和，不含友伤
+    pub damage: f64,
+    #[serde(rename = "friendlyFire")]
+    pub friendly_fire: f64,
+    #[serde(rename = "mappedName")]
+    pub mapped_name: String,
+}
+
+// DPS的分母：武器活跃时间（第一次与最后一次造成伤害之差）或任务时间
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DPSBase {
+    #[default]
+    #[serde(rename = "span")]
+    Span,
+    #[serde(rename = "mission_time")]
+    MissionTime,
+}
+
+#[derive(Deserialize)]
+pub struct MissionWeaponDamageQuery {
+    #[serde(default)]
+    pub dps_base: DPSBase,
+    // 伤害（含友伤）低于该值的武器不计入
+    #[serde(default)]
+    pub min_damage: f64,
 }
 
 #[derive(Serialize)]
@@ -175,7 +212,7 @@ pub struct MissionResourceInfo {
     resource_mapping: HashMap<String, String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionKPIComponent {
     pub name: String,
     #[serde(rename = "sourceValue")]
@@ -193,7 +230,7 @@ pub struct MissionKPIComponent {
     pub weight: f64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MissionKPIInfo {
     #[serde(rename = "playerName")]
     pub player_name: String,
@@ -223,6 +260,7 @@ pub struct MissionKPIInfo {
 
 pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(load::load_mission);
+    cfg.service(delete::api_delete_mission);
     cfg.service(mission_list::get_api_mission_list);
     cfg.service(mission_list::get_mission_list);
 
@@ -230,7 +268,9 @@ pub fn scoped_config(cfg: &mut web::ServiceConfig) {
     cfg.service(mission::get_mission_general);
     cfg.service(mission::get_mission_damage);
     cfg.service(mission::get_mission_weapon_damage);
+    cfg.service(mission::get_mission_weapon_damage_by_character);
     cfg.service(mission::get_mission_resource_info);
     cfg.service(mission::get_player_character);
     cfg.service(mission::get_mission_kpi);
+    cfg.service(mission::get_mission_kpi_csv);
 }